@@ -1,6 +1,7 @@
 use std::fmt::Display;
 
 use serde::{Serialize, Deserialize};
+use sct_reader::position::{MagneticHeading, TrueHeading};
 
 use crate::{position::Position, util};
 
@@ -17,10 +18,10 @@ pub struct AircraftUpdate {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AircraftData {
     pub position: Position, //
-    pub heading_mag: f32, //
-    pub heading_true: f32, //
-    pub track_mag: f32, //
-    pub track_true: f32, //
+    pub heading_mag: MagneticHeading, //
+    pub heading_true: TrueHeading, //
+    pub track_mag: MagneticHeading, //
+    pub track_true: TrueHeading, //
     pub pitch: f32, //
     pub bank: f32, //
     pub indicated_airspeed: f32, //
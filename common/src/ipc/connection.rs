@@ -0,0 +1,225 @@
+//! Framed, version-checked connections over a socket.
+
+use std::{io::{self, ErrorKind, Read, Write}, marker::PhantomData, net::{TcpStream, ToSocketAddrs}, time::{Duration, Instant}};
+#[cfg(unix)]
+use std::{os::unix::net::UnixStream, path::Path};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::{frame::{self, Frame, Hello, HELLO_LEN}, IpcError};
+
+const READ_CHUNK_LEN: usize = 8 * 1024;
+
+/// A byte stream a [`Connection`] can run over.
+pub trait Transport: Read + Write {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+}
+impl Transport for TcpStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+}
+#[cfg(unix)]
+impl Transport for UnixStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        UnixStream::set_read_timeout(self, timeout)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Heartbeat {
+    /// How long this end can go without sending anything before it sends a heartbeat.
+    pub interval: Duration,
+    /// How long this end waits to hear anything from the other before giving up on it. Should be
+    /// a few times the other end's interval.
+    pub timeout: Duration,
+}
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Heartbeat { interval: Duration::from_secs(1), timeout: Duration::from_secs(5) }
+    }
+}
+
+/// One end of a connection, sending `Out` packets and receiving `In` ones. See
+/// [`RadarConnection`](super::RadarConnection) and [`UiConnection`](super::UiConnection).
+///
+/// Either end can be the one that listens; the handshake is the same both ways. Heartbeats are
+/// sent while waiting in [`Connection::recv`] or [`Connection::recv_timeout`]. An end that can go
+/// a while without doing either should call [`Connection::keep_alive`] regularly.
+pub struct Connection<S: Transport, Out, In> {
+    stream: S,
+    heartbeat: Heartbeat,
+    /// Bytes read that don't yet make up a whole frame.
+    buffer: Vec<u8>,
+    last_sent: Instant,
+    last_received: Instant,
+    packets: PhantomData<fn(Out) -> In>,
+}
+impl<S: Transport, Out: Serialize, In: DeserializeOwned> Connection<S, Out, In> {
+    /// Swaps [`Hello`]s over a newly opened stream, failing if the other end speaks a different
+    /// protocol version.
+    pub fn new(mut stream: S, heartbeat: Heartbeat) -> Result<Self, IpcError> {
+        stream.write_all(&Hello::new().encode())?;
+        stream.flush()?;
+        stream.set_read_timeout(Some(heartbeat.timeout))?;
+        let mut theirs = [0; HELLO_LEN];
+        stream.read_exact(&mut theirs).map_err(|e| match e.kind() {
+            ErrorKind::WouldBlock | ErrorKind::TimedOut => IpcError::PeerTimedOut,
+            ErrorKind::UnexpectedEof => IpcError::Closed,
+            _ => IpcError::Io(e),
+        })?;
+        Hello::decode(&theirs)?.check()?;
+        let now = Instant::now();
+        Ok(Connection {
+            stream,
+            heartbeat,
+            buffer: vec![],
+            last_sent: now,
+            last_received: now,
+            packets: PhantomData,
+        })
+    }
+
+    pub fn send(&mut self, packet: Out) -> Result<(), IpcError> {
+        self.write(&Frame::Packet(packet))
+    }
+
+    /// Waits as long as it takes for the next packet.
+    pub fn recv(&mut self) -> Result<In, IpcError> {
+        loop {
+            if let Some(packet) = self.recv_timeout(self.heartbeat.interval)? {
+                return Ok(packet);
+            }
+        }
+    }
+
+    /// Waits up to about `wait` for the next packet.
+    pub fn recv_timeout(&mut self, wait: Duration) -> Result<Option<In>, IpcError> {
+        let deadline = Instant::now() + wait;
+        loop {
+            if let Some(packet) = self.next_buffered()? {
+                return Ok(Some(packet));
+            }
+            self.keep_alive()?;
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            self.stream.set_read_timeout(Some((deadline - now).min(self.heartbeat.interval)))?;
+            let mut chunk = [0; READ_CHUNK_LEN];
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err(IpcError::Closed),
+                Ok(len) => {
+                    self.buffer.extend_from_slice(&chunk[..len]);
+                    self.last_received = Instant::now();
+                }
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    if self.last_received.elapsed() > self.heartbeat.timeout {
+                        return Err(IpcError::PeerTimedOut);
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Sends a heartbeat if nothing has been sent for the heartbeat interval.
+    pub fn keep_alive(&mut self) -> Result<(), IpcError> {
+        if self.last_sent.elapsed() >= self.heartbeat.interval {
+            self.write(&Frame::Heartbeat)?;
+        }
+        Ok(())
+    }
+
+    /// Tells the other end this one is going, so it sees [`IpcError::Closed`] rather than a
+    /// timeout or a broken pipe.
+    pub fn close(mut self) -> Result<(), IpcError> {
+        self.write(&Frame::Goodbye)
+    }
+
+    fn next_buffered(&mut self) -> Result<Option<In>, IpcError> {
+        while let Some((frame, len)) = frame::decode::<In>(&self.buffer)? {
+            self.buffer.drain(..len);
+            match frame {
+                Frame::Heartbeat => {}
+                Frame::Packet(packet) => return Ok(Some(packet)),
+                Frame::Goodbye => return Err(IpcError::Closed),
+            }
+        }
+        Ok(None)
+    }
+
+    fn write(&mut self, frame: &Frame<Out>) -> Result<(), IpcError> {
+        self.stream.write_all(&frame::encode(frame)?)?;
+        self.stream.flush()?;
+        self.last_sent = Instant::now();
+        Ok(())
+    }
+}
+impl<Out: Serialize, In: DeserializeOwned> Connection<TcpStream, Out, In> {
+    pub fn connect_tcp(address: impl ToSocketAddrs) -> Result<Self, IpcError> {
+        let stream = TcpStream::connect(address)?;
+        stream.set_nodelay(true)?;
+        Self::new(stream, Heartbeat::default())
+    }
+}
+#[cfg(unix)]
+impl<Out: Serialize, In: DeserializeOwned> Connection<UnixStream, Out, In> {
+    pub fn connect_unix(path: impl AsRef<Path>) -> Result<Self, IpcError> {
+        Self::new(UnixStream::connect(path)?, Heartbeat::default())
+    }
+}
+
+#[test]
+fn test_connection() {
+    use std::{net::TcpListener, thread};
+
+    use super::{radar_to_ui, ui_to_radar, RadarConnection, UiConnection};
+    use crate::api_requests::{text_command::TextCommandRequest, ApiRequestType};
+
+    let heartbeat = Heartbeat { interval: Duration::from_millis(20), timeout: Duration::from_millis(500) };
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let ui = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut ui = UiConnection::new(stream, heartbeat).unwrap();
+        let radar_to_ui::PacketType::ApiRequest(ApiRequestType::TextCommand(command)) = ui.recv().unwrap();
+        // Long enough for several heartbeats each way, none of which show up as packets.
+        assert!(ui.recv_timeout(Duration::from_millis(150)).unwrap().is_none());
+        ui.send(ui_to_radar::PacketType::LogMessage(format!("{} {}", command.callsign, command.command))).unwrap();
+        assert!(matches!(ui.recv(), Err(IpcError::Closed)));
+
+        // Each end of a mismatched connection turns the other away.
+        let (mut stream, _) = listener.accept().unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        assert!(matches!(UiConnection::new(stream, heartbeat), Err(IpcError::VersionMismatch { ours: 1, theirs: 99 })));
+    });
+
+    let mut radar = RadarConnection::new(TcpStream::connect(address).unwrap(), heartbeat).unwrap();
+    radar.send(radar_to_ui::PacketType::ApiRequest(ApiRequestType::TextCommand(TextCommandRequest {
+        callsign: "BAW123".to_owned(),
+        command: "FH".to_owned(),
+        args: vec!["090".to_owned()],
+    }))).unwrap();
+    let ui_to_radar::PacketType::LogMessage(message) = radar.recv().unwrap() else {
+        panic!("Expected a log message");
+    };
+    assert_eq!(message, "BAW123 FH");
+    radar.close().unwrap();
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream.write_all(&[b'R', b'D', b'R', b'V', 99, 0, 0, 0]).unwrap();
+    assert!(matches!(RadarConnection::new(stream, heartbeat), Err(IpcError::NotAPeer)));
+    ui.join().unwrap();
+
+    // A peer that has gone quiet is noticed.
+    #[cfg(unix)]
+    {
+        let (radar_end, mut ui_end) = UnixStream::pair().unwrap();
+        ui_end.write_all(&Hello::new().encode()).unwrap();
+        let mut radar = RadarConnection::new(radar_end, heartbeat).unwrap();
+        assert!(matches!(radar.recv(), Err(IpcError::PeerTimedOut)));
+    }
+}
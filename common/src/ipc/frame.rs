@@ -0,0 +1,151 @@
+//! The wire format, independent of any transport.
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{IpcError, PROTOCOL_VERSION};
+
+/// Opens every [`Hello`], so a stray connection from something else is caught straight away.
+pub const MAGIC: [u8; 4] = *b"RDRV";
+pub const HELLO_LEN: usize = 8;
+/// Frames are far smaller than this in practice. A longer length means the stream is out of step.
+pub const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+const LENGTH_PREFIX_LEN: usize = 4;
+
+/// The first thing each end sends. Its layout is the same for every protocol version, so ends
+/// speaking different versions can still tell each other so: [`MAGIC`], then the version as a
+/// little-endian `u16`, then two reserved zero bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hello {
+    pub version: u16,
+}
+impl Hello {
+    pub fn new() -> Hello {
+        Hello { version: PROTOCOL_VERSION }
+    }
+    pub fn encode(&self) -> [u8; HELLO_LEN] {
+        let mut bytes = [0; HELLO_LEN];
+        bytes[..4].copy_from_slice(&MAGIC);
+        bytes[4..6].copy_from_slice(&self.version.to_le_bytes());
+        bytes
+    }
+    pub fn decode(bytes: &[u8; HELLO_LEN]) -> Result<Hello, IpcError> {
+        if bytes[..4] != MAGIC {
+            return Err(IpcError::NotAPeer);
+        }
+        Ok(Hello { version: u16::from_le_bytes([bytes[4], bytes[5]]) })
+    }
+    /// Whether the other end's greeting means the two can talk.
+    pub fn check(&self) -> Result<(), IpcError> {
+        if self.version == PROTOCOL_VERSION {
+            Ok(())
+        } else {
+            Err(IpcError::VersionMismatch { ours: PROTOCOL_VERSION, theirs: self.version })
+        }
+    }
+}
+impl Default for Hello {
+    fn default() -> Self {
+        Hello::new()
+    }
+}
+
+/// Everything sent after the [`Hello`]. `P` is the sending end's `PacketType`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Frame<P> {
+    /// Sent when there's been nothing else to send for a while, so the other end knows this one is
+    /// still there.
+    Heartbeat,
+    Packet(P),
+    /// The sender is about to close the connection.
+    Goodbye,
+}
+
+/// The frame with its length prefix, ready to write.
+pub fn encode<P: Serialize>(frame: &Frame<P>) -> Result<Vec<u8>, IpcError> {
+    let payload = bincode::serialize(frame)?;
+    if payload.len() > MAX_FRAME_LEN {
+        return Err(IpcError::FrameTooLarge(payload.len()));
+    }
+    let mut bytes = Vec::with_capacity(LENGTH_PREFIX_LEN + payload.len());
+    bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&payload);
+    Ok(bytes)
+}
+
+/// The first frame in `bytes` and how many bytes it took up, or `None` if it hasn't all arrived
+/// yet.
+pub fn decode<P: DeserializeOwned>(bytes: &[u8]) -> Result<Option<(Frame<P>, usize)>, IpcError> {
+    let Some(prefix) = bytes.get(..LENGTH_PREFIX_LEN) else {
+        return Ok(None);
+    };
+    let len = u32::from_le_bytes(prefix.try_into().unwrap()) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(IpcError::FrameTooLarge(len));
+    }
+    let Some(payload) = bytes.get(LENGTH_PREFIX_LEN..LENGTH_PREFIX_LEN + len) else {
+        return Ok(None);
+    };
+    Ok(Some((bincode::deserialize(payload)?, LENGTH_PREFIX_LEN + len)))
+}
+
+/// Pins the bytes on the wire, so a change that would stop older builds understanding newer ones
+/// (or the other way round) fails here rather than in the field. If one of these has to change,
+/// bump [`PROTOCOL_VERSION`] along with it.
+#[test]
+fn test_wire_compatibility() {
+    use super::{radar_to_ui, ui_to_radar};
+    use crate::api_requests::{text_command::TextCommandRequest, ApiRequestType};
+
+    assert_eq!(PROTOCOL_VERSION, 1);
+    assert_eq!(Hello::new().encode(), [b'R', b'D', b'R', b'V', 1, 0, 0, 0]);
+
+    assert_eq!(encode::<ui_to_radar::PacketType>(&Frame::Heartbeat).unwrap(), [4, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(encode::<ui_to_radar::PacketType>(&Frame::Goodbye).unwrap(), [4, 0, 0, 0, 2, 0, 0, 0]);
+    let log = Frame::Packet(ui_to_radar::PacketType::LogMessage("hi".to_owned()));
+    let log_bytes = [18, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, b'h', b'i'];
+    assert_eq!(encode(&log).unwrap(), log_bytes);
+
+    let command_bytes = [
+        &[55, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0][..],
+        &[6, 0, 0, 0, 0, 0, 0, 0],
+        b"BAW123",
+        &[2, 0, 0, 0, 0, 0, 0, 0],
+        b"FH",
+        &[1, 0, 0, 0, 0, 0, 0, 0],
+        &[3, 0, 0, 0, 0, 0, 0, 0],
+        b"090",
+    ]
+    .concat();
+    let (frame, used) = decode::<radar_to_ui::PacketType>(&command_bytes).unwrap().unwrap();
+    assert_eq!(used, command_bytes.len());
+    let Frame::Packet(radar_to_ui::PacketType::ApiRequest(ApiRequestType::TextCommand(TextCommandRequest { callsign, command, args }))) = frame else {
+        panic!("Expected a text command, got {:?}", frame);
+    };
+    assert_eq!((callsign.as_str(), command.as_str(), args), ("BAW123", "FH", vec!["090".to_owned()]));
+}
+
+#[test]
+fn test_framing() {
+    use super::ui_to_radar::PacketType;
+
+    let mut stream = encode(&Frame::Packet(PacketType::LogMessage("first".to_owned()))).unwrap();
+    stream.extend(encode::<PacketType>(&Frame::Heartbeat).unwrap());
+
+    // Nothing comes out until the whole of a frame has arrived.
+    for end in 0..stream.len() - 8 {
+        assert!(decode::<PacketType>(&stream[..end]).unwrap().is_none());
+    }
+    let (first, used) = decode::<PacketType>(&stream).unwrap().unwrap();
+    assert!(matches!(first, Frame::Packet(PacketType::LogMessage(message)) if message == "first"));
+    let (second, rest) = decode::<PacketType>(&stream[used..]).unwrap().unwrap();
+    assert!(matches!(second, Frame::Heartbeat));
+    assert_eq!(used + rest, stream.len());
+
+    assert!(matches!(decode::<PacketType>(&[0xFF, 0xFF, 0xFF, 0xFF]), Err(IpcError::FrameTooLarge(_))));
+    assert!(matches!(decode::<PacketType>(&[4, 0, 0, 0, 9, 0, 0, 0]), Err(IpcError::Encoding(_))));
+
+    assert!(matches!(Hello::decode(b"HTTP/1.1"), Err(IpcError::NotAPeer)));
+    let future = Hello::decode(&[b'R', b'D', b'R', b'V', 2, 0, 0, 0]).unwrap();
+    assert!(matches!(future.check(), Err(IpcError::VersionMismatch { ours: 1, theirs: 2 })));
+    assert!(Hello::new().check().is_ok());
+}
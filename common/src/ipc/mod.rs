@@ -1,2 +1,63 @@
+//! Messages between the radar and a control UI running as separate processes.
+//!
+//! Each end opens with a fixed-size [`Hello`](frame::Hello) giving its protocol version, and
+//! either end hangs up if they differ. After that everything is a [`Frame`](frame::Frame): a
+//! little-endian `u32` length followed by that many bytes of bincode. [`Connection`] does the
+//! framing, handshake and heartbeats over a TCP or Unix socket.
+
+use std::{fmt::Display, io};
+
+use self::connection::Connection;
+
+pub mod connection;
+pub mod frame;
 pub mod radar_to_ui;
-pub mod ui_to_radar;
\ No newline at end of file
+pub mod ui_to_radar;
+
+/// Bump whenever [`frame::Frame`], either `PacketType` or anything inside them changes shape, as
+/// bincode has no way to skip what it doesn't recognise.
+pub const PROTOCOL_VERSION: u16 = 1;
+
+/// The radar's end: sends [`radar_to_ui::PacketType`] and receives [`ui_to_radar::PacketType`].
+pub type RadarConnection<S> = Connection<S, radar_to_ui::PacketType, ui_to_radar::PacketType>;
+/// The UI's end: sends [`ui_to_radar::PacketType`] and receives [`radar_to_ui::PacketType`].
+pub type UiConnection<S> = Connection<S, ui_to_radar::PacketType, radar_to_ui::PacketType>;
+
+#[derive(Debug)]
+pub enum IpcError {
+    Io(io::Error),
+    Encoding(bincode::Error),
+    /// The other end didn't open with a [`Hello`](frame::Hello), so isn't speaking this protocol.
+    NotAPeer,
+    VersionMismatch { ours: u16, theirs: u16 },
+    /// A frame longer than [`frame::MAX_FRAME_LEN`], which is taken to mean the stream is corrupt.
+    FrameTooLarge(usize),
+    /// Nothing, not even a heartbeat, has been heard from the other end for too long.
+    PeerTimedOut,
+    /// The other end said goodbye or closed the connection.
+    Closed,
+}
+impl Display for IpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IpcError::Io(e) => write!(f, "{}", e),
+            IpcError::Encoding(e) => write!(f, "Unable to encode or decode frame: {}", e),
+            IpcError::NotAPeer => write!(f, "The other end is not speaking the radar protocol"),
+            IpcError::VersionMismatch { ours, theirs } => write!(f, "Protocol version mismatch: this end speaks version {} but the other end speaks version {}", ours, theirs),
+            IpcError::FrameTooLarge(len) => write!(f, "Frame of {} bytes is too large", len),
+            IpcError::PeerTimedOut => write!(f, "Nothing heard from the other end"),
+            IpcError::Closed => write!(f, "Connection closed"),
+        }
+    }
+}
+impl std::error::Error for IpcError {}
+impl From<io::Error> for IpcError {
+    fn from(value: io::Error) -> Self {
+        IpcError::Io(value)
+    }
+}
+impl From<bincode::Error> for IpcError {
+    fn from(value: bincode::Error) -> Self {
+        IpcError::Encoding(value)
+    }
+}
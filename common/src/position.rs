@@ -1,3 +1,4 @@
+use sct_reader::position::TrueHeading;
 use serde::{Serialize, Deserialize};
 
 
@@ -33,10 +34,10 @@ impl Position {
         Position { lat, lon, alt }
     }
 
-    pub fn get_point_at_dist_and_brg(&self, distance_m: f32, bearing: f32) -> Position {
+    pub fn get_point_at_dist_and_brg(&self, distance_m: f32, bearing: TrueHeading) -> Position {
         let origin_lat = self.lat.to_radians();
         let origin_lon = self.lon.to_radians();
-        let a = bearing.to_radians();
+        let a = bearing.value().to_radians();
         let lat = (origin_lat.sin() * (distance_m / EARTH_RADIUS_M).cos() + origin_lat.cos() * (distance_m / EARTH_RADIUS_M).sin() * a.cos()).asin();
 
         let lon = origin_lon + (a.sin() * (distance_m / EARTH_RADIUS_M).sin() * origin_lat.cos()).atan2((distance_m / EARTH_RADIUS_M).cos() - origin_lat.sin() * lat.sin());
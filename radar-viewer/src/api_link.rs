@@ -4,6 +4,7 @@ use common::{aircraft_data::{Autopilot, fms_graphics::{FmsGraphic, FmsLine, FmsA
 use serde::{Deserialize, Serialize};
 use common::{ipc::{radar_to_ui, ui_to_radar}, aircraft_data::AircraftUpdate};
 use log::{info, error};
+use sct_reader::position::Heading;
 use ureq::serde_json;
 
 const API_POLL_INTERVAL: Duration = Duration::from_millis(100);
@@ -211,10 +212,10 @@ impl From<SimAircraft> for common::aircraft_data::AircraftUpdate {
             callsign,
             data: common::aircraft_data::AircraftData {
                 position,
-                heading_mag: heading_mag.degrees,
-                heading_true: heading_true.degrees,
-                track_mag: track_mag.degrees,
-                track_true: track_true.degrees,
+                heading_mag: Heading::wrapping(heading_mag.degrees),
+                heading_true: Heading::wrapping(heading_true.degrees),
+                track_mag: Heading::wrapping(track_mag.degrees),
+                track_true: Heading::wrapping(track_true.degrees),
                 pitch: pitch.degrees,
                 bank: bank.degrees,
                 indicated_airspeed: indicated_airspeed.knots,
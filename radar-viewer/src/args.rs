@@ -30,6 +30,10 @@ pub struct Args {
     #[arg(short, value_name = "ASR_FILE_PATH")]
     pub asr_file_path: PathBuf,
 
+    /// Paths to extra video map overlays (.geojson or .kml), which are shown on top of the sector
+    #[arg(short = 'o', value_name = "OVERLAY_FILE_PATH")]
+    pub overlay_file_paths: Vec<PathBuf>,
+
     /// Centre lat
     #[arg(short = 'y', value_name = "CENTRE_LAT")]
     pub centre_lat: f32,
@@ -2,6 +2,7 @@ use std::{ops::DerefMut, sync::mpsc::Receiver};
 
 use common::api_requests::text_command::TextCommandRequest;
 use macroquad::{texture::Image, color::{GREEN, Color, RED}, ui::{Skin, widgets::{InputText, Editbox}, hash, root_ui, InputHandler, self, Id}, math::Vec2, window::{self, screen_width}, shapes::draw_rectangle, text::draw_text, input::is_key_pressed, miniquad::KeyCode};
+use sct_reader::{coordinate::CoordinateFormat, position::{Position, Valid}};
 
 use crate::{logger::Logger, aircraft::{AircraftManager, Aircraft}};
 
@@ -112,11 +113,39 @@ fn try_parse_text_command(callsign: &str, txt: &str, aircraft_manager: &Aircraft
     let mut split = txt.split_whitespace();
 
     let command = split.next()?.to_string();
-    let args = split.map(|arg| arg.to_owned()).collect::<Vec<_>>();
+    let args = normalise_coordinates(split.map(|arg| arg.to_owned()).collect::<Vec<_>>());
     let request = TextCommandRequest {
         callsign: callsign.to_owned(),
         command,
         args
     };
     Some(request)
-}
\ No newline at end of file
+}
+
+/// Rewrites any coordinates in `args` as a EuroScope latitude and longitude, so commands can be given positions in whichever notation was pasted in.
+/// Plain decimal numbers are left alone, as they're far more likely to be headings, levels or speeds.
+fn normalise_coordinates(args: Vec<String>) -> Vec<String> {
+    let mut normalised = vec![];
+    let mut i = 0;
+    while i < args.len() {
+        let single = parse_coordinate(&args[i]).map(|position| (position, 1));
+        let pair = || args.get(i + 1).and_then(|next| parse_coordinate(&format!("{} {}", args[i], next))).map(|position| (position, 2));
+        if let Some((position, used)) = single.or_else(pair) {
+            let (lat, lon) = position.to_es();
+            normalised.push(lat);
+            normalised.push(lon);
+            i += used;
+        } else {
+            normalised.push(args[i].clone());
+            i += 1;
+        }
+    }
+    normalised
+}
+
+fn parse_coordinate(text: &str) -> Option<Position<Valid>> {
+    match CoordinateFormat::detect(text)? {
+        CoordinateFormat::Decimal => None,
+        format => format.parse(text).ok()
+    }
+}
@@ -1,9 +1,10 @@
 use std::{thread::{JoinHandle, self}, sync::{Arc, atomic::{AtomicBool, Ordering}}, path::PathBuf, io::BufReader, fs::File};
 
 use common::{radar_profile::{colours::RadarColours, RadarProfile, LatLon}, position::Position};
-use sct_reader::reader::SctReader;
+use log::warn;
+use sct_reader::{reader::SctReader, overlay::Overlay};
 
-use crate::sector::Sector;
+use crate::sector::{cache, Sector};
 
 use super::display::RadarDisplay;
 
@@ -11,6 +12,17 @@ use super::display::RadarDisplay;
 
 
 
+/// The files and starting view for one radar display.
+pub struct SectorLoadRequest {
+    pub sct: PathBuf,
+    pub sym: PathBuf,
+    pub asr: PathBuf,
+    pub overlays: Vec<PathBuf>,
+    pub centre_lat: f32,
+    pub centre_lon: f32,
+    pub zoom: f32,
+}
+
 pub struct RadarDisplayLoader {
     thread: Option<JoinHandle<Option<Vec<PartiallyLoadedSector>>>>,
     ready: Arc<AtomicBool>,
@@ -23,7 +35,7 @@ impl RadarDisplayLoader {
         }
     }
 
-    pub fn start_load(&mut self, sct: PathBuf, sym: PathBuf, asr: PathBuf, centre_lat: f32, centre_lon: f32, zoom: f32) {
+    pub fn start_load(&mut self, request: SectorLoadRequest) {
         if !self.ready.load(Ordering::Relaxed) || self.thread.is_some() {
             println!("Not starting to load");
             return;
@@ -32,7 +44,7 @@ impl RadarDisplayLoader {
         let ready = Arc::clone(&self.ready);
         self.thread = Some(
             thread::spawn(move || {
-                let res = load_sectors(vec![(sct, sym, asr, centre_lat, centre_lon, zoom)]);
+                let res = load_sectors(vec![request]);
                 ready.store(true, Ordering::Relaxed);
                 res
             })
@@ -68,23 +80,45 @@ impl PartiallyLoadedSector {
 
 //(Sector, RadarColours, f32)
 
-fn load_sectors(paths: Vec<(PathBuf, PathBuf, PathBuf, f32, f32, f32)>) -> Option<Vec<PartiallyLoadedSector>> {
-    let mut partially_loaded_sectors: Vec<PartiallyLoadedSector> = Vec::with_capacity(paths.len());
-        for (sct, sym, asr, centre_lat, centre_lon, zoom) in &paths {
+fn load_sectors(requests: Vec<SectorLoadRequest>) -> Option<Vec<PartiallyLoadedSector>> {
+    let mut partially_loaded_sectors: Vec<PartiallyLoadedSector> = Vec::with_capacity(requests.len());
+        for SectorLoadRequest { sct, sym, asr, overlays: overlay_paths, centre_lat, centre_lon, zoom } in &requests {
             // Parse the toml file
             // let file = std::fs::read_to_string(path).ok()?;
             // let profile = toml::from_str::<RadarProfile>(&file).ok()?;
             
-            // Attempt to load the sector file
-            let mut sector: Sector = SctReader::new(BufReader::new(File::open(sct).ok()?))
-            .try_read()
-            .ok()?.into();
+            // Read any overlays, skipping those that can't be read
+            let overlays = overlay_paths.iter().filter_map(|path| {
+                Overlay::read_file(path).map_err(|e| warn!("Unable to load overlay {}: {}", path.display(), e)).ok()
+            }).collect::<Vec<_>>();
+
+            // Use the cached sector if the sector file and overlays haven't changed, otherwise
+            // load the sector file and add the overlays to it
+            let sources = std::iter::once(sct.clone()).chain(overlay_paths.iter().cloned()).collect::<Vec<_>>();
+            let mut sector = match cache::load(&sources) {
+                Some(sector) => sector,
+                None => {
+                    let mut sct_sector = SctReader::new(BufReader::new(File::open(sct).ok()?))
+                    .try_read()
+                    .ok()?;
+                    for overlay in &overlays {
+                        sct_sector.add_overlay(overlay);
+                    }
+                    let sector: Sector = sct_sector.into();
+                    cache::store(&sources, &sector);
+                    sector
+                }
+            };
 
             sector.default_centre_pt = Position::new(*centre_lat, *centre_lon);
             
             // Read and apply the filters
             let filters = common::radar_profile::filters::RadarFilters::read_from_asr_file(asr).ok()?;
             sector.load_filters_from_profile(&filters);
+            // Overlays aren't in the ASR file, so are shown regardless
+            for overlay in &overlays {
+                sector.show_overlay(overlay);
+            }
             
             // Read the colours from symbology file
             let colours = common::radar_profile::colours::RadarColours::read_from_symbology_file(sym).ok()?;
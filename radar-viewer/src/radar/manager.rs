@@ -5,7 +5,7 @@ use macroquad::{color::{Color, BLACK}, input::is_key_pressed, miniquad::KeyCode,
 
 use crate::{aircraft::AircraftManager, args::Args};
 
-use super::{display::RadarDisplay, loader::RadarDisplayLoader};
+use super::{display::RadarDisplay, loader::{RadarDisplayLoader, SectorLoadRequest}};
 
 
 
@@ -17,7 +17,15 @@ pub struct RadarManager {
 impl RadarManager {
     pub fn new(args: &Args) -> RadarManager {
         let mut loader = RadarDisplayLoader::new();
-        loader.start_load(args.sector_file_path.clone(), args.symbology_file_path.clone(), args.asr_file_path.clone(), args.centre_lat, args.centre_lon, args.zoom_level_n_mi);
+        loader.start_load(SectorLoadRequest {
+            sct: args.sector_file_path.clone(),
+            sym: args.symbology_file_path.clone(),
+            asr: args.asr_file_path.clone(),
+            overlays: args.overlay_file_paths.clone(),
+            centre_lat: args.centre_lat,
+            centre_lon: args.centre_lon,
+            zoom: args.zoom_level_n_mi,
+        });
         Self { loader, radar_displays: vec![], active_display: 0 }
     }
     fn store(&mut self, radar_displays: Vec<RadarDisplay>) {
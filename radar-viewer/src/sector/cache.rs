@@ -0,0 +1,217 @@
+//! On-disk cache of loaded sectors, so that repeat launches skip parsing the sector file and
+//! triangulating its regions.
+//!
+//! Each cache file holds a [`CacheKey`] followed by the [`Sector`], both written with bincode. The
+//! key records every source file's path, size, modification time and content hash, so any change
+//! to the sector file or its overlays misses the cache.
+
+use std::{fs::{self, File}, io::{BufReader, BufWriter}, path::{Path, PathBuf}, time::SystemTime};
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::util;
+
+use super::Sector;
+
+/// Bump whenever [`Sector`] or anything inside it changes shape, or it's built differently from
+/// the same files, so old caches are ignored.
+const CACHE_VERSION: u32 = 2;
+
+const CACHE_DIR_NAME: &str = "sector-cache";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CacheKey {
+    version: u32,
+    sources: Vec<SourceStamp>,
+}
+impl CacheKey {
+    fn new(sources: &[PathBuf]) -> Option<CacheKey> {
+        let sources = sources.iter().map(|path| SourceStamp::new(path)).collect::<Option<Vec<_>>>()?;
+        Some(CacheKey { version: CACHE_VERSION, sources })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SourceStamp {
+    path: PathBuf,
+    size: u64,
+    modified: Option<SystemTime>,
+    content_hash: u64,
+}
+impl SourceStamp {
+    fn new(path: &Path) -> Option<SourceStamp> {
+        let path = fs::canonicalize(path).ok()?;
+        let metadata = fs::metadata(&path).ok()?;
+        let content_hash = fnv1a(&fs::read(&path).ok()?);
+        Some(SourceStamp { path, size: metadata.len(), modified: metadata.modified().ok(), content_hash })
+    }
+}
+
+/// The cached sector for these source files, the sector file first and then any overlays, if
+/// there is one and none of the files have changed since.
+pub fn load(sources: &[PathBuf]) -> Option<Sector> {
+    load_from(&cache_dir()?, sources)
+}
+
+fn load_from(dir: &Path, sources: &[PathBuf]) -> Option<Sector> {
+    let key = CacheKey::new(sources)?;
+    let path = cache_path(dir, &key);
+    let mut reader = BufReader::new(File::open(&path).ok()?);
+    // The key is read on its own first, so a stale cache isn't read any further.
+    let cached_key: CacheKey = bincode::deserialize_from(&mut reader).ok()?;
+    if cached_key != key {
+        return None;
+    }
+    match bincode::deserialize_from(&mut reader) {
+        Ok(sector) => {
+            info!("Loaded sector from cache {}", path.display());
+            Some(sector)
+        }
+        Err(e) => {
+            warn!("Unable to read sector cache {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Caches a freshly loaded sector. Failing to write the cache only costs the next launch time, so
+/// is logged and otherwise ignored.
+pub fn store(sources: &[PathBuf], sector: &Sector) {
+    if let Some(dir) = cache_dir() {
+        store_in(&dir, sources, sector);
+    }
+}
+
+fn store_in(dir: &Path, sources: &[PathBuf], sector: &Sector) {
+    let Some(key) = CacheKey::new(sources) else { return };
+    let path = cache_path(dir, &key);
+    if let Err(e) = write(&path, &key, sector) {
+        warn!("Unable to write sector cache {}: {}", path.display(), e);
+    }
+}
+
+fn write(path: &Path, key: &CacheKey, sector: &Sector) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Written alongside and then renamed into place, so a crash mid-write can't leave a cache
+    // that passes the key check but is cut short.
+    let temp_path = path.with_extension("tmp");
+    {
+        let mut writer = BufWriter::new(File::create(&temp_path)?);
+        bincode::serialize_into(&mut writer, key)?;
+        bincode::serialize_into(&mut writer, sector)?;
+    }
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+fn cache_dir() -> Option<PathBuf> {
+    Some(util::get_config_dir()?.join(CACHE_DIR_NAME))
+}
+
+/// One cache file per combination of source paths, so switching between sectors keeps both
+/// cached.
+fn cache_path(dir: &Path, key: &CacheKey) -> PathBuf {
+    let paths = key.sources.iter().map(|source| source.path.to_string_lossy()).collect::<Vec<_>>().join("\n");
+    dir.join(format!("{:016x}.bin", fnv1a(paths.as_bytes())))
+}
+
+/// 64-bit FNV-1a. Only used to notice changes, so doesn't need to resist deliberate collisions.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| (hash ^ *byte as u64).wrapping_mul(PRIME))
+}
+
+/// macroquad colours aren't serde types, so are stored as `[r, g, b, a]`.
+pub mod colour {
+    use macroquad::color::Color;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(colour: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        [colour.r, colour.g, colour.b, colour.a].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let [r, g, b, a] = <[f32; 4]>::deserialize(deserializer)?;
+        Ok(Color { r, g, b, a })
+    }
+
+    pub mod option {
+        use macroquad::color::Color;
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        pub fn serialize<S: Serializer>(colour: &Option<Color>, serializer: S) -> Result<S::Ok, S::Error> {
+            colour.map(|colour| [colour.r, colour.g, colour.b, colour.a]).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Color>, D::Error> {
+            Ok(Option::<[f32; 4]>::deserialize(deserializer)?.map(|[r, g, b, a]| Color { r, g, b, a }))
+        }
+    }
+}
+
+#[test]
+fn test_cache() {
+    use std::time::Duration;
+
+    use sct_reader::reader::{test_sct, SctReader};
+
+    let dir = std::env::temp_dir().join(format!("sector-cache-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let sct_path = dir.join("test.sct");
+    let sct = test_sct("Cache test", "\
+[VOR]
+MAY 117.900 N051.01.02.500 E000.06.58.000
+[FIXES]
+BIG N051.19.49.000 E000.02.46.000
+[GEO]
+Coast N051.00.00.000 E000.00.00.000 N051.01.00.000 E000.01.00.000 255
+[REGIONS]
+REGIONNAME Apron
+255 N051.00.00.000 E000.00.00.000
+N051.01.00.000 E000.01.00.000
+N051.01.00.000 E000.00.00.000
+");
+    fs::write(&sct_path, &sct).unwrap();
+    let sources = vec![sct_path.clone()];
+    let sector: Sector = SctReader::new(sct.as_bytes()).try_read().unwrap().into();
+    let bytes = |sector: &Sector| bincode::serialize(sector).unwrap();
+
+    assert!(load_from(&dir, &sources).is_none());
+    store_in(&dir, &sources, &sector);
+    let cached = load_from(&dir, &sources).unwrap();
+    assert_eq!(bytes(&cached), bytes(&sector));
+
+    // Same size, different content, with the modification time put back.
+    let modified = fs::metadata(&sct_path).unwrap().modified().unwrap();
+    let set_modified = |time: SystemTime| File::options().write(true).open(&sct_path).unwrap().set_modified(time).unwrap();
+    fs::write(&sct_path, sct.replace("BIG", "BOG")).unwrap();
+    set_modified(modified);
+    assert!(load_from(&dir, &sources).is_none());
+    fs::write(&sct_path, &sct).unwrap();
+    set_modified(modified);
+    assert!(load_from(&dir, &sources).is_some());
+
+    set_modified(modified + Duration::from_secs(60));
+    assert!(load_from(&dir, &sources).is_none());
+    set_modified(modified);
+    assert!(load_from(&dir, &sources).is_some());
+
+    fs::write(&sct_path, format!("{sct}; A comment\n")).unwrap();
+    set_modified(modified);
+    assert!(load_from(&dir, &sources).is_none());
+    fs::write(&sct_path, &sct).unwrap();
+    set_modified(modified);
+    assert!(load_from(&dir, &sources).is_some());
+
+    // A cache written by an older version.
+    let mut key = CacheKey::new(&sources).unwrap();
+    key.version -= 1;
+    write(&cache_path(&dir, &key), &key, &sector).unwrap();
+    assert!(load_from(&dir, &sources).is_none());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
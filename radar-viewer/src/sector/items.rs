@@ -3,7 +3,8 @@ use sct_reader::waypoint::Waypoint;
 
 use crate::radar::position_calc::PositionCalculator;
 use common::position::Position;
-use super::{draw::{Draw, DrawableObjectType}, mapped_vec::MappedVec};
+use serde::{Deserialize, Serialize};
+use super::{cache, draw::{Draw, DrawableObjectType}, mapped_vec::MappedVec};
 
 fn sct_reader_pos_to_common_pos(value: sct_reader::position::Position<sct_reader::position::Valid>) -> Position {
     Position { lat: value.lat as f32, lon: value.lon as f32, alt: 0.0 }
@@ -12,7 +13,7 @@ fn sct_reader_pos_to_common_pos(value: sct_reader::position::Position<sct_reader
 
 
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct NamedPoint {
     pub identifier: String,
     pub position: Position,
@@ -77,7 +78,7 @@ impl<W> From<W> for NamedPoint where W: Waypoint {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct LineGroup {
     pub identifier: String,
     pub lines: Vec<ColouredLine>,
@@ -124,10 +125,11 @@ impl From<sct_reader::line::LineGroup<sct_reader::line::ColouredLine>> for LineG
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ColouredLine {
     pub start: Position,
     pub end: Position,
+    #[serde(with = "cache::colour::option")]
     pub colour: Option<Color>,
 }
 impl From<sct_reader::line::ColouredLine> for ColouredLine {
@@ -140,7 +142,7 @@ impl From<sct_reader::line::ColouredLine> for ColouredLine {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PolyGroup {
     pub identifier: String,
     pub polys: Vec<ColouredPoly>,
@@ -196,8 +198,9 @@ impl PolyGroup {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ColouredPoly {
+    #[serde(with = "cache::colour")]
     pub colour: Color,
     pub points: Vec<Position>,
     pub indices: Vec<usize>,
@@ -219,16 +222,17 @@ impl From<sct_reader::sector::Region> for ColouredPoly {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct LabelGroup {
     pub name: String,
     pub labels: MappedVec<Label>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Label {
     pub text: String,
     pub position: Position,
+    #[serde(with = "cache::colour")]
     pub colour: Color,
     pub show: bool,
 }
@@ -256,7 +260,7 @@ pub fn mq_colour_from_sf_colour(value: sct_reader::colour::Colour) -> Color {
         r: value.r as f32 / 255.0,
         g: value.g as f32 / 255.0,
         b: value.b as f32 / 255.0,
-        a: 1.0,
+        a: value.a as f32 / 255.0,
     }
 }
 impl Draw for Label {
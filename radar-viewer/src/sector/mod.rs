@@ -5,14 +5,16 @@ use std::collections::HashMap;
 use crate::util;
 use common::{radar_profile::{filters::{RadarFilters, WaypointFilter}, colours::RadarColours}, position::Position};
 use macroquad::{prelude::Color, ui::{Ui, hash}};
-use sct_reader::waypoint::Waypoint;
+use serde::{Deserialize, Serialize};
+use sct_reader::{waypoint::Waypoint, overlay::Overlay, sector::LineCategory};
 
+pub mod cache;
 pub mod draw;
 pub mod items;
 pub mod mapped_vec;
 pub mod ui;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Sector {
     pub name: String,
     pub default_centre_pt: Position,
@@ -87,6 +89,38 @@ impl Sector {
         
     }
     
+    pub fn show_overlay(&mut self, overlay: &Overlay) {
+        for (category, overlay_group) in &overlay.lines {
+            let line_groups = match category {
+                LineCategory::Artcc => &mut self.artcc_entries,
+                LineCategory::ArtccHigh => &mut self.artcc_high_entries,
+                LineCategory::ArtccLow => &mut self.artcc_low_entries,
+                LineCategory::LowAirway => &mut self.low_airways,
+                LineCategory::HighAirway => &mut self.high_airways,
+                LineCategory::Sid => &mut self.sid_entries,
+                LineCategory::Star => &mut self.star_entries,
+                LineCategory::Geo => &mut self.geo_entries,
+            };
+            if let Some(line_group) = line_groups.get_by_name_mut(&overlay_group.name) {
+                line_group.set_visibility(true);
+            }
+        }
+        for overlay_group in &overlay.regions {
+            if let Some(poly_group) = self.regions.get_by_name_mut(&overlay_group.name) {
+                poly_group.set_visibility(true);
+            }
+        }
+        for overlay_group in &overlay.labels {
+            if let Some(label_group) = self.labels.get_by_name_mut(&overlay_group.name) {
+                for label in label_group.labels.entries() {
+                    if overlay_group.labels.iter().any(|overlay_label| overlay_label.name == label.text) {
+                        label.set_visibility(true);
+                    }
+                }
+            }
+        }
+    }
+
     pub fn ui_window(&mut self, ui: &mut Ui, search: &str) {
         self.fixes.for_each(|fix| {
             if fix.identifier.starts_with(search) {
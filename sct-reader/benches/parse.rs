@@ -0,0 +1,92 @@
+//! Parse time for a large sector file.
+//!
+//! Run with `cargo bench -p sct-reader`. By default this parses a synthetic file of country-sized
+//! proportions in which every SID, STAR and airway line refers to fixes by name. Pass a path to
+//! time a real file instead, e.g. `cargo bench -p sct-reader -- UK_2023_11.sct`.
+
+use std::{fmt::Write, hint::black_box, time::Instant};
+
+use sct_reader::{position::lat_to_es, position::lon_to_es, reader::SctReader};
+
+const FIXES: usize = 20_000;
+const VORS: usize = 500;
+const NDBS: usize = 500;
+const AIRPORTS: usize = 500;
+const ROUTE_LINES: usize = 20_000;
+const ITERATIONS: u32 = 5;
+
+fn main() {
+    let path = std::env::args().skip(1).find(|arg| !arg.starts_with("--"));
+    let (name, contents) = match path {
+        Some(path) => {
+            let contents = std::fs::read(&path).expect("unable to read the sector file");
+            (path, contents)
+        }
+        None => ("synthetic".to_owned(), synthetic_sector().into_bytes()),
+    };
+
+    let mut times = (0..ITERATIONS)
+        .map(|_| {
+            let start = Instant::now();
+            let sector = SctReader::new(contents.as_slice()).try_read();
+            let elapsed = start.elapsed();
+            black_box(sector).expect("unable to parse the sector file");
+            elapsed
+        })
+        .collect::<Vec<_>>();
+    times.sort();
+
+    println!(
+        "parse {} ({} KiB): median {:?}, min {:?} over {} runs",
+        name,
+        contents.len() / 1024,
+        times[times.len() / 2],
+        times[0],
+        ITERATIONS
+    );
+}
+
+fn synthetic_sector() -> String {
+    let mut sct = String::from(
+        "[INFO]\nSynthetic\nXXXX_CTR\nXXXX\nN051.00.00.000\nW001.00.00.000\n60\n38\n1\n1\n",
+    );
+    let position = |i: usize| {
+        let lat = 45.0 + (i % 200) as f64 * 0.05;
+        let lon = -10.0 + (i / 200) as f64 * 0.2;
+        format!("{} {}", lat_to_es(lat), lon_to_es(lon))
+    };
+
+    sct.push_str("[VOR]\n");
+    for i in 0..VORS {
+        writeln!(sct, "V{:03} 113.000 {}", i, position(i * 7)).unwrap();
+    }
+    sct.push_str("[NDB]\n");
+    for i in 0..NDBS {
+        writeln!(sct, "N{:03} 350.000 {}", i, position(i * 11)).unwrap();
+    }
+    sct.push_str("[AIRPORT]\n");
+    for i in 0..AIRPORTS {
+        writeln!(sct, "A{:03} 118.000 {} D", i, position(i * 13)).unwrap();
+    }
+    sct.push_str("[FIXES]\n");
+    for i in 0..FIXES {
+        writeln!(sct, "F{:05} {}", i, position(i)).unwrap();
+    }
+
+    sct.push_str("[SID]\n");
+    for i in 0..ROUTE_LINES / 2 {
+        let name = if i % 10 == 0 {
+            format!("A{:03} SID{}", i % AIRPORTS, i)
+        } else {
+            String::new()
+        };
+        let (a, b) = ((i * 37) % FIXES, (i * 37 + 1) % FIXES);
+        writeln!(sct, "{:<26}F{:05} F{:05} F{:05} F{:05}", name, a, a, b, b).unwrap();
+    }
+    sct.push_str("[HIGH AIRWAY]\n");
+    for i in 0..ROUTE_LINES / 2 {
+        let (a, b) = ((i * 53) % FIXES, (i * 53 + 1) % VORS);
+        writeln!(sct, "UL{} F{:05} F{:05} V{:03} V{:03}", i % 100, a, a, b, b).unwrap();
+    }
+    sct
+}
@@ -0,0 +1,431 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use crate::{
+    airway::{key, PositionKey},
+    line::{ColouredLine, Line},
+    position::{Position, Valid},
+    sector::{LineCategory, Sector},
+    spatial::BoundingBox,
+};
+
+/// Loose ends of a boundary closer together than this are joined up by [`AirspaceMap::new`].
+pub const DEFAULT_GAP_TOLERANCE_M: f64 = 200.0;
+
+/// Points this close to a polygon's edge (in degrees) are treated as being on its boundary.
+const BOUNDARY_EPSILON: f64 = 1e-9;
+
+/// A named ARTCC group joined up into closed rings.
+///
+/// A position inside an odd number of rings is inside the airspace, so the rings can describe
+/// separate pieces and holes alike. Calculations treat latitude and longitude as flat
+/// coordinates, which is fine at the size of a sector but means rings crossing the antimeridian
+/// are not supported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AirspacePolygon {
+    pub name: String,
+    pub category: LineCategory,
+    /// The vertices of each ring in order. The last vertex joins back up to the first.
+    pub rings: Vec<Vec<Position<Valid>>>,
+    bbox: BoundingBox,
+}
+impl AirspacePolygon {
+    fn new(name: String, category: LineCategory, rings: Vec<Vec<Position<Valid>>>) -> Option<Self> {
+        let bbox = BoundingBox::from_points(rings.iter().flatten())?;
+        Some(AirspacePolygon {
+            name,
+            category,
+            rings,
+            bbox,
+        })
+    }
+
+    pub fn bounding_box(&self) -> BoundingBox {
+        self.bbox
+    }
+
+    pub fn contains(&self, position: Position<Valid>) -> bool {
+        self.bbox.contains(position)
+            && self
+                .rings
+                .iter()
+                .filter(|ring| ring_contains(ring, position))
+                .count()
+                % 2
+                == 1
+    }
+
+    /// Whether the two airspaces overlap. Airspaces that only share a border don't intersect.
+    pub fn intersects(&self, other: &AirspacePolygon) -> bool {
+        if !self.bbox.intersects(&other.bbox) {
+            return false;
+        }
+        if self.rings == other.rings {
+            return true;
+        }
+        let crosses = self.edges().any(|(a_1, a_2)| {
+            other
+                .edges()
+                .any(|(b_1, b_2)| segments_cross(a_1, a_2, b_1, b_2))
+        });
+        crosses || self.has_vertex_inside(other) || other.has_vertex_inside(self)
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (Position<Valid>, Position<Valid>)> + '_ {
+        self.rings.iter().flat_map(|ring| ring_edges(ring))
+    }
+
+    /// Whether any of this polygon's vertices are inside `other`, and not just on its boundary.
+    fn has_vertex_inside(&self, other: &AirspacePolygon) -> bool {
+        self.rings.iter().flatten().any(|vertex| {
+            other.contains(*vertex)
+                && !other
+                    .edges()
+                    .any(|(a, b)| point_segment_distance(*vertex, a, b) < BOUNDARY_EPSILON)
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AirspaceIssue {
+    /// Some of the boundary couldn't be joined into a ring. `ends` are the loose ends left over
+    /// once any small gaps were closed.
+    Unclosed { ends: Vec<Position<Valid>> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AirspaceReport {
+    pub name: String,
+    pub category: LineCategory,
+    pub issue: AirspaceIssue,
+}
+impl Display for AirspaceReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.issue {
+            AirspaceIssue::Unclosed { ends } => {
+                write!(
+                    f,
+                    "{} {} is not closed, with loose ends at",
+                    self.category, self.name
+                )?;
+                for (i, end) in ends.iter().enumerate() {
+                    let (lat, lon) = end.to_es();
+                    let separator = if i == 0 { "" } else { "," };
+                    write!(f, "{} {} {}", separator, lat, lon)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The `[ARTCC]`, `[ARTCC HIGH]` and `[ARTCC LOW]` groups of a [`Sector`], each joined up into an
+/// [`AirspacePolygon`].
+///
+/// Segments can be in any order and either direction. Groups that can't be fully closed are
+/// reported, and keep whichever rings could be closed.
+#[derive(Debug, Clone, Default)]
+pub struct AirspaceMap {
+    polygons: Vec<AirspacePolygon>,
+    reports: Vec<AirspaceReport>,
+}
+impl AirspaceMap {
+    pub fn new(sector: &Sector) -> AirspaceMap {
+        AirspaceMap::with_gap_tolerance(sector, DEFAULT_GAP_TOLERANCE_M)
+    }
+
+    /// As [`AirspaceMap::new`], joining up loose ends up to `gap_tolerance_m` apart.
+    pub fn with_gap_tolerance(sector: &Sector, gap_tolerance_m: f64) -> AirspaceMap {
+        let mut map = AirspaceMap::default();
+        for category in [
+            LineCategory::Artcc,
+            LineCategory::ArtccHigh,
+            LineCategory::ArtccLow,
+        ] {
+            for group in sector.line_groups(category) {
+                let (rings, ends) = build_rings(&group.lines, gap_tolerance_m);
+                if !ends.is_empty() {
+                    map.reports.push(AirspaceReport {
+                        name: group.name.clone(),
+                        category,
+                        issue: AirspaceIssue::Unclosed { ends },
+                    });
+                }
+                map.polygons
+                    .extend(AirspacePolygon::new(group.name.clone(), category, rings));
+            }
+        }
+        map
+    }
+
+    pub fn polygons(&self) -> &[AirspacePolygon] {
+        &self.polygons
+    }
+    pub fn polygon(&self, category: LineCategory, name: &str) -> Option<&AirspacePolygon> {
+        self.polygons
+            .iter()
+            .find(|polygon| polygon.category == category && polygon.name == name)
+    }
+
+    /// Groups that couldn't be closed.
+    pub fn reports(&self) -> &[AirspaceReport] {
+        &self.reports
+    }
+
+    /// The airspaces `position` is inside.
+    pub fn containing(&self, position: Position<Valid>) -> impl Iterator<Item = &AirspacePolygon> {
+        self.polygons
+            .iter()
+            .filter(move |polygon| polygon.contains(position))
+    }
+
+    /// The other airspaces overlapping `polygon`.
+    pub fn intersecting<'a>(
+        &'a self,
+        polygon: &'a AirspacePolygon,
+    ) -> impl Iterator<Item = &'a AirspacePolygon> {
+        self.polygons.iter().filter(move |other| {
+            (other.category, &other.name) != (polygon.category, &polygon.name)
+                && other.intersects(polygon)
+        })
+    }
+}
+
+/// Joins a group's segments into closed rings, returning them along with any loose ends.
+///
+/// Loose ends within `gap_tolerance_m` of each other are joined first. Spurs hanging off the
+/// boundary are then trimmed back, and any open trails left between junctions are dropped, so
+/// everything that remains splits cleanly into rings.
+fn build_rings(
+    lines: &[ColouredLine],
+    gap_tolerance_m: f64,
+) -> (Vec<Vec<Position<Valid>>>, Vec<Position<Valid>>) {
+    let mut nodes: Vec<Position<Valid>> = vec![];
+    let mut node_index: HashMap<PositionKey, usize> = HashMap::new();
+    let mut adjacency: Vec<Vec<usize>> = vec![];
+    let mut edges: Vec<(usize, usize)> = vec![];
+    let mut seen: HashSet<(usize, usize)> = HashSet::new();
+
+    let mut add_edge = |a: usize, b: usize, adjacency: &mut Vec<Vec<usize>>| {
+        if a != b && seen.insert((a.min(b), a.max(b))) {
+            adjacency[a].push(edges.len());
+            adjacency[b].push(edges.len());
+            edges.push((a, b));
+        }
+    };
+    for line in lines {
+        let [a, b] = [line.start(), line.end()].map(|position| {
+            *node_index.entry(key(position)).or_insert_with(|| {
+                nodes.push(position);
+                adjacency.push(vec![]);
+                nodes.len() - 1
+            })
+        });
+        add_edge(a, b, &mut adjacency);
+    }
+
+    let mut loose = (0..nodes.len())
+        .filter(|node| adjacency[*node].len() % 2 == 1)
+        .collect::<Vec<_>>();
+    let mut ends = vec![];
+    while let Some(a) = loose.pop() {
+        let nearest = loose
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (i, nodes[a].distance_m(&nodes[*b])))
+            .filter(|(_, distance)| *distance <= gap_tolerance_m)
+            .min_by(|(_, x), (_, y)| x.total_cmp(y));
+        match nearest {
+            Some((i, _)) => {
+                let b = loose.swap_remove(i);
+                add_edge(a, b, &mut adjacency);
+            }
+            None => ends.push(a),
+        }
+    }
+
+    let mut used = vec![false; edges.len()];
+    let unused = |node: usize, used: &[bool]| -> Vec<usize> {
+        adjacency[node]
+            .iter()
+            .copied()
+            .filter(|edge| !used[*edge])
+            .collect()
+    };
+    let other_end = |edge: usize, node: usize| {
+        let (a, b) = edges[edge];
+        if a == node {
+            b
+        } else {
+            a
+        }
+    };
+
+    // Trim spurs back to where they join the rest of the boundary.
+    for end in &ends {
+        let mut current = *end;
+        while let [edge] = unused(current, &used)[..] {
+            used[edge] = true;
+            current = other_end(edge, current);
+        }
+    }
+    // Drop open trails between junctions.
+    while let Some(start) = (0..nodes.len()).find(|node| unused(*node, &used).len() % 2 == 1) {
+        let mut current = start;
+        while let Some(edge) = unused(current, &used).first().copied() {
+            used[edge] = true;
+            current = other_end(edge, current);
+        }
+    }
+
+    // Every node now has an even number of unused edges, so each walk comes back to its start.
+    let mut rings = vec![];
+    while let Some(first) = (0..edges.len()).find(|edge| !used[*edge]) {
+        let start = edges[first].0;
+        let mut ring = vec![nodes[start]];
+        let mut current = start;
+        while let Some(edge) = unused(current, &used).first().copied() {
+            used[edge] = true;
+            current = other_end(edge, current);
+            if current == start {
+                break;
+            }
+            ring.push(nodes[current]);
+        }
+        rings.push(ring);
+    }
+
+    ends.sort_unstable();
+    (rings, ends.into_iter().map(|node| nodes[node]).collect())
+}
+
+fn ring_edges(
+    ring: &[Position<Valid>],
+) -> impl Iterator<Item = (Position<Valid>, Position<Valid>)> + '_ {
+    ring.iter()
+        .copied()
+        .zip(ring.iter().copied().cycle().skip(1))
+}
+
+/// Even-odd test, casting a ray east from `position`.
+fn ring_contains(ring: &[Position<Valid>], position: Position<Valid>) -> bool {
+    let mut inside = false;
+    for (a, b) in ring_edges(ring) {
+        if (a.lat > position.lat) != (b.lat > position.lat) {
+            let lon = a.lon + (position.lat - a.lat) / (b.lat - a.lat) * (b.lon - a.lon);
+            if position.lon < lon {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+/// Which side of the line from `a` to `b` `c` is on: positive to the left, negative to the right
+/// and zero when the three are in line.
+fn orientation(a: Position<Valid>, b: Position<Valid>, c: Position<Valid>) -> f64 {
+    (b.lon - a.lon) * (c.lat - a.lat) - (b.lat - a.lat) * (c.lon - a.lon)
+}
+
+/// Whether the segments cross each other at a single point. Segments that only touch, or run
+/// along each other, don't count.
+pub(crate) fn segments_cross(
+    a_1: Position<Valid>,
+    a_2: Position<Valid>,
+    b_1: Position<Valid>,
+    b_2: Position<Valid>,
+) -> bool {
+    let opposite = |x: f64, y: f64| (x > 0.0 && y < 0.0) || (x < 0.0 && y > 0.0);
+    opposite(orientation(b_1, b_2, a_1), orientation(b_1, b_2, a_2))
+        && opposite(orientation(a_1, a_2, b_1), orientation(a_1, a_2, b_2))
+}
+
+/// Distance in degrees, treating latitude and longitude as flat coordinates.
+fn point_segment_distance(p: Position<Valid>, a: Position<Valid>, b: Position<Valid>) -> f64 {
+    let (d_lat, d_lon) = (b.lat - a.lat, b.lon - a.lon);
+    let length_sq = d_lat * d_lat + d_lon * d_lon;
+    let t = if length_sq == 0.0 {
+        0.0
+    } else {
+        (((p.lat - a.lat) * d_lat + (p.lon - a.lon) * d_lon) / length_sq).clamp(0.0, 1.0)
+    };
+    (p.lat - (a.lat + t * d_lat)).hypot(p.lon - (a.lon + t * d_lon))
+}
+
+#[test]
+fn test_airspace_map() {
+    use crate::reader::SctReader;
+
+    // ALPHA has one segment reversed and a gap of about 30 m in its south side. BRAVO overlaps
+    // ALPHA, CHARLIE shares its eastern border and DELTA is missing a side.
+    let sct = "\
+[INFO]
+Airspace test
+EGTT_CTR
+EGKK
+N051.00.00.000
+W001.00.00.000
+60
+38
+1
+1
+[ARTCC HIGH]
+ALPHA N051.00.00.000 W001.00.00.000 N051.00.00.000 E000.00.00.000
+ALPHA N052.00.00.000 E000.00.00.000 N051.00.00.000 E000.00.00.000
+ALPHA N052.00.00.000 E000.00.00.000 N052.00.00.000 W001.00.00.000
+ALPHA N052.00.00.000 W001.00.00.000 N051.00.01.000 W001.00.00.000
+BRAVO N051.30.00.000 W000.30.00.000 N051.30.00.000 E000.30.00.000
+BRAVO N051.30.00.000 E000.30.00.000 N052.30.00.000 E000.30.00.000
+BRAVO N052.30.00.000 E000.30.00.000 N052.30.00.000 W000.30.00.000
+BRAVO N052.30.00.000 W000.30.00.000 N051.30.00.000 W000.30.00.000
+CHARLIE N051.00.00.000 E000.00.00.000 N051.00.00.000 E001.00.00.000
+CHARLIE N051.00.00.000 E001.00.00.000 N052.00.00.000 E001.00.00.000
+CHARLIE N052.00.00.000 E001.00.00.000 N052.00.00.000 E000.00.00.000
+CHARLIE N052.00.00.000 E000.00.00.000 N051.00.00.000 E000.00.00.000
+DELTA N053.00.00.000 W001.00.00.000 N053.00.00.000 E000.00.00.000
+DELTA N053.00.00.000 E000.00.00.000 N054.00.00.000 E000.00.00.000
+DELTA N054.00.00.000 E000.00.00.000 N054.00.00.000 W001.00.00.000
+";
+    let sector = SctReader::new(sct.as_bytes()).try_read().unwrap();
+    let map = AirspaceMap::new(&sector);
+
+    let alpha = map.polygon(LineCategory::ArtccHigh, "ALPHA").unwrap();
+    assert_eq!(alpha.rings.len(), 1);
+    assert_eq!(alpha.rings[0].len(), 5);
+    let bravo = map.polygon(LineCategory::ArtccHigh, "BRAVO").unwrap();
+    let charlie = map.polygon(LineCategory::ArtccHigh, "CHARLIE").unwrap();
+    assert!(map.polygon(LineCategory::ArtccHigh, "DELTA").is_none());
+
+    let inside_alpha = Position::new(51.2, -0.5).validate().unwrap();
+    let names = map
+        .containing(inside_alpha)
+        .map(|polygon| polygon.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["ALPHA"]);
+    assert!(!alpha.contains(Position::new(51.5, 0.5).validate().unwrap()));
+
+    assert!(alpha.intersects(bravo));
+    assert!(bravo.intersects(charlie));
+    assert!(!alpha.intersects(charlie));
+    let overlapping = map
+        .intersecting(bravo)
+        .map(|polygon| polygon.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(overlapping, vec!["ALPHA", "CHARLIE"]);
+
+    assert_eq!(map.reports().len(), 1);
+    assert_eq!(
+        map.reports()[0].to_string(),
+        "[ARTCC HIGH] DELTA is not closed, with loose ends at N053.00.00.000 W001.00.00.000, \
+         N054.00.00.000 W001.00.00.000"
+    );
+    // Without any tolerance ALPHA can't be closed either.
+    assert_eq!(
+        AirspaceMap::with_gap_tolerance(&sector, 0.0)
+            .reports()
+            .len(),
+        2
+    );
+}
@@ -0,0 +1,464 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    error::Error,
+    line::{ColouredLine, Line},
+    position::{Position, Valid},
+    sector::Sector,
+    waypoint::Waypoint,
+    SectorResult,
+};
+
+/// Positions are matched to waypoints after rounding to this many steps per degree, which is
+/// about a metre and comfortably coarser than the thousandths of a second used in sector files.
+const KEY_STEPS_PER_DEGREE: f64 = 100_000.0;
+
+pub(crate) type PositionKey = (i64, i64);
+
+pub(crate) fn key(position: Position<Valid>) -> PositionKey {
+    (
+        (position.lat * KEY_STEPS_PER_DEGREE).round() as i64,
+        (position.lon * KEY_STEPS_PER_DEGREE).round() as i64,
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AirwayLevel {
+    Low,
+    High,
+    Both,
+}
+
+/// A point along an airway. `identifier` is the fix, VOR or NDB at this position, if there is one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AirwayPoint {
+    pub identifier: Option<String>,
+    pub position: Position<Valid>,
+}
+impl Display for AirwayPoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.identifier {
+            Some(identifier) => write!(f, "{}", identifier),
+            None => {
+                let (lat, lon) = self.position.to_es();
+                write!(f, "{} {}", lat, lon)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Airway {
+    pub name: String,
+    pub level: AirwayLevel,
+    /// The points of the airway in order. A continuous airway has a single sequence; each break
+    /// in the airway starts a new one.
+    pub sequences: Vec<Vec<AirwayPoint>>,
+}
+impl Airway {
+    pub fn is_continuous(&self) -> bool {
+        self.sequences.len() <= 1
+    }
+
+    /// The points from `from` to `to` inclusive, in that order. Airways can be followed in either
+    /// direction.
+    pub fn between(&self, from: &str, to: &str) -> SectorResult<Vec<AirwayPoint>> {
+        let find = |sequence: &[AirwayPoint], identifier: &str| {
+            sequence
+                .iter()
+                .position(|point| point.identifier.as_deref() == Some(identifier))
+        };
+        let not_on_airway = |waypoint: &str| Error::NotOnAirway {
+            waypoint: waypoint.to_owned(),
+            airway: self.name.clone(),
+        };
+
+        let mut found_from = false;
+        let mut found_to = false;
+        for sequence in &self.sequences {
+            match (find(sequence, from), find(sequence, to)) {
+                (Some(start), Some(end)) if start <= end => {
+                    return Ok(sequence[start..=end].to_vec())
+                }
+                (Some(start), Some(end)) => {
+                    return Ok(sequence[end..=start].iter().rev().cloned().collect())
+                }
+                (start, end) => {
+                    found_from |= start.is_some();
+                    found_to |= end.is_some();
+                }
+            }
+        }
+        match (found_from, found_to) {
+            (false, _) => Err(not_on_airway(from)),
+            (_, false) => Err(not_on_airway(to)),
+            _ => Err(Error::BrokenAirway {
+                airway: self.name.clone(),
+                from: from.to_owned(),
+                to: to.to_owned(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AirwayIssue {
+    /// The airway is made up of more than one separate piece.
+    Discontinuous { sequences: usize },
+    /// More than two segments of the airway meet at this point.
+    Branch { at: AirwayPoint },
+    /// A segment ends at a position that isn't a known fix, VOR or NDB.
+    UnnamedPoint { position: Position<Valid> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AirwayReport {
+    pub airway: String,
+    pub issue: AirwayIssue,
+}
+impl Display for AirwayReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.issue {
+            AirwayIssue::Discontinuous { sequences } => write!(
+                f,
+                "Airway {} is broken into {} pieces",
+                self.airway, sequences
+            ),
+            AirwayIssue::Branch { at } => write!(f, "Airway {} branches at {}", self.airway, at),
+            AirwayIssue::UnnamedPoint { position } => {
+                let (lat, lon) = position.to_es();
+                write!(
+                    f,
+                    "Airway {} has a point at {} {} which is not a known fix",
+                    self.airway, lat, lon
+                )
+            }
+        }
+    }
+}
+
+/// The airways of a [`Sector`], with each one's segments joined up into ordered sequences of
+/// points.
+///
+/// Airways with the same name in `[LOW AIRWAY]` and `[HIGH AIRWAY]` are treated as one airway.
+#[derive(Debug, Clone, Default)]
+pub struct AirwayNetwork {
+    airways: HashMap<String, Airway>,
+    waypoints: HashMap<String, Vec<Position<Valid>>>,
+    reports: Vec<AirwayReport>,
+}
+impl AirwayNetwork {
+    pub fn new(sector: &Sector) -> AirwayNetwork {
+        let mut network = AirwayNetwork::default();
+        let mut names = HashMap::new();
+
+        fn add_waypoints<W: Waypoint>(
+            waypoints: &[W],
+            names: &mut HashMap<PositionKey, String>,
+            by_identifier: &mut HashMap<String, Vec<Position<Valid>>>,
+            name_points: bool,
+        ) {
+            for waypoint in waypoints {
+                if name_points {
+                    names
+                        .entry(key(waypoint.position()))
+                        .or_insert_with(|| waypoint.identifier().clone());
+                }
+                by_identifier
+                    .entry(waypoint.identifier().clone())
+                    .or_default()
+                    .push(waypoint.position());
+            }
+        }
+        add_waypoints(&sector.fixes, &mut names, &mut network.waypoints, true);
+        add_waypoints(&sector.vors, &mut names, &mut network.waypoints, true);
+        add_waypoints(&sector.ndbs, &mut names, &mut network.waypoints, true);
+        add_waypoints(&sector.airports, &mut names, &mut network.waypoints, false);
+
+        let mut segments: Vec<(String, AirwayLevel, Vec<&ColouredLine>)> = vec![];
+        let mut segment_index: HashMap<&str, usize> = HashMap::new();
+        for (groups, level) in [
+            (&sector.low_airways, AirwayLevel::Low),
+            (&sector.high_airways, AirwayLevel::High),
+        ] {
+            for group in groups {
+                match segment_index.get(group.name.as_str()) {
+                    Some(index) => {
+                        let (_, existing_level, lines) = &mut segments[*index];
+                        if *existing_level != level {
+                            *existing_level = AirwayLevel::Both;
+                        }
+                        lines.extend(group.lines.iter());
+                    }
+                    None => {
+                        segment_index.insert(&group.name, segments.len());
+                        segments.push((group.name.clone(), level, group.lines.iter().collect()));
+                    }
+                }
+            }
+        }
+
+        for (name, level, lines) in segments {
+            let (sequences, issues) = build_sequences(&lines, &names);
+            network
+                .reports
+                .extend(issues.into_iter().map(|issue| AirwayReport {
+                    airway: name.clone(),
+                    issue,
+                }));
+            network.airways.insert(
+                name.clone(),
+                Airway {
+                    name,
+                    level,
+                    sequences,
+                },
+            );
+        }
+        network
+    }
+
+    pub fn airway(&self, name: &str) -> Option<&Airway> {
+        self.airways.get(name)
+    }
+    pub fn airways(&self) -> impl Iterator<Item = &Airway> {
+        self.airways.values()
+    }
+
+    /// Problems found while joining up the airways, such as gaps and branches.
+    pub fn reports(&self) -> &[AirwayReport] {
+        &self.reports
+    }
+
+    /// Expands a route such as `"DVR L9 KONAN"` into the positions along it. Waypoints and airways
+    /// alternate, although consecutive waypoints (optionally separated by `DCT`) are joined
+    /// directly.
+    pub fn expand(&self, route: &str) -> SectorResult<Vec<Position<Valid>>> {
+        Ok(self
+            .expand_points(route)?
+            .into_iter()
+            .map(|point| point.position)
+            .collect())
+    }
+
+    /// As [`AirwayNetwork::expand`], but keeping the identifier of each point.
+    pub fn expand_points(&self, route: &str) -> SectorResult<Vec<AirwayPoint>> {
+        let tokens = route
+            .split_whitespace()
+            .filter(|token| *token != "DCT")
+            .collect::<Vec<_>>();
+        let mut points: Vec<AirwayPoint> = vec![];
+        let mut i = 0;
+        while i < tokens.len() {
+            let token = tokens[i];
+            let previous = points.last().and_then(|point| point.identifier.as_deref());
+            match (self.airways.get(token), previous, tokens.get(i + 1)) {
+                // Identifiers are checked as waypoints first, so that a fix which happens to share
+                // its name with an airway is still usable.
+                _ if self.waypoints.contains_key(token)
+                    && (previous.is_none() || !self.airways.contains_key(token)) =>
+                {
+                    points.push(self.resolve(token, points.last())?);
+                    i += 1;
+                }
+                (Some(airway), Some(from), Some(to)) => {
+                    let along = airway.between(from, to)?;
+                    points.extend(along.into_iter().skip(1));
+                    i += 2;
+                }
+                (Some(_), _, _) => return Err(Error::InvalidRoute),
+                // Between a waypoint and another token is where an airway goes.
+                (None, Some(_), Some(_)) => return Err(Error::UnknownAirway(token.to_owned())),
+                (None, _, _) => return Err(Error::UnknownWaypoint(token.to_owned())),
+            }
+        }
+        if points.is_empty() {
+            return Err(Error::InvalidRoute);
+        }
+        Ok(points)
+    }
+
+    /// Looks up a waypoint by identifier, taking the nearest to `previous` if there is more than
+    /// one.
+    fn resolve(
+        &self,
+        identifier: &str,
+        previous: Option<&AirwayPoint>,
+    ) -> SectorResult<AirwayPoint> {
+        let candidates = self
+            .waypoints
+            .get(identifier)
+            .ok_or_else(|| Error::UnknownWaypoint(identifier.to_owned()))?;
+        let position = match previous {
+            Some(previous) => *candidates
+                .iter()
+                .min_by(|a, b| {
+                    distance_sq(previous.position, **a)
+                        .total_cmp(&distance_sq(previous.position, **b))
+                })
+                .unwrap(),
+            None => candidates[0],
+        };
+        Ok(AirwayPoint {
+            identifier: Some(identifier.to_owned()),
+            position,
+        })
+    }
+}
+
+fn distance_sq(a: Position<Valid>, b: Position<Valid>) -> f64 {
+    let d_lat = b.lat - a.lat;
+    let d_lon = (b.lon - a.lon) * ((a.lat + b.lat) / 2.0).to_radians().cos();
+    d_lat * d_lat + d_lon * d_lon
+}
+
+/// Joins an airway's segments into ordered sequences of points.
+///
+/// Each sequence is a walk along segments that haven't been used yet, starting from the end of a
+/// chain where there is one. A straightforward airway gives a single sequence; gaps and branches
+/// give more.
+fn build_sequences(
+    lines: &[&ColouredLine],
+    names: &HashMap<PositionKey, String>,
+) -> (Vec<Vec<AirwayPoint>>, Vec<AirwayIssue>) {
+    let mut issues = vec![];
+    let mut nodes: Vec<AirwayPoint> = vec![];
+    let mut node_index: HashMap<PositionKey, usize> = HashMap::new();
+    let mut adjacency: Vec<Vec<usize>> = vec![];
+    let mut edges: Vec<(usize, usize)> = vec![];
+
+    let mut node = |position: Position<Valid>,
+                    nodes: &mut Vec<AirwayPoint>,
+                    adjacency: &mut Vec<Vec<usize>>,
+                    issues: &mut Vec<AirwayIssue>| {
+        *node_index.entry(key(position)).or_insert_with(|| {
+            let identifier = names.get(&key(position)).cloned();
+            if identifier.is_none() {
+                issues.push(AirwayIssue::UnnamedPoint { position });
+            }
+            nodes.push(AirwayPoint {
+                identifier,
+                position,
+            });
+            adjacency.push(vec![]);
+            nodes.len() - 1
+        })
+    };
+
+    for line in lines {
+        let a = node(line.start(), &mut nodes, &mut adjacency, &mut issues);
+        let b = node(line.end(), &mut nodes, &mut adjacency, &mut issues);
+        if a == b || edges.contains(&(a, b)) || edges.contains(&(b, a)) {
+            continue;
+        }
+        adjacency[a].push(edges.len());
+        adjacency[b].push(edges.len());
+        edges.push((a, b));
+    }
+
+    for (i, edge_ids) in adjacency.iter().enumerate() {
+        if edge_ids.len() > 2 {
+            issues.push(AirwayIssue::Branch {
+                at: nodes[i].clone(),
+            });
+        }
+    }
+
+    let mut used = vec![false; edges.len()];
+    let unused_degree =
+        |node: usize, used: &[bool]| adjacency[node].iter().filter(|edge| !used[**edge]).count();
+    let mut sequences = vec![];
+    while used.iter().any(|used| !used) {
+        let start = (0..nodes.len())
+            .find(|node| unused_degree(*node, &used) % 2 == 1)
+            .or_else(|| (0..nodes.len()).find(|node| unused_degree(*node, &used) > 0))
+            .unwrap();
+
+        let mut sequence = vec![nodes[start].clone()];
+        let mut current = start;
+        while let Some(edge) = adjacency[current].iter().copied().find(|edge| !used[*edge]) {
+            used[edge] = true;
+            let (a, b) = edges[edge];
+            current = if a == current { b } else { a };
+            sequence.push(nodes[current].clone());
+        }
+        sequences.push(sequence);
+    }
+
+    if sequences.len() > 1 {
+        issues.push(AirwayIssue::Discontinuous {
+            sequences: sequences.len(),
+        });
+    }
+    (sequences, issues)
+}
+
+#[test]
+fn test_airway_network() {
+    use crate::reader::SctReader;
+
+    let sct = "\
+[INFO]
+Airway test
+EGTT_CTR
+EGKK
+N051.08.53.000
+W000.11.25.000
+60
+38
+1
+1
+[VOR]
+DVR 114.950 N051.09.45.000 E001.21.34.000
+[FIXES]
+KONAN N051.07.58.000 E002.00.00.000
+KOK N051.05.41.000 E002.39.07.000
+SASKI N051.01.30.000 E000.54.48.000
+LYD N050.59.58.000 E000.52.43.000
+[LOW AIRWAY]
+L9 KONAN KONAN KOK KOK
+L9 DVR DVR KONAN KONAN
+L9 LYD LYD SASKI SASKI
+";
+    let sector = SctReader::new(sct.as_bytes()).try_read().unwrap();
+    let network = AirwayNetwork::new(&sector);
+
+    let l9 = network.airway("L9").unwrap();
+    assert_eq!(l9.level, AirwayLevel::Low);
+    assert!(!l9.is_continuous());
+    assert_eq!(
+        network.reports(),
+        &[AirwayReport {
+            airway: "L9".to_owned(),
+            issue: AirwayIssue::Discontinuous { sequences: 2 }
+        }]
+    );
+
+    let route = network.expand_points("DVR L9 KOK").unwrap();
+    let identifiers = route
+        .iter()
+        .map(|point| point.identifier.as_deref().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(identifiers, vec!["DVR", "KONAN", "KOK"]);
+
+    let reversed = network.expand("KOK L9 DVR DCT SASKI").unwrap();
+    assert_eq!(reversed.len(), 4);
+    assert_eq!(reversed[2], sector.vors[0].position);
+
+    assert_eq!(
+        network.expand("DVR L9 SASKI"),
+        Err(Error::BrokenAirway {
+            airway: "L9".to_owned(),
+            from: "DVR".to_owned(),
+            to: "SASKI".to_owned()
+        })
+    );
+    assert_eq!(
+        network.expand("DVR L10 KOK"),
+        Err(Error::UnknownAirway("L10".to_owned()))
+    );
+    assert_eq!(
+        network.expand("DVR L9 KOK DCT NOPE"),
+        Err(Error::UnknownWaypoint("NOPE".to_owned()))
+    );
+}
@@ -0,0 +1,265 @@
+use crate::{
+    colour::Colour,
+    line::{ColouredLine, LineGroup},
+    position::{Position, Valid, EARTH_RADIUS_M, METRES_PER_FOOT, METRES_PER_N_MI},
+    sector::{Region, RegionGroup},
+    waypoint::{Airport, RunwayEnd},
+};
+
+/// How [`RunwayEnd::extended_centreline`] draws a centreline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CentrelineOptions {
+    /// How far the centreline runs out from the threshold.
+    pub length_n_mi: f64,
+    /// Distance between range marks, counted from the threshold. Zero or less draws none.
+    pub tick_interval_n_mi: f64,
+    /// Full width of each range mark, centred on the centreline.
+    pub tick_length_n_mi: f64,
+    pub colour: Option<Colour>,
+}
+impl Default for CentrelineOptions {
+    fn default() -> Self {
+        CentrelineOptions {
+            length_n_mi: 10.0,
+            tick_interval_n_mi: 1.0,
+            tick_length_n_mi: 0.5,
+            colour: None,
+        }
+    }
+}
+
+/// The shape of the polygon drawn by [`RunwayEnd::approach_funnel`], which widens evenly from the
+/// threshold out to its full width.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FunnelOptions {
+    pub length_n_mi: f64,
+    /// Half the width of the funnel at the threshold. Zero gives a triangle.
+    pub threshold_half_width_n_mi: f64,
+    /// Half the width of the funnel at its far end.
+    pub outer_half_width_n_mi: f64,
+    pub colour: Colour,
+}
+impl Default for FunnelOptions {
+    fn default() -> Self {
+        FunnelOptions {
+            length_n_mi: 10.0,
+            threshold_half_width_n_mi: 0.1,
+            outer_half_width_n_mi: 1.0,
+            colour: Colour::new(255, 255, 255),
+        }
+    }
+}
+
+/// A straight glide path down to a runway threshold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlidePath {
+    pub angle_deg: f64,
+    /// Height of the glide path above the runway as it crosses the threshold.
+    pub threshold_crossing_height_ft: f64,
+    /// Sector files don't give runway elevations, so this is zero unless set.
+    pub threshold_elevation_ft: f64,
+}
+impl Default for GlidePath {
+    fn default() -> Self {
+        GlidePath {
+            angle_deg: 3.0,
+            threshold_crossing_height_ft: 50.0,
+            threshold_elevation_ft: 0.0,
+        }
+    }
+}
+impl GlidePath {
+    /// The altitude of the glide path at `distance_n_mi` from the threshold, allowing for the
+    /// Earth's surface curving away below it (about 90 ft at 10 nm).
+    pub fn altitude_ft(&self, distance_n_mi: f64) -> f64 {
+        let distance_m = distance_n_mi * METRES_PER_N_MI;
+        let height_m = distance_m * self.angle_deg.to_radians().tan()
+            + distance_m.powi(2) / (2.0 * EARTH_RADIUS_M);
+        self.threshold_elevation_ft + self.threshold_crossing_height_ft + height_m / METRES_PER_FOOT
+    }
+}
+
+/// Approach geometry for a runway end, measured from its threshold out along the final approach,
+/// i.e. away from the stop end.
+impl RunwayEnd {
+    /// The true bearing from the threshold out along the final approach.
+    fn outbound_bearing(&self) -> f64 {
+        self.se_threshold_pos
+            .initial_bearing(&self.td_threshold_pos)
+    }
+
+    /// The position `distance_n_mi` out along the extended centreline, and the bearing of the
+    /// centreline there.
+    fn on_centreline(&self, distance_n_mi: f64) -> (Position<Valid>, f64) {
+        let position = self
+            .td_threshold_pos
+            .destination(distance_n_mi * METRES_PER_N_MI, self.outbound_bearing());
+        if distance_n_mi == 0.0 {
+            (position, self.outbound_bearing())
+        } else {
+            (position, self.td_threshold_pos.final_bearing(&position))
+        }
+    }
+
+    /// The extended centreline from the threshold, followed by a range mark across it every
+    /// `tick_interval_n_mi`.
+    pub fn extended_centreline(&self, options: &CentrelineOptions) -> Vec<ColouredLine> {
+        let (end, _) = self.on_centreline(options.length_n_mi);
+        let mut lines = vec![ColouredLine::new(
+            self.td_threshold_pos,
+            end,
+            options.colour,
+        )];
+        if options.tick_interval_n_mi <= 0.0 {
+            return lines;
+        }
+        let half_tick_m = options.tick_length_n_mi * METRES_PER_N_MI / 2.0;
+        // Counting the marks up front, with a little slack for rounding, keeps a mark at the very
+        // end of the centreline when the interval doesn't divide the length exactly in binary.
+        let ticks = (options.length_n_mi / options.tick_interval_n_mi + 1e-9).floor() as usize;
+        for i in 1..=ticks {
+            let (centre, bearing) = self.on_centreline(i as f64 * options.tick_interval_n_mi);
+            lines.push(ColouredLine::new(
+                centre.destination(half_tick_m, bearing - 90.0),
+                centre.destination(half_tick_m, bearing + 90.0),
+                options.colour,
+            ));
+        }
+        lines
+    }
+
+    /// A polygon around the final approach, widening away from the threshold.
+    pub fn approach_funnel(&self, options: &FunnelOptions) -> Region {
+        let (outer, bearing) = self.on_centreline(options.length_n_mi);
+        let inner_bearing = self.outbound_bearing();
+        let outer_m = options.outer_half_width_n_mi * METRES_PER_N_MI;
+        let mut vertices = vec![
+            outer.destination(outer_m, bearing - 90.0),
+            outer.destination(outer_m, bearing + 90.0),
+        ];
+        if options.threshold_half_width_n_mi > 0.0 {
+            let inner_m = options.threshold_half_width_n_mi * METRES_PER_N_MI;
+            vertices.push(
+                self.td_threshold_pos
+                    .destination(inner_m, inner_bearing + 90.0),
+            );
+            vertices.push(
+                self.td_threshold_pos
+                    .destination(inner_m, inner_bearing - 90.0),
+            );
+        } else {
+            vertices.push(self.td_threshold_pos);
+        }
+        Region {
+            colour: options.colour,
+            colour_name: None,
+            vertices,
+        }
+    }
+}
+
+impl Airport {
+    /// Extended centrelines for both ends of every runway, each group named after the airport
+    /// and runway, e.g. "EGKK 26L".
+    pub fn extended_centrelines(
+        &self,
+        options: &CentrelineOptions,
+    ) -> Vec<LineGroup<ColouredLine>> {
+        self.runway_ends()
+            .map(|end| {
+                LineGroup::new(
+                    format!("{} {}", self.identifier, end.identifier()),
+                    end.extended_centreline(options),
+                )
+            })
+            .collect()
+    }
+
+    /// Approach funnels for both ends of every runway, in one group named after the airport.
+    pub fn approach_funnels(&self, options: &FunnelOptions) -> RegionGroup {
+        let mut group = RegionGroup::new(self.identifier.clone());
+        group.regions = self
+            .runway_ends()
+            .map(|end| end.approach_funnel(options))
+            .collect();
+        group
+    }
+}
+
+#[test]
+fn test_approach_geometry() {
+    use crate::reader::test_sector;
+
+    let sector = test_sector(
+        "Approach test",
+        "\
+[AIRPORT]
+EGKK 124.225 N051.08.53.000 W000.11.25.000 D
+[RUNWAY]
+08R 26L 077 257 N051.08.50.000 W000.12.00.000 N051.09.20.000 W000.09.30.000 EGKK
+",
+    );
+    let airport = &sector.airports[0];
+    let end = &airport.runways[0].end_a;
+
+    let options = CentrelineOptions::default();
+    let lines = end.extended_centreline(&options);
+    assert_eq!(lines.len(), 11);
+    let centreline = &lines[0].line;
+    assert_eq!(centreline.start, end.td_threshold_pos);
+    assert!((end.td_threshold_pos.distance_n_mi(&centreline.end) - 10.0).abs() < 1e-6);
+    // The centreline runs out the opposite way to the landing direction.
+    assert!(centreline.end.distance_m(&end.se_threshold_pos) > 10.0 * METRES_PER_N_MI);
+    let fifth = &lines[5].line;
+    assert!((fifth.start.distance_n_mi(&fifth.end) - 0.5).abs() < 1e-3);
+    let middle = fifth.start.destination(
+        fifth.start.distance_m(&fifth.end) / 2.0,
+        fifth.start.initial_bearing(&fifth.end),
+    );
+    assert!((end.td_threshold_pos.distance_n_mi(&middle) - 5.0).abs() < 1e-3);
+
+    // A tenth of a mile can't be held exactly, so adding it up drifts past the last mark.
+    let fine = end.extended_centreline(&CentrelineOptions {
+        length_n_mi: 3.0,
+        tick_interval_n_mi: 0.1,
+        ..options
+    });
+    assert_eq!(fine.len(), 31);
+    let last = &fine[30].line;
+    let middle = last.start.destination(
+        last.start.distance_m(&last.end) / 2.0,
+        last.start.initial_bearing(&last.end),
+    );
+    assert!((end.td_threshold_pos.distance_n_mi(&middle) - 3.0).abs() < 1e-3);
+
+    let groups = airport.extended_centrelines(&options);
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[1].name, "EGKK 26L");
+
+    let funnel = end.approach_funnel(&FunnelOptions::default());
+    assert_eq!(funnel.vertices.len(), 4);
+    assert!((funnel.vertices[0].distance_n_mi(&funnel.vertices[1]) - 2.0).abs() < 1e-2);
+    assert!((funnel.vertices[2].distance_n_mi(&funnel.vertices[3]) - 0.2).abs() < 1e-3);
+    let triangle = end.approach_funnel(&FunnelOptions {
+        threshold_half_width_n_mi: 0.0,
+        ..FunnelOptions::default()
+    });
+    assert_eq!(triangle.vertices[2], end.td_threshold_pos);
+    assert_eq!(
+        airport
+            .approach_funnels(&FunnelOptions::default())
+            .regions
+            .len(),
+        2
+    );
+
+    let glide_path = GlidePath::default();
+    assert_eq!(glide_path.altitude_ft(0.0), 50.0);
+    let at_ten = glide_path.altitude_ft(10.0);
+    assert!(at_ten > 3_230.0 && at_ten < 3_330.0);
+    let elevated = GlidePath {
+        threshold_elevation_ft: 196.0,
+        ..glide_path
+    };
+    assert_eq!(elevated.altitude_ft(10.0), at_ten + 196.0);
+}
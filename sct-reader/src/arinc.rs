@@ -0,0 +1,890 @@
+//! Reads navigation data from ARINC 424 files, so a sector can be brought up to date with the
+//! current AIRAC cycle.
+//!
+//! Only the primary records of the sections a sector file has an equivalent for are read: VHF
+//! navaids (`D`), NDBs (`DB` and `PN`), waypoints (`EA` and `PC`), airports (`PA`), runways (`PG`)
+//! and airways (`ER`). Everything else is skipped.
+
+use std::{collections::HashMap, fmt::Display, io::BufRead};
+
+use crate::{
+    diagnostic::{Diagnostic, Severity},
+    encoding::{DecodedLine, DecodedLines, Encoding},
+    error::Error,
+    line::{ColouredLine, LineGroup},
+    position::{MagneticHeading, Position, Valid},
+    sector::Sector,
+    waypoint::{
+        Airport, Fix, Ndb, RunwayEnd, RunwayModifier, RunwayStrip, Vor, Waypoint, MATCH_RADIUS_N_MI,
+    },
+    AirspaceClass, SectorResult,
+};
+
+/// ARINC 424 airport records have no tower frequency, so airports read from one are given the
+/// frequency sector files use for "none".
+pub const NO_FREQUENCY: &str = "199.998";
+
+/// Moves smaller than this are rounding between the two formats rather than real changes.
+const MOVE_TOLERANCE_M: f64 = 1.0;
+
+/// The navigation data read from an ARINC 424 file, in the same structures as a [`Sector`].
+#[derive(Debug, Clone, Default)]
+pub struct NavData {
+    /// Airports have a tower frequency of [`NO_FREQUENCY`] and airspace class G, which ARINC 424
+    /// airport records don't carry. Runway headings are magnetic where either end's record gives
+    /// a magnetic bearing. Where both ends only give true bearings, the heading is the true
+    /// bearing between the thresholds, uncorrected for variation.
+    pub airports: Vec<Airport>,
+    pub vors: Vec<Vor>,
+    pub ndbs: Vec<Ndb>,
+    /// Enroute and terminal waypoints.
+    pub fixes: Vec<Fix>,
+    pub low_airways: Vec<LineGroup<ColouredLine>>,
+    pub high_airways: Vec<LineGroup<ColouredLine>>,
+    pub non_critical_errors: Vec<Diagnostic>,
+}
+
+pub struct ArincReader<R: BufRead> {
+    source: R,
+    file_name: Option<String>,
+}
+impl<R: BufRead> ArincReader<R> {
+    pub fn new(source: R) -> Self {
+        Self {
+            source,
+            file_name: None,
+        }
+    }
+
+    /// Sets the file name reported in diagnostics.
+    pub fn with_file_name(mut self, file_name: impl Into<String>) -> Self {
+        self.file_name = Some(file_name.into());
+        self
+    }
+
+    pub fn try_read(self) -> SectorResult<NavData> {
+        let mut partial = PartialNavData {
+            file_name: self.file_name,
+            ..Default::default()
+        };
+        for (mut line_number, line) in DecodedLines::new(self.source, Encoding::Utf8).enumerate() {
+            line_number += 1;
+            let line = match line? {
+                DecodedLine::Text(line) => line,
+                DecodedLine::Invalid(line) => {
+                    partial.error(line_number, line.trim_end(), Error::InvalidEncoding);
+                    continue;
+                }
+            };
+            let line = line.trim_end();
+            if let Err(e) = partial.read_record(line_number, line) {
+                partial.error(line_number, line, e);
+            }
+        }
+        partial.pair_runways();
+        partial.join_airways();
+        Ok(partial.data)
+    }
+}
+
+#[derive(Default)]
+struct PartialNavData {
+    file_name: Option<String>,
+    data: NavData,
+    /// Positions of navaids and waypoints by identifier, ICAO region and section, for resolving
+    /// the fixes along airways.
+    positions: HashMap<(String, String, String), Position<Valid>>,
+    runway_ends: Vec<(String, RunwayRecord)>,
+    /// Airway records with their line number and source line, joined up once every fix is known.
+    airway_points: Vec<(usize, String, String, AirwayRecord)>,
+}
+impl PartialNavData {
+    fn error(&mut self, line_number: usize, line: &str, error: Error) {
+        self.data.non_critical_errors.push(
+            Diagnostic::new(line_number, line, None, Severity::Error, error)
+                .with_file(self.file_name.as_deref()),
+        );
+    }
+
+    fn read_record(&mut self, line_number: usize, line: &str) -> SectorResult<()> {
+        // Headers and comments are skipped.
+        if !(line.starts_with('S') || line.starts_with('T')) {
+            return Ok(());
+        }
+        if !line.is_ascii() {
+            return Err(Error::InvalidArincRecord);
+        }
+        let section = field(line, 5, 5);
+        let subsection = match section {
+            "P" => field(line, 13, 13),
+            _ => field(line, 6, 6),
+        };
+        // So are continuation records. Airway records keep their continuation number further
+        // along than everything else.
+        let continuation = match (section, subsection) {
+            ("E", "R") => field(line, 39, 39),
+            _ => field(line, 22, 22),
+        };
+        if !matches!(continuation, "0" | "1") {
+            return Ok(());
+        }
+        // Navaids and waypoints are looked up by the section and subsection that airway records
+        // give for their fixes, e.g. `PC` for a terminal waypoint.
+        let fix_section = format!("{}{}", section, subsection);
+        match (section, subsection) {
+            ("D", "") => self.read_vhf_navaid(line, &fix_section),
+            ("D", "B") | ("P", "N") => self.read_ndb(line, &fix_section),
+            ("E", "A") | ("P", "C") => self.read_waypoint(line, &fix_section),
+            ("P", "A") => self.read_airport(line),
+            ("P", "G") => self.read_runway(line),
+            ("E", "R") => {
+                let route = field(line, 14, 18).to_owned();
+                let record = AirwayRecord::read(line)?;
+                self.airway_points
+                    .push((line_number, line.to_owned(), route, record));
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn read_vhf_navaid(&mut self, line: &str, fix_section: &str) -> SectorResult<()> {
+        // ILS DMEs belong with the ILS rather than in [VOR].
+        if field(line, 29, 29) == "I" {
+            return Ok(());
+        }
+        let position = if field(line, 33, 41).is_empty() {
+            position(line, 56, 65)?
+        } else {
+            position(line, 33, 42)?
+        };
+        let frequency = field(line, 23, 27)
+            .parse::<f64>()
+            .map_err(|_| Error::InvalidArincRecord)?
+            / 100.0;
+        let identifier = field(line, 14, 17).to_owned();
+        self.remember(line, &identifier, fix_section, position);
+        self.data.vors.push(Vor {
+            identifier,
+            position,
+            frequency: format!("{:.3}", frequency),
+        });
+        Ok(())
+    }
+
+    fn read_ndb(&mut self, line: &str, fix_section: &str) -> SectorResult<()> {
+        let position = position(line, 33, 42)?;
+        let frequency = field(line, 23, 27)
+            .parse::<f64>()
+            .map_err(|_| Error::InvalidArincRecord)?
+            / 10.0;
+        let identifier = field(line, 14, 17).to_owned();
+        self.remember(line, &identifier, fix_section, position);
+        self.data.ndbs.push(Ndb {
+            identifier,
+            position,
+            frequency: format!("{:.3}", frequency),
+        });
+        Ok(())
+    }
+
+    fn read_waypoint(&mut self, line: &str, fix_section: &str) -> SectorResult<()> {
+        let position = position(line, 33, 42)?;
+        let identifier = field(line, 14, 18).to_owned();
+        self.remember(line, &identifier, fix_section, position);
+        self.data.fixes.push(Fix {
+            identifier,
+            position,
+        });
+        Ok(())
+    }
+
+    fn read_airport(&mut self, line: &str) -> SectorResult<()> {
+        self.data.airports.push(Airport {
+            identifier: field(line, 7, 10).to_owned(),
+            position: position(line, 33, 42)?,
+            tower_frequency: NO_FREQUENCY.to_owned(),
+            airspace_class: AirspaceClass::G,
+            runways: vec![],
+        });
+        Ok(())
+    }
+
+    fn read_runway(&mut self, line: &str) -> SectorResult<()> {
+        let designator = field(line, 14, 18)
+            .strip_prefix("RW")
+            .ok_or(Error::InvalidRunway)?;
+        let (number, modifier) = runway_designator(designator)?;
+        let bearing = field(line, 28, 31);
+        // A bearing ending in `T` is true rather than magnetic, and is left for the reciprocal to
+        // fill in.
+        let magnetic_hdg = match bearing.strip_suffix('T') {
+            Some(_) => None,
+            None => Some(MagneticHeading::wrapping(
+                bearing.parse::<f32>().map_err(|_| Error::InvalidRunway)? / 10.0,
+            )),
+        };
+        let record = RunwayRecord {
+            number,
+            modifier,
+            magnetic_hdg,
+            threshold: position(line, 33, 42)?,
+        };
+        self.runway_ends
+            .push((field(line, 7, 10).to_owned(), record));
+        Ok(())
+    }
+
+    fn remember(
+        &mut self,
+        line: &str,
+        identifier: &str,
+        fix_section: &str,
+        position: Position<Valid>,
+    ) {
+        let region = field(line, 20, 21).to_owned();
+        self.positions.insert(
+            (identifier.to_owned(), region, fix_section.to_owned()),
+            position,
+        );
+    }
+
+    /// ARINC 424 gives each runway end its own record, where sector files describe the whole
+    /// strip. Ends are paired with their reciprocal at the same airport.
+    fn pair_runways(&mut self) {
+        let mut ends = std::mem::take(&mut self.runway_ends);
+        while let Some((airport, end)) = ends.pop() {
+            let reciprocal = ends.iter().position(|(other_airport, other)| {
+                *other_airport == airport
+                    && other.number == reciprocal_number(end.number)
+                    && other.modifier == end.modifier.reciprocal()
+            });
+            let (Some(reciprocal), Some(airport)) = (
+                reciprocal,
+                self.data
+                    .airports
+                    .iter_mut()
+                    .find(|existing| existing.identifier == airport),
+            ) else {
+                continue;
+            };
+            let (_, other) = ends.remove(reciprocal);
+            // With no magnetic bearing at either end, the true bearing stands in for it, as
+            // documented on `NavData::airports`.
+            let heading = |end: &RunwayRecord, other: &RunwayRecord| {
+                end.magnetic_hdg
+                    .or_else(|| other.magnetic_hdg.map(|heading| heading.reciprocal()))
+                    .unwrap_or_else(|| {
+                        MagneticHeading::wrapping(
+                            end.threshold.initial_bearing(&other.threshold) as f32
+                        )
+                    })
+            };
+            let (end_a, end_b) = if other.number < end.number {
+                (other, end)
+            } else {
+                (end, other)
+            };
+            airport.runways.push(RunwayStrip {
+                end_a: RunwayEnd {
+                    number: end_a.number,
+                    td_threshold_pos: end_a.threshold,
+                    se_threshold_pos: end_b.threshold,
+                    modifier: end_a.modifier.clone(),
+                    magnetic_hdg: heading(&end_a, &end_b),
+                },
+                end_b: RunwayEnd {
+                    number: end_b.number,
+                    td_threshold_pos: end_b.threshold,
+                    se_threshold_pos: end_a.threshold,
+                    modifier: end_b.modifier.clone(),
+                    magnetic_hdg: heading(&end_b, &end_a),
+                },
+            });
+        }
+        for airport in &mut self.data.airports {
+            airport
+                .runways
+                .sort_by_key(|runway| (runway.end_a.number, runway.end_a.identifier()));
+        }
+    }
+
+    /// Joins the points of each airway in sequence order, breaking it wherever a point is marked
+    /// as the end of a continuous section or the sequence starts again.
+    fn join_airways(&mut self) {
+        let points = std::mem::take(&mut self.airway_points);
+        let mut previous: Option<(&str, &AirwayRecord, Position<Valid>)> = None;
+        for (line_number, line, route, point) in &points {
+            let key = (
+                point.fix.clone(),
+                point.region.clone(),
+                point.section.clone(),
+            );
+            let Some(position) = self.positions.get(&key).copied() else {
+                self.error(
+                    *line_number,
+                    line,
+                    Error::UnknownWaypoint(point.fix.clone()),
+                );
+                previous = None;
+                continue;
+            };
+            if let Some((previous_route, from, from_position)) = previous {
+                if previous_route == route && from.sequence < point.sequence && !from.end {
+                    let line = ColouredLine::new(from_position, position, None);
+                    if from.level != 'H' {
+                        add_airway_line(&mut self.data.low_airways, route, line.clone());
+                    }
+                    if from.level != 'L' {
+                        add_airway_line(&mut self.data.high_airways, route, line);
+                    }
+                }
+            }
+            previous = Some((route, point, position));
+        }
+    }
+}
+
+struct RunwayRecord {
+    number: u8,
+    modifier: RunwayModifier,
+    magnetic_hdg: Option<MagneticHeading>,
+    threshold: Position<Valid>,
+}
+
+struct AirwayRecord {
+    sequence: u32,
+    fix: String,
+    region: String,
+    section: String,
+    /// The last point of a continuous section of the airway.
+    end: bool,
+    /// `L` for low, `H` for high, or `B` for both.
+    level: char,
+}
+impl AirwayRecord {
+    fn read(line: &str) -> SectorResult<AirwayRecord> {
+        Ok(AirwayRecord {
+            sequence: field(line, 26, 29)
+                .parse()
+                .map_err(|_| Error::InvalidArincRecord)?,
+            fix: field(line, 30, 34).to_owned(),
+            region: field(line, 35, 36).to_owned(),
+            section: format!("{}{}", field(line, 37, 37), field(line, 38, 38)),
+            end: field(line, 41, 41) == "E",
+            level: field(line, 46, 46).chars().next().unwrap_or('B'),
+        })
+    }
+}
+
+fn add_airway_line(groups: &mut Vec<LineGroup<ColouredLine>>, route: &str, line: ColouredLine) {
+    match groups.iter_mut().find(|group| group.name == route) {
+        Some(group) => group.lines.push(line),
+        None => groups.push(LineGroup::new(route.to_owned(), vec![line])),
+    }
+}
+
+/// The text in the 1-based, inclusive column range, trimmed. Records are often written without
+/// their trailing blanks, so columns past the end of the line are empty.
+fn field(line: &str, first: usize, last: usize) -> &str {
+    line.get(first - 1..last.min(line.len()))
+        .unwrap_or("")
+        .trim()
+}
+
+/// A latitude such as `N51085300` starting at `lat_column`, and a longitude such as `W000112500`
+/// starting at `lon_column`. Seconds are given in hundredths.
+fn position(line: &str, lat_column: usize, lon_column: usize) -> SectorResult<Position<Valid>> {
+    let lat = coordinate(field(line, lat_column, lat_column + 8), 'N', 'S', 2)?;
+    let lon = coordinate(field(line, lon_column, lon_column + 9), 'E', 'W', 3)?;
+    Position::new(lat, lon).validate()
+}
+
+fn coordinate(
+    text: &str,
+    positive: char,
+    negative: char,
+    degree_digits: usize,
+) -> SectorResult<f64> {
+    let sign = match text.chars().next() {
+        Some(c) if c == positive => 1.0,
+        Some(c) if c == negative => -1.0,
+        _ => return Err(Error::InvalidCoordinate),
+    };
+    let digits = &text[1..];
+    if digits.len() != degree_digits + 6 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(Error::InvalidCoordinate);
+    }
+    let degs: f64 = digits[..degree_digits].parse().unwrap();
+    let mins: f64 = digits[degree_digits..degree_digits + 2].parse().unwrap();
+    let secs = digits[degree_digits + 2..].parse::<f64>().unwrap() / 100.0;
+    if mins >= 60.0 || secs >= 60.0 {
+        return Err(Error::InvalidCoordinate);
+    }
+    Ok(sign * (degs + mins / 60.0 + secs / 3600.0))
+}
+
+fn runway_designator(designator: &str) -> SectorResult<(u8, RunwayModifier)> {
+    let digits = designator.len().min(2);
+    let number = designator[..digits]
+        .parse::<u8>()
+        .ok()
+        .filter(|number| (1..=36).contains(number))
+        .ok_or(Error::InvalidRunway)?;
+    let modifier = match &designator[digits..] {
+        "" => RunwayModifier::None,
+        "L" => RunwayModifier::Left,
+        "R" => RunwayModifier::Right,
+        "C" => RunwayModifier::Centre,
+        "G" => RunwayModifier::Grass,
+        _ => return Err(Error::InvalidRunway),
+    };
+    Ok((number, modifier))
+}
+
+fn reciprocal_number(number: u8) -> u8 {
+    if number > 18 {
+        number - 18
+    } else {
+        number + 18
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavDataKind {
+    Airport,
+    Vor,
+    Ndb,
+    Fix,
+    Runway,
+}
+impl Display for NavDataKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Airport => "Airport",
+                Self::Vor => "VOR",
+                Self::Ndb => "NDB",
+                Self::Fix => "Fix",
+                Self::Runway => "Runway",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Moved {
+        from: Position<Valid>,
+        to: Position<Valid>,
+    },
+    Frequency {
+        from: String,
+        to: String,
+    },
+    /// Nothing with the same identifier was found nearby in the ARINC 424 data, so the item was
+    /// left as it was.
+    NotFound,
+}
+
+/// Something [`Sector::update_from_arinc`] changed, or couldn't find.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavDataChange {
+    pub kind: NavDataKind,
+    /// Runways are identified by their airport and end, e.g. `EGKK 08R`.
+    pub identifier: String,
+    pub change: Change,
+}
+impl Display for NavDataChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} ", self.kind, self.identifier)?;
+        match &self.change {
+            Change::Moved { from, to } => write!(f, "{}", from.describe_move(to)),
+            Change::Frequency { from, to } => {
+                write!(f, "frequency changed from {} to {}", from, to)
+            }
+            Change::NotFound => write!(f, "is not in the ARINC 424 data"),
+        }
+    }
+}
+
+impl Sector {
+    /// Updates the positions and frequencies of the airports, runways, VORs, NDBs and fixes
+    /// already in the sector from newer ARINC 424 data. Nothing is added or removed, and lines
+    /// drawn in the sector are left where they are.
+    pub fn update_from_arinc(&mut self, data: &NavData) -> Vec<NavDataChange> {
+        let mut changes = vec![];
+        for airport in &mut self.airports {
+            let Some(update) = nearest(&data.airports, airport) else {
+                changes.push(not_found(NavDataKind::Airport, &airport.identifier));
+                continue;
+            };
+            update_position(
+                &mut changes,
+                NavDataKind::Airport,
+                &airport.identifier,
+                &mut airport.position,
+                update.position,
+            );
+            for runway in &mut airport.runways {
+                update_runway(&mut changes, &airport.identifier, runway, &update.runways);
+            }
+        }
+        for vor in &mut self.vors {
+            match nearest(&data.vors, vor) {
+                Some(update) => {
+                    let identifier = &vor.identifier;
+                    update_position(
+                        &mut changes,
+                        NavDataKind::Vor,
+                        identifier,
+                        &mut vor.position,
+                        update.position,
+                    );
+                    update_frequency(
+                        &mut changes,
+                        NavDataKind::Vor,
+                        identifier,
+                        &mut vor.frequency,
+                        &update.frequency,
+                    );
+                }
+                None => changes.push(not_found(NavDataKind::Vor, &vor.identifier)),
+            }
+        }
+        for ndb in &mut self.ndbs {
+            match nearest(&data.ndbs, ndb) {
+                Some(update) => {
+                    let identifier = &ndb.identifier;
+                    update_position(
+                        &mut changes,
+                        NavDataKind::Ndb,
+                        identifier,
+                        &mut ndb.position,
+                        update.position,
+                    );
+                    update_frequency(
+                        &mut changes,
+                        NavDataKind::Ndb,
+                        identifier,
+                        &mut ndb.frequency,
+                        &update.frequency,
+                    );
+                }
+                None => changes.push(not_found(NavDataKind::Ndb, &ndb.identifier)),
+            }
+        }
+        for fix in &mut self.fixes {
+            match nearest(&data.fixes, fix) {
+                Some(update) => update_position(
+                    &mut changes,
+                    NavDataKind::Fix,
+                    &fix.identifier,
+                    &mut fix.position,
+                    update.position,
+                ),
+                None => changes.push(not_found(NavDataKind::Fix, &fix.identifier)),
+            }
+        }
+        changes
+    }
+}
+
+/// The closest item in `candidates` with the same identifier as `existing`, if one is close
+/// enough to be the same thing.
+fn nearest<'a, W: Waypoint>(candidates: &'a [W], existing: &W) -> Option<&'a W> {
+    let position = existing.position();
+    candidates
+        .iter()
+        .filter(|candidate| candidate.identifier() == existing.identifier())
+        .map(|candidate| (candidate, position.distance_n_mi(&candidate.position())))
+        .filter(|(_, distance)| *distance <= MATCH_RADIUS_N_MI)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(candidate, _)| candidate)
+}
+
+fn not_found(kind: NavDataKind, identifier: &str) -> NavDataChange {
+    NavDataChange {
+        kind,
+        identifier: identifier.to_owned(),
+        change: Change::NotFound,
+    }
+}
+
+fn update_position(
+    changes: &mut Vec<NavDataChange>,
+    kind: NavDataKind,
+    identifier: &str,
+    position: &mut Position<Valid>,
+    update: Position<Valid>,
+) {
+    if position.distance_m(&update) >= MOVE_TOLERANCE_M {
+        changes.push(NavDataChange {
+            kind,
+            identifier: identifier.to_owned(),
+            change: Change::Moved {
+                from: *position,
+                to: update,
+            },
+        });
+        *position = update;
+    }
+}
+
+/// Frequencies are compared by value, so `117.9` and `117.900` are the same.
+fn update_frequency(
+    changes: &mut Vec<NavDataChange>,
+    kind: NavDataKind,
+    identifier: &str,
+    frequency: &mut String,
+    update: &str,
+) {
+    let same = match (frequency.parse::<f64>(), update.parse::<f64>()) {
+        (Ok(a), Ok(b)) => (a - b).abs() < 0.0005,
+        _ => frequency == update,
+    };
+    if !same {
+        changes.push(NavDataChange {
+            kind,
+            identifier: identifier.to_owned(),
+            change: Change::Frequency {
+                from: frequency.clone(),
+                to: update.to_owned(),
+            },
+        });
+        *frequency = update.to_owned();
+    }
+}
+
+fn update_runway(
+    changes: &mut Vec<NavDataChange>,
+    airport: &str,
+    runway: &mut RunwayStrip,
+    updates: &[RunwayStrip],
+) {
+    let (a, b) = (runway.end_a.identifier(), runway.end_b.identifier());
+    let update = updates.iter().find_map(|update| {
+        let (update_a, update_b) = (update.end_a.identifier(), update.end_b.identifier());
+        if update_a == a && update_b == b {
+            Some((&update.end_a, &update.end_b))
+        } else if update_a == b && update_b == a {
+            Some((&update.end_b, &update.end_a))
+        } else {
+            None
+        }
+    });
+    let Some((update_a, update_b)) = update else {
+        changes.push(not_found(
+            NavDataKind::Runway,
+            &format!("{} {}", airport, a),
+        ));
+        return;
+    };
+    for (end, update) in [(&mut runway.end_a, update_a), (&mut runway.end_b, update_b)] {
+        let identifier = format!("{} {}", airport, end.identifier());
+        update_position(
+            changes,
+            NavDataKind::Runway,
+            &identifier,
+            &mut end.td_threshold_pos,
+            update.td_threshold_pos,
+        );
+    }
+    runway.end_a.se_threshold_pos = runway.end_b.td_threshold_pos;
+    runway.end_b.se_threshold_pos = runway.end_a.td_threshold_pos;
+}
+
+#[test]
+fn test_arinc() {
+    use crate::reader::test_sector;
+
+    /// A 132 column record with each field starting at its 1-based column.
+    fn record(fields: &[(usize, &str)]) -> String {
+        let mut line = vec![b' '; 132];
+        for (column, text) in fields {
+            line[column - 1..column - 1 + text.len()].copy_from_slice(text.as_bytes());
+        }
+        String::from_utf8(line).unwrap()
+    }
+    let vor = |identifier, frequency, lat, lon| {
+        record(&[
+            (1, "SEURD"),
+            (14, identifier),
+            (20, "EG"),
+            (22, "1"),
+            (23, frequency),
+            (28, "VDHW"),
+            (33, lat),
+            (42, lon),
+        ])
+    };
+    let fix = |identifier, lat, lon| {
+        record(&[
+            (1, "SEUREA"),
+            (7, "ENRT"),
+            (14, identifier),
+            (20, "EG"),
+            (22, "1"),
+            (33, lat),
+            (42, lon),
+        ])
+    };
+    let runway = |designator, bearing, lat, lon| {
+        record(&[
+            (1, "SEURP"),
+            (7, "EGKK"),
+            (11, "EG"),
+            (13, "G"),
+            (14, designator),
+            (22, "1"),
+            (23, "10879"),
+            (28, bearing),
+            (33, lat),
+            (42, lon),
+        ])
+    };
+    let airway = |route, sequence, fix, section, description, level| {
+        record(&[
+            (1, "SEURER"),
+            (14, route),
+            (26, sequence),
+            (30, fix),
+            (35, "EG"),
+            (37, section),
+            (39, "0"),
+            (40, description),
+            (46, level),
+        ])
+    };
+    let arinc = [
+        "HDR01 An example file".to_owned(),
+        vor("MAY", "11795", "N51010250", "E000065800"),
+        record(&[
+            (1, "SEURDB"),
+            (14, "LAM"),
+            (20, "EG"),
+            (22, "1"),
+            (23, "03620"),
+            (33, "N51390000"),
+            (42, "E000090000"),
+        ]),
+        fix("BIG", "N51194900", "E000024600"),
+        fix("KONAN", "N51195400", "E001570000"),
+        // A continuation record, which is skipped.
+        record(&[(1, "SEUREA"), (14, "KONAN"), (20, "EG"), (22, "2")]),
+        record(&[
+            (1, "SEURP"),
+            (7, "EGKK"),
+            (11, "EG"),
+            (13, "A"),
+            (22, "0"),
+            (33, "N51085300"),
+            (42, "W000112500"),
+        ]),
+        runway("RW08R", "0773", "N51084802", "W000125793"),
+        runway("RW26L", "2573", "N51092165", "W000093199"),
+        airway("L9", "0010", "MAY  ", "D ", "  ", "B"),
+        // An essential waypoint, which does not end the airway.
+        airway("L9", "0020", "BIG  ", "EA", "E ", "H"),
+        airway("L9", "0030", "KONAN", "EA", "EE", "H"),
+        airway("L9", "0040", "NOPE ", "EA", "  ", "H"),
+        vor("BAD", "11000", "N51996000", "E000000000"),
+        // A terminal NDB and a terminal waypoint sharing an identifier, told apart by subsection.
+        record(&[
+            (1, "SEURP"),
+            (7, "EGKK"),
+            (11, "EG"),
+            (13, "N"),
+            (14, "GY"),
+            (20, "EG"),
+            (22, "1"),
+            (23, "03380"),
+            (33, "N51070000"),
+            (42, "W000100000"),
+        ]),
+        record(&[
+            (1, "SEURP"),
+            (7, "EGKK"),
+            (11, "EG"),
+            (13, "C"),
+            (14, "GY"),
+            (20, "EG"),
+            (22, "1"),
+            (33, "N51100000"),
+            (42, "W000050000"),
+        ]),
+        airway("M1", "0010", "GY   ", "PN", "  ", "B"),
+        airway("M1", "0020", "GY   ", "PC", "EE", "B"),
+    ]
+    .join("\n");
+    let data = ArincReader::new(arinc.as_bytes()).try_read().unwrap();
+
+    assert_eq!(data.vors.len(), 1);
+    assert_eq!(data.vors[0].frequency, "117.950");
+    assert!((data.vors[0].position.lat - 51.0173611).abs() < 1e-6);
+    assert_eq!(data.ndbs[0].frequency, "362.000");
+    assert_eq!(data.fixes.len(), 3);
+    let runways = &data.airports[0].runways;
+    assert_eq!(runways.len(), 1);
+    assert_eq!(runways[0].end_a.identifier(), "08R");
+    assert_eq!(runways[0].end_b.identifier(), "26L");
+    assert_eq!(
+        runways[0].end_a.se_threshold_pos,
+        runways[0].end_b.td_threshold_pos
+    );
+    assert_eq!(runways[0].end_b.magnetic_hdg.value(), 257.3);
+    assert_eq!(data.low_airways[0].lines.len(), 1);
+    assert_eq!(data.high_airways[0].lines.len(), 2);
+    let terminal = &data.low_airways[1];
+    assert_eq!(terminal.name, "M1");
+    assert_eq!(terminal.lines[0].line.start, data.ndbs[1].position);
+    assert_eq!(terminal.lines[0].line.end, data.fixes[2].position);
+    // The unknown fix and the invalid coordinate are reported.
+    let errors = data
+        .non_critical_errors
+        .iter()
+        .map(|diagnostic| (diagnostic.line_number, diagnostic.error.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        vec![
+            (14, Error::InvalidCoordinate),
+            (13, Error::UnknownWaypoint("NOPE".to_owned())),
+        ]
+    );
+
+    let mut sector = test_sector(
+        "ARINC test",
+        "\
+[VOR]
+MAY 117.900 N051.01.02.500 E000.06.58.000
+[NDB]
+LAM 362.0 N051.39.00.000 E000.09.00.000
+[AIRPORT]
+EGKK 126.830 N051.08.53.000 W000.11.25.000 D
+[RUNWAY]
+26L 08R 257 077 N051.09.21.650 W000.09.31.990 N051.08.49.000 W000.12.57.930 EGKK
+[FIXES]
+BIG N051.19.49.000 E000.02.46.000
+GONE N051.00.00.000 E000.00.00.000
+",
+    );
+    let changes = sector
+        .update_from_arinc(&data)
+        .iter()
+        .map(NavDataChange::to_string)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        changes,
+        vec![
+            "Runway EGKK 08R moved 30 m from N051.08.49.000 W000.12.57.930 to N051.08.48.020 W000.12.57.930",
+            "VOR MAY frequency changed from 117.900 to 117.950",
+            "Fix GONE is not in the ARINC 424 data",
+        ]
+    );
+    let runway = &sector.airports[0].runways[0];
+    assert_eq!(runway.end_a.se_threshold_pos, runway.end_b.td_threshold_pos);
+    assert_eq!(sector.vors[0].frequency, "117.950");
+}
@@ -0,0 +1,60 @@
+//! Reports what changed between two sector files, e.g. between two AIRAC releases.
+//!
+//! ```text
+//! sct-diff [--json] <old.sct> <new.sct>
+//! ```
+//!
+//! Exits with 0 if the sectors are the same, 1 if they differ and 2 if either can't be read.
+
+use std::{fs::File, io::BufReader, path::Path, process::ExitCode};
+
+use sct_reader::{reader::SctReader, sector::Sector};
+
+const USAGE: &str = "usage: sct-diff [--json] <old.sct> <new.sct>";
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let (json, old, new) = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["--json", old, new] | [old, new, "--json"] => (true, old, new),
+        [old, new] if !old.starts_with("--") && !new.starts_with("--") => (false, old, new),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let (old, new) = match (read_sector(old), read_sector(new)) {
+        (Ok(old), Ok(new)) => (old, new),
+        (Err(message), _) | (_, Err(message)) => {
+            eprintln!("{}", message);
+            return ExitCode::from(2);
+        }
+    };
+
+    let diff = old.diff(&new);
+    if json {
+        println!("{}", diff.to_json());
+    } else {
+        print!("{}", diff);
+    }
+    if diff.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    }
+}
+
+fn read_sector(input: &str) -> Result<Sector, String> {
+    let file = File::open(input).map_err(|e| format!("unable to open {}: {}", input, e))?;
+    let file_name = Path::new(input)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let sector = SctReader::new(BufReader::new(file))
+        .with_file_name(file_name)
+        .try_read()
+        .map_err(|e| format!("unable to read {}: {}", input, e))?;
+    for diagnostic in &sector.non_critical_errors {
+        eprintln!("{}", diagnostic);
+    }
+    Ok(sector)
+}
@@ -0,0 +1,70 @@
+//! Checks a sector file for mistakes that don't stop it loading.
+//!
+//! ```text
+//! sct-lint [--deny-warnings] <sector.sct>
+//! ```
+//!
+//! Exits with 1 if anything at error severity is found, or any warning with `--deny-warnings`.
+
+use std::{fs::File, io::BufReader, path::Path, process::ExitCode};
+
+use sct_reader::{diagnostic::Severity, reader::SctReader};
+
+const USAGE: &str = "usage: sct-lint [--deny-warnings] <sector.sct>";
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let (deny_warnings, input) = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["--deny-warnings", input] => (true, input),
+        [input] if !input.starts_with("--") => (false, input),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let file = match File::open(input) {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("unable to open {}: {}", input, e);
+            return ExitCode::from(2);
+        }
+    };
+    let file_name = Path::new(input)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let sector = match SctReader::new(BufReader::new(file))
+        .with_file_name(file_name)
+        .try_read()
+    {
+        Ok(sector) => sector,
+        Err(e) => {
+            eprintln!("unable to read {}: {}", input, e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let lints = sector.lint();
+    for lint in &lints {
+        println!("{}[{}]: {}", lint.severity(), lint.name(), lint);
+        // Lints from reading the file point at the line, below the heading already printed.
+        if let Some(diagnostic) = lint.diagnostic() {
+            for line in diagnostic.render().lines().skip(1) {
+                println!("{}", line);
+            }
+        }
+    }
+    let errors = lints
+        .iter()
+        .filter(|lint| lint.severity() == Severity::Error)
+        .count();
+    let warnings = lints.len() - errors;
+    println!("{} errors, {} warnings", errors, warnings);
+
+    if errors > 0 || (deny_warnings && warnings > 0) {
+        ExitCode::from(1)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
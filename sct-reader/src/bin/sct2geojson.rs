@@ -0,0 +1,74 @@
+//! Converts a sector file to GeoJSON, for reviewing sector changes in GIS tools.
+//!
+//! ```text
+//! sct2geojson <sector.sct> [output.geojson]
+//! sct2geojson <sector.sct> --layers <directory>
+//! ```
+//!
+//! The first form writes a single `FeatureCollection` to the output file, or to stdout if none is
+//! given. The second writes one file per layer into the directory, e.g. `fixes.geojson`.
+
+use std::{fs::File, io::BufReader, path::Path, process::ExitCode};
+
+use sct_reader::reader::SctReader;
+
+const USAGE: &str = "usage: sct2geojson <sector.sct> [output.geojson]
+       sct2geojson <sector.sct> --layers <directory>";
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let result = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        [input] => convert(input, None),
+        [input, "--layers", directory] => convert_layers(input, directory),
+        [input, output] if !output.starts_with("--") => convert(input, Some(output)),
+        _ => Err(USAGE.to_owned()),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn read_sector(input: &str) -> Result<sct_reader::sector::Sector, String> {
+    let file = File::open(input).map_err(|e| format!("unable to open {}: {}", input, e))?;
+    let file_name = Path::new(input)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let sector = SctReader::new(BufReader::new(file))
+        .with_file_name(file_name)
+        .try_read()
+        .map_err(|e| format!("unable to read {}: {}", input, e))?;
+    for diagnostic in &sector.non_critical_errors {
+        eprintln!("{}", diagnostic);
+    }
+    Ok(sector)
+}
+
+fn convert(input: &str, output: Option<&str>) -> Result<(), String> {
+    let json = read_sector(input)?.to_geojson();
+    match output {
+        Some(output) => {
+            std::fs::write(output, json).map_err(|e| format!("unable to write {}: {}", output, e))
+        }
+        None => {
+            println!("{}", json);
+            Ok(())
+        }
+    }
+}
+
+fn convert_layers(input: &str, directory: &str) -> Result<(), String> {
+    let sector = read_sector(input)?;
+    std::fs::create_dir_all(directory)
+        .map_err(|e| format!("unable to create {}: {}", directory, e))?;
+    for (layer, json) in sector.to_geojson_layers() {
+        let path = Path::new(directory).join(format!("{}.geojson", layer));
+        std::fs::write(&path, json)
+            .map_err(|e| format!("unable to write {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
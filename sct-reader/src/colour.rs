@@ -1,16 +1,36 @@
-use std::str::FromStr;
+use std::{collections::HashMap, str::FromStr};
 
-use crate::error::Error;
+use crate::{
+    error::Error,
+    sector::{LineCategory, Sector},
+};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// An RGB colour with an alpha channel. Sector files can't give an alpha, so everything read from
+/// one is opaque; translucency comes from overlays or from themes applied with a [`Palette`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Colour {
     pub r: u8,
     pub g: u8,
     pub b: u8,
+    /// From 0, fully transparent, to 255, opaque.
+    pub a: u8,
 }
 impl Colour {
     pub const fn new(r: u8, g: u8, b: u8) -> Self {
-        Self { r, g, b }
+        Self { r, g, b, a: 255 }
+    }
+    pub const fn with_alpha(self, a: u8) -> Self {
+        Self { a, ..self }
+    }
+    pub const fn is_opaque(&self) -> bool {
+        self.a == 255
+    }
+}
+/// The sector file encoding, which has no room for the alpha channel.
+impl From<Colour> for u32 {
+    fn from(value: Colour) -> Self {
+        value.r as u32 | (value.g as u32) << 8 | (value.b as u32) << 16
     }
 }
 impl From<u32> for Colour {
@@ -18,7 +38,7 @@ impl From<u32> for Colour {
         let r = (value & 0xFF) as u8;
         let g = ((value >> 8) & 0xFF) as u8;
         let b = ((value >> 16) & 0xFF) as u8;
-        Self { r, g, b }
+        Self::new(r, g, b)
     }
 }
 impl FromStr for Colour {
@@ -29,3 +49,137 @@ impl FromStr for Colour {
             .map(Self::from)
     }
 }
+
+/// Colours by `#define` name, compared without regard to case as in sector files.
+///
+/// Lines, regions and labels remember the name their colour was given by, so a palette taken from
+/// a sector with [`Sector::palette`] can be changed and applied back with
+/// [`Sector::apply_palette`] to recolour everything drawn with those names.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Palette {
+    colours: HashMap<String, Colour>,
+}
+impl Palette {
+    pub fn new() -> Palette {
+        Palette::default()
+    }
+    pub fn with_colour(mut self, name: &str, colour: Colour) -> Self {
+        self.set(name, colour);
+        self
+    }
+    /// Sets the colour for `name`, returning the one it replaces.
+    pub fn set(&mut self, name: &str, colour: Colour) -> Option<Colour> {
+        self.colours.insert(name.to_lowercase(), colour)
+    }
+    pub fn get(&self, name: &str) -> Option<Colour> {
+        self.colours.get(&name.to_lowercase()).copied()
+    }
+    pub fn len(&self) -> usize {
+        self.colours.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.colours.is_empty()
+    }
+}
+
+impl Sector {
+    /// The sector's `#define`d colours.
+    pub fn palette(&self) -> Palette {
+        Palette {
+            colours: self.colours.clone(),
+        }
+    }
+
+    /// Recolours every line, region and label whose colour was given by a name in `palette`, and
+    /// updates those `#define`s. Colours given as numbers, and names the palette doesn't have, are
+    /// left alone.
+    pub fn apply_palette(&mut self, palette: &Palette) {
+        let recolour = |name: Option<&str>| name.and_then(|name| palette.get(name));
+        for (name, colour) in self.colours.iter_mut() {
+            if let Some(new) = palette.get(name) {
+                *colour = new;
+            }
+        }
+        for category in LineCategory::ALL {
+            for line in self
+                .line_groups_mut(category)
+                .iter_mut()
+                .flat_map(|group| group.lines.iter_mut())
+            {
+                if let Some(colour) = recolour(line.colour_name.as_deref()) {
+                    line.colour = Some(colour);
+                }
+            }
+        }
+        for region in self
+            .regions
+            .iter_mut()
+            .flat_map(|group| group.regions.iter_mut())
+        {
+            if let Some(colour) = recolour(region.colour_name.as_deref()) {
+                region.colour = colour;
+            }
+        }
+        for label in self
+            .labels
+            .iter_mut()
+            .flat_map(|group| group.labels.iter_mut())
+        {
+            if let Some(colour) = recolour(label.colour_name.as_deref()) {
+                label.colour = colour;
+            }
+        }
+    }
+}
+
+#[test]
+fn test_palette() {
+    use crate::reader::test_sector;
+
+    let mut sector = test_sector(
+        "Palette test",
+        "\
+#define COLOR_AoRcenter1 8421504
+#define COLOR_Coast 16711680
+[ARTCC]
+London N051.00.00.000 W001.00.00.000 N052.00.00.000 W001.00.00.000 color_aorcenter1
+[GEO]
+Coast N051.00.00.000 E000.00.00.000 N051.01.00.000 E000.01.00.000 COLOR_Coast
+Coast N051.01.00.000 E000.01.00.000 N051.02.00.000 E000.02.00.000 16711680
+[REGIONS]
+REGIONNAME Sector
+COLOR_AoRcenter1 N051.00.00.000 E000.00.00.000
+N051.01.00.000 E000.01.00.000
+N051.01.00.000 E000.00.00.000
+[LABELS]
+\"London\" N051.30.00.000 W000.10.00.000 COLOR_AoRcenter1
+",
+    );
+    let artcc = &sector.artcc_entries[0].lines[0];
+    assert_eq!(artcc.colour_name.as_deref(), Some("COLOR_AoRcenter1"));
+    assert_eq!(sector.geo_entries[0].lines[1].colour_name, None);
+
+    let palette = sector.palette();
+    assert_eq!(palette.len(), 2);
+    assert_eq!(palette.get("COLOR_COAST"), Some(Colour::from(16711680)));
+
+    let dark = Colour::new(20, 30, 40).with_alpha(128);
+    sector.apply_palette(
+        &Palette::new()
+            .with_colour("COLOR_AoRcenter1", dark)
+            .with_colour("COLOR_Unknown", Colour::new(1, 2, 3)),
+    );
+    assert_eq!(sector.artcc_entries[0].lines[0].colour, Some(dark));
+    assert_eq!(sector.regions[0].regions[0].colour, dark);
+    assert_eq!(sector.labels[0].labels[0].colour, dark);
+    assert_eq!(sector.colours["color_aorcenter1"], dark);
+    assert!(!sector.colours.contains_key("color_unknown"));
+    // A colour given as a number stays as it is, even when it matches a name.
+    assert_eq!(
+        sector.geo_entries[0].lines[1].colour,
+        Some(Colour::from(16711680))
+    );
+    assert!(!dark.is_opaque());
+    assert_eq!(u32::from(dark), u32::from(Colour::new(20, 30, 40)));
+}
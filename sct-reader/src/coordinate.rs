@@ -0,0 +1,481 @@
+use std::fmt::Display;
+
+use crate::{
+    error::Error,
+    position::{lat_to_es, lon_to_es, split_degrees, Position, Valid},
+    SectorResult,
+};
+
+/// The notations a latitude and longitude pair can be read from and written in. Each example
+/// below is the same position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CoordinateFormat {
+    /// `N051.07.25.010 W002.39.13.334`, as used in `.sct` and `.ese` files.
+    EuroScope,
+    /// `N51.07.25.010 W2.39.13.334`, the dotted layout without the zero padding on the degrees,
+    /// as written by VRC and older sector tools. Reading also accepts fewer digits elsewhere,
+    /// e.g. `N51.7.25`, but always needs the seconds so it isn't mistaken for decimal degrees.
+    Vrc,
+    /// `51.123614 -2.653704`. Reading also accepts a comma between the two, a trailing `°` and a
+    /// hemisphere letter in place of the sign, e.g. `51.123614N, 2.653704W`.
+    Decimal,
+    /// `51°07'25.0"N 002°39'13.3"W`. Reading also accepts `′` and `″` and the hemisphere letter
+    /// in front.
+    Dms,
+    /// `5107N00239W`, to the nearest minute, as used in ICAO flight plans.
+    IcaoMinutes,
+    /// `510725N0023913W`, the ICAO layout to the nearest second.
+    IcaoSeconds,
+}
+impl CoordinateFormat {
+    /// Every format, in the order [`CoordinateFormat::detect`] tries them.
+    pub const ALL: [CoordinateFormat; 6] = [
+        CoordinateFormat::EuroScope,
+        CoordinateFormat::Vrc,
+        CoordinateFormat::Decimal,
+        CoordinateFormat::Dms,
+        CoordinateFormat::IcaoMinutes,
+        CoordinateFormat::IcaoSeconds,
+    ];
+
+    /// Works out which notation `text` is written in. This doesn't check the position is on the
+    /// globe, so use [`CoordinateFormat::parse`] for that.
+    pub fn detect(text: &str) -> Option<CoordinateFormat> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.read_pair(text).is_some())
+    }
+
+    /// Reads a latitude and longitude pair written in this notation.
+    pub fn parse(&self, text: &str) -> SectorResult<Position<Valid>> {
+        let (lat, lon) = self.read_pair(text).ok_or(Error::InvalidCoordinate)?;
+        Position::new(lat, lon).validate()
+    }
+
+    /// Reads a latitude on its own, e.g. a single field of a sector file line.
+    pub fn parse_lat(&self, text: &str) -> SectorResult<f64> {
+        self.read(text, Axis::Lat)
+            .filter(|lat| (-90.0..=90.0).contains(lat))
+            .ok_or(Error::InvalidCoordinate)
+    }
+
+    /// Reads a longitude on its own, e.g. a single field of a sector file line.
+    pub fn parse_lon(&self, text: &str) -> SectorResult<f64> {
+        self.read(text, Axis::Lon)
+            .filter(|lon| (-180.0..=180.0).contains(lon))
+            .ok_or(Error::InvalidCoordinate)
+    }
+
+    pub fn format(&self, position: &Position<Valid>) -> String {
+        let (lat, lon) = (position.lat, position.lon);
+        match self {
+            Self::EuroScope => format!("{} {}", lat_to_es(lat), lon_to_es(lon)),
+            Self::Vrc => format!("{} {}", vrc(lat, Axis::Lat), vrc(lon, Axis::Lon)),
+            Self::Decimal => format!("{:.6} {:.6}", lat, lon),
+            Self::Dms => format!("{} {}", dms(lat, Axis::Lat), dms(lon, Axis::Lon)),
+            Self::IcaoMinutes | Self::IcaoSeconds => {
+                format!("{}{}", self.icao(lat, Axis::Lat), self.icao(lon, Axis::Lon))
+            }
+        }
+    }
+
+    fn read_pair(&self, text: &str) -> Option<(f64, f64)> {
+        let text = text.trim();
+        let (lat, lon) = match self {
+            // The ICAO notations run the two together, so split after the latitude's hemisphere.
+            Self::IcaoMinutes | Self::IcaoSeconds => {
+                let split = text.find(['N', 'S'])? + 1;
+                text.split_at(split)
+            }
+            _ => split_pair(text)?,
+        };
+        Some((self.read(lat, Axis::Lat)?, self.read(lon, Axis::Lon)?))
+    }
+
+    fn read(&self, text: &str, axis: Axis) -> Option<f64> {
+        match self {
+            Self::EuroScope => read_euroscope(text, axis),
+            Self::Vrc => read_vrc(text, axis),
+            Self::Decimal => read_decimal(text, axis),
+            Self::Dms => read_dms(text, axis),
+            Self::IcaoMinutes => read_icao(text, axis, false),
+            Self::IcaoSeconds => read_icao(text, axis, true),
+        }
+    }
+
+    fn icao(&self, value: f64, axis: Axis) -> String {
+        let hemisphere = axis.hemisphere(value);
+        let width = axis.degree_digits();
+        if *self == Self::IcaoSeconds {
+            let (degs, mins, secs) = split_degrees(value.abs(), 60);
+            format!("{degs:0width$}{mins:02}{secs:02}{hemisphere}")
+        } else {
+            let (degs, mins, _) = split_degrees(value.abs(), 1);
+            format!("{degs:0width$}{mins:02}{hemisphere}")
+        }
+    }
+}
+impl Display for CoordinateFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::EuroScope => "EuroScope",
+                Self::Vrc => "VRC",
+                Self::Decimal => "decimal degrees",
+                Self::Dms => "degrees, minutes and seconds",
+                Self::IcaoMinutes => "ICAO (minutes)",
+                Self::IcaoSeconds => "ICAO (seconds)",
+            }
+        )
+    }
+}
+
+/// The notations found in sector files, in the order they're tried.
+const SECTOR_FILE_FORMATS: [CoordinateFormat; 3] = [
+    CoordinateFormat::EuroScope,
+    CoordinateFormat::Vrc,
+    CoordinateFormat::Decimal,
+];
+
+/// Reads a latitude and longitude from two fields of a sector file, in any of the notations
+/// sector files are written in, and gives the latitude's notation.
+pub(crate) fn read_sector_pair(lat: &str, lon: &str) -> SectorResult<(Position, CoordinateFormat)> {
+    let (lat, format) = read_sector_field(lat, Axis::Lat)?;
+    let (lon, _) = read_sector_field(lon, Axis::Lon)?;
+    Ok((Position::new(lat, lon), format))
+}
+
+/// Reads a single latitude or longitude field of a sector file.
+pub(crate) fn read_sector_field(text: &str, axis: Axis) -> SectorResult<(f64, CoordinateFormat)> {
+    for format in SECTOR_FILE_FORMATS {
+        // Decimal degrees need a decimal point, so that an identifier like `E12` isn't taken for
+        // a coordinate.
+        if format == CoordinateFormat::Decimal && !text.contains('.') {
+            continue;
+        }
+        if let Some(value) = format.read(text, axis) {
+            return Ok((value, format));
+        }
+    }
+    if minutes_or_seconds_out_of_range(text, axis) {
+        return Err(Error::MinutesOrSecondsOutOfRange(text.to_owned()));
+    }
+    Err(Error::InvalidPosition)
+}
+
+/// Writes a single latitude or longitude field of a sector file in `format`, precisely enough
+/// that [`read_sector_field`] gives back the same value. Sector files can only hold the EuroScope,
+/// VRC and decimal notations, so the others are written in EuroScope notation.
+pub(crate) fn write_sector_field(value: f64, axis: Axis, format: CoordinateFormat) -> String {
+    match (format, axis) {
+        (CoordinateFormat::Vrc, _) => vrc(value, axis),
+        (CoordinateFormat::Decimal, _) => {
+            // `Display` gives the shortest digits that parse back to the same value, but leaves
+            // off the decimal point that tells decimal degrees apart from an identifier.
+            let text = value.to_string();
+            if text.contains('.') {
+                text
+            } else {
+                format!("{text}.0")
+            }
+        }
+        (_, Axis::Lat) => lat_to_es(value),
+        (_, Axis::Lon) => lon_to_es(value),
+    }
+}
+
+/// Whether `text` is laid out like a dotted coordinate, but has 60 or more minutes or seconds.
+fn minutes_or_seconds_out_of_range(text: &str, axis: Axis) -> bool {
+    let Some(first) = text.chars().next() else {
+        return false;
+    };
+    if axis.sign_of(first).is_none() {
+        return false;
+    }
+    let mut sections = text[first.len_utf8()..].splitn(3, '.');
+    let (Some(degs), Some(mins), Some(secs)) = (sections.next(), sections.next(), sections.next())
+    else {
+        return false;
+    };
+    match (unsigned(degs), unsigned(mins), unsigned(secs)) {
+        (Some(_), Some(mins), Some(secs)) => mins >= 60.0 || secs >= 60.0,
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Axis {
+    Lat,
+    Lon,
+}
+impl Axis {
+    fn hemispheres(&self) -> (char, char) {
+        match self {
+            Self::Lat => ('N', 'S'),
+            Self::Lon => ('E', 'W'),
+        }
+    }
+    fn hemisphere(&self, value: f64) -> char {
+        let (positive, negative) = self.hemispheres();
+        if value.is_sign_negative() {
+            negative
+        } else {
+            positive
+        }
+    }
+    fn degree_digits(&self) -> usize {
+        match self {
+            Self::Lat => 2,
+            Self::Lon => 3,
+        }
+    }
+    /// The sign a hemisphere letter gives a coordinate, if it's a letter for this axis.
+    fn sign_of(&self, letter: char) -> Option<f64> {
+        let (positive, negative) = self.hemispheres();
+        match letter.to_ascii_uppercase() {
+            c if c == positive => Some(1.0),
+            c if c == negative => Some(-1.0),
+            _ => None,
+        }
+    }
+}
+
+/// Splits a pair separated by a comma or whitespace.
+fn split_pair(text: &str) -> Option<(&str, &str)> {
+    if let Some((lat, lon)) = text.split_once(',') {
+        return Some((lat.trim(), lon.trim()));
+    }
+    let mut parts = text.split_whitespace();
+    let pair = (parts.next()?, parts.next()?);
+    parts.next().is_none().then_some(pair)
+}
+
+/// Removes a hemisphere letter from either end of `text`, returning the sign it gives.
+fn strip_hemisphere(text: &str, axis: Axis) -> Option<(f64, &str)> {
+    let first = text.chars().next()?;
+    let last = text.chars().last()?;
+    if let Some(sign) = axis.sign_of(first) {
+        Some((sign, &text[first.len_utf8()..]))
+    } else if let Some(sign) = axis.sign_of(last) {
+        Some((sign, &text[..text.len() - last.len_utf8()]))
+    } else {
+        None
+    }
+}
+
+fn from_dms(degs: f64, mins: f64, secs: f64) -> Option<f64> {
+    let valid = mins < 60.0 && secs < 60.0;
+    valid.then(|| degs + mins / 60.0 + secs / 3600.0)
+}
+
+fn unsigned(text: &str) -> Option<f64> {
+    let digits = !text.is_empty() && text.chars().all(|c| c.is_ascii_digit() || c == '.');
+    digits.then(|| text.parse().ok()).flatten()
+}
+
+fn read_euroscope(text: &str, axis: Axis) -> Option<f64> {
+    let layout = "H000.00.00.000";
+    let matches = text.len() == layout.len()
+        && text.bytes().zip(layout.bytes()).all(|(c, l)| match l {
+            b'0' => c.is_ascii_digit(),
+            b'.' => c == b'.',
+            _ => c.is_ascii_uppercase(),
+        });
+    if !matches {
+        return None;
+    }
+    read_vrc(text, axis)
+}
+
+fn read_vrc(text: &str, axis: Axis) -> Option<f64> {
+    let first = text.chars().next()?;
+    let sign = axis.sign_of(first)?;
+    let mut sections = text[1..].splitn(3, '.');
+    let degs = sections.next()?;
+    let mins = sections.next()?;
+    let secs = sections.next()?;
+    if degs.len() > axis.degree_digits() + 1 || degs.contains('.') || mins.contains('.') {
+        return None;
+    }
+    Some(sign * from_dms(unsigned(degs)?, unsigned(mins)?, unsigned(secs)?)?)
+}
+
+fn read_decimal(text: &str, axis: Axis) -> Option<f64> {
+    let text = text.trim_end_matches('°');
+    if let Some((sign, value)) = strip_hemisphere(text, axis) {
+        return Some(sign * unsigned(value.trim_end_matches('°'))?);
+    }
+    match text.strip_prefix('-') {
+        Some(value) => Some(-unsigned(value)?),
+        None => unsigned(text.strip_prefix('+').unwrap_or(text)),
+    }
+}
+
+fn read_dms(text: &str, axis: Axis) -> Option<f64> {
+    let (sign, text) = strip_hemisphere(text, axis)?;
+    let (degs, rest) = text.split_once('°')?;
+    let (mins, rest) = rest.split_once(['\'', '′']).unwrap_or(("0", rest));
+    let secs = rest
+        .strip_suffix(['"', '″'])
+        .or_else(|| rest.strip_suffix("''"))
+        .unwrap_or(rest);
+    let secs = if secs.is_empty() { "0" } else { secs };
+    if degs.contains('.') || mins.contains('.') {
+        return None;
+    }
+    Some(sign * from_dms(unsigned(degs)?, unsigned(mins)?, unsigned(secs)?)?)
+}
+
+fn read_icao(text: &str, axis: Axis, with_seconds: bool) -> Option<f64> {
+    let last = text.chars().last()?;
+    let sign = axis.sign_of(last).filter(|_| last.is_ascii_uppercase())?;
+    let digits = &text[..text.len() - 1];
+    let width = axis.degree_digits();
+    let expected = width + if with_seconds { 4 } else { 2 };
+    if digits.len() != expected || !digits.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let degs = unsigned(&digits[..width])?;
+    let mins = unsigned(&digits[width..width + 2])?;
+    let secs = unsigned(digits.get(width + 2..).unwrap_or("0")).unwrap_or(0.0);
+    Some(sign * from_dms(degs, mins, secs)?)
+}
+
+fn vrc(value: f64, axis: Axis) -> String {
+    let (degs, mins, thousandths) = split_degrees(value.abs(), 60_000);
+    format!(
+        "{}{}.{:02}.{:02}.{:03}",
+        axis.hemisphere(value),
+        degs,
+        mins,
+        thousandths / 1000,
+        thousandths % 1000
+    )
+}
+
+fn dms(value: f64, axis: Axis) -> String {
+    let (degs, mins, tenths) = split_degrees(value.abs(), 600);
+    let width = axis.degree_digits();
+    format!(
+        "{degs:0width$}°{mins:02}'{:02}.{}\"{}",
+        tenths / 10,
+        tenths % 10,
+        axis.hemisphere(value)
+    )
+}
+
+#[test]
+fn test_coordinate_formats() {
+    let position = Position::try_new_from_es("N051.07.25.010", "W002.39.13.334")
+        .unwrap()
+        .validate()
+        .unwrap();
+    let written = [
+        (
+            CoordinateFormat::EuroScope,
+            "N051.07.25.010 W002.39.13.334",
+            0.0,
+        ),
+        (CoordinateFormat::Vrc, "N51.07.25.010 W2.39.13.334", 0.0),
+        (CoordinateFormat::Decimal, "51.123614 -2.653704", 0.1),
+        (CoordinateFormat::Dms, "51°07'25.0\"N 002°39'13.3\"W", 5.0),
+        (CoordinateFormat::IcaoMinutes, "5107N00239W", 1000.0),
+        (CoordinateFormat::IcaoSeconds, "510725N0023913W", 30.0),
+    ];
+    for (format, text, precision_m) in written {
+        assert_eq!(format.format(&position), text);
+        assert_eq!(CoordinateFormat::detect(text), Some(format));
+        let parsed = Position::parse(text).unwrap();
+        assert!(parsed.distance_m(&position) <= precision_m, "{format}");
+    }
+    assert_eq!(
+        Position::parse("N051.07.25.010 W002.39.13.334"),
+        Ok(position)
+    );
+
+    // Looser spellings people paste in.
+    for text in [
+        "51.123614, -2.653704",
+        "51.123614N 2.653704W",
+        "N51.123614° W2.653704°",
+        "N51°07′25.01″ W2°39′13.33″",
+        "n51.7.25.010 w2.39.13.334",
+    ] {
+        assert!(
+            Position::parse(text).unwrap().distance_m(&position) < 1.0,
+            "{text}"
+        );
+    }
+
+    // Seconds and minutes round up into the next unit rather than printing 60.
+    let rounded = Position::new(-33.999_999_9, 151.999_99).validate().unwrap();
+    assert_eq!(
+        CoordinateFormat::Dms.format(&rounded),
+        "34°00'00.0\"S 152°00'00.0\"E"
+    );
+    assert_eq!(
+        CoordinateFormat::IcaoMinutes.format(&rounded),
+        "3400S15200E"
+    );
+
+    assert_eq!(
+        CoordinateFormat::EuroScope.parse_lon("E002.39.13.334"),
+        Ok(2.653_703_888_888_889)
+    );
+    assert!(CoordinateFormat::EuroScope
+        .parse_lat("E002.39.13.334")
+        .is_err());
+    assert!(CoordinateFormat::Dms
+        .parse("51°60'00\"N 002°00'00\"W")
+        .is_err());
+    assert!(CoordinateFormat::IcaoMinutes.parse("5107N00239").is_err());
+    assert_eq!(Position::parse("91.0 0.0"), Err(Error::InvalidPosition));
+    assert_eq!(Position::parse("EGLL"), Err(Error::InvalidCoordinate));
+}
+
+#[test]
+fn test_sector_file_coordinates() {
+    use crate::reader::test_sector;
+
+    let field = |text: &str| read_sector_field(text, Axis::Lat);
+    assert_eq!(
+        field("N051.07.25.010").unwrap().1,
+        CoordinateFormat::EuroScope
+    );
+    assert_eq!(field("N51.7.25.010").unwrap().1, CoordinateFormat::Vrc);
+    assert_eq!(field("-51.123614").unwrap().1, CoordinateFormat::Decimal);
+    assert_eq!(field("N12"), Err(Error::InvalidPosition));
+    assert_eq!(
+        field("N051.75.99.000"),
+        Err(Error::MinutesOrSecondsOutOfRange(
+            "N051.75.99.000".to_owned()
+        ))
+    );
+
+    let sector = test_sector(
+        "Decimal test",
+        "\
+[AIRPORT]
+EGKK 126.830 51.148055 -0.190277 D
+[FIXES]
+BIG 51.330277 0.046111
+DET N51.18.14.000 E0.35.50.000
+BAD N051.75.99.000 E000.35.50.000
+",
+    );
+    assert_eq!(
+        sector.sector_info.coordinate_format,
+        CoordinateFormat::Decimal
+    );
+    assert_eq!(sector.fixes.len(), 2);
+    assert!((sector.fixes[1].position.lon - 0.597222).abs() < 1e-6);
+    let diagnostic = &sector.non_critical_errors[0];
+    assert_eq!(diagnostic.line_number, 16);
+    assert_eq!(
+        &diagnostic.source_line[diagnostic.columns.clone()],
+        "N051.75.99.000"
+    );
+}
@@ -0,0 +1,159 @@
+use std::{fmt::Display, ops::Range};
+
+use crate::error::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    Warning,
+    Error,
+}
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Warning => "warning",
+                Self::Error => "error",
+            }
+        )
+    }
+}
+
+/// A problem found while reading a single line of a sector file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diagnostic {
+    pub file: Option<String>,
+    /// 1-based line number.
+    pub line_number: usize,
+    /// Byte range within `source_line` that the diagnostic refers to.
+    pub columns: Range<usize>,
+    /// The section header the line was found under, e.g. `[SID]`.
+    pub section: Option<String>,
+    pub severity: Severity,
+    pub error: Error,
+    pub source_line: String,
+}
+impl Diagnostic {
+    pub fn new(
+        line_number: usize,
+        source_line: &str,
+        section: Option<&str>,
+        severity: Severity,
+        error: Error,
+    ) -> Diagnostic {
+        let columns = highlight_range(source_line, &error);
+        Diagnostic {
+            file: None,
+            line_number,
+            columns,
+            section: section.map(str::to_owned),
+            severity,
+            error,
+            source_line: source_line.to_owned(),
+        }
+    }
+
+    pub fn with_file(mut self, file: Option<&str>) -> Diagnostic {
+        self.file = file.map(str::to_owned);
+        self
+    }
+
+    /// 1-based column of the start of the highlighted range, counted in characters.
+    pub fn column(&self) -> usize {
+        self.source_line
+            .get(..self.columns.start)
+            .map_or(0, |prefix| prefix.chars().count())
+            + 1
+    }
+
+    /// Renders the diagnostic with the offending source line and a caret underneath it.
+    ///
+    /// ```text
+    /// error: Unknown fix FOO in [SID] line 3412
+    ///     --> UK.sct:3412:27
+    ///      |
+    /// 3412 | EGLL 27L BPK7F            FOO FOO BPK BPK
+    ///      |                           ^^^
+    /// ```
+    pub fn render(&self) -> String {
+        let line_number = self.line_number.to_string();
+        let gutter = " ".repeat(line_number.len());
+        let highlighted = self
+            .source_line
+            .get(self.columns.clone())
+            .map_or(1, |text| text.chars().count().max(1));
+
+        let mut output = format!("{}: {}\n", self.severity, self);
+        output += &format!(
+            "{}--> {}:{}:{}\n",
+            gutter,
+            self.file.as_deref().unwrap_or("<source>"),
+            self.line_number,
+            self.column()
+        );
+        output += &format!("{} |\n", gutter);
+        output += &format!("{} | {}\n", line_number, self.source_line);
+        output += &format!(
+            "{} | {}{}\n",
+            gutter,
+            " ".repeat(self.column() - 1),
+            "^".repeat(highlighted)
+        );
+        output
+    }
+}
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.error)?;
+        if let Some(section) = &self.section {
+            write!(f, " in {}", section)?;
+        }
+        write!(f, " line {}", self.line_number)
+    }
+}
+
+/// Works out which part of the line an error refers to. Unknown identifiers and bad coordinates
+/// are highlighted on their own; anything else highlights the whole line.
+fn highlight_range(source_line: &str, error: &Error) -> Range<usize> {
+    if let Error::UnknownWaypoint(identifier)
+    | Error::UnknownAirport(identifier)
+    | Error::MinutesOrSecondsOutOfRange(identifier) = error
+    {
+        let mut offset = 0;
+        for token in source_line.split_whitespace() {
+            let start = offset + source_line[offset..].find(token).unwrap_or(0);
+            offset = start + token.len();
+            if token == identifier {
+                return start..offset;
+            }
+        }
+    }
+    let start = source_line.len() - source_line.trim_start().len();
+    start..source_line.trim_end().len().max(start)
+}
+
+#[test]
+fn test_render() {
+    let diagnostic = Diagnostic::new(
+        3412,
+        "EGLL 27L BPK7F            FOO FOO BPK BPK",
+        Some("[SID]"),
+        Severity::Error,
+        Error::UnknownWaypoint("FOO".to_owned()),
+    )
+    .with_file(Some("UK.sct"));
+    assert_eq!(diagnostic.columns, 26..29);
+    assert_eq!(
+        diagnostic.render(),
+        "\
+error: Unknown fix FOO in [SID] line 3412
+    --> UK.sct:3412:27
+     |
+3412 | EGLL 27L BPK7F            FOO FOO BPK BPK
+     |                           ^^^
+"
+    );
+}
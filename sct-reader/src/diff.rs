@@ -0,0 +1,637 @@
+//! Compares two sectors item by item, e.g. to review what changed between two AIRAC releases.
+
+use std::{collections::HashMap, fmt::Display, fmt::Write};
+
+use crate::{
+    airway::{key, PositionKey},
+    colour::Colour,
+    geojson::{colour, coordinate},
+    json::string,
+    line::{ColouredLine, Line},
+    position::{Position, Valid},
+    sector::{Label, LineCategory, Sector},
+    waypoint::{RunwayStrip, Waypoint, MATCH_RADIUS_N_MI},
+};
+
+/// Sector files give positions to a thousandth of a second, about 3 cm, so anything closer than
+/// this is the same position.
+const MOVE_TOLERANCE_M: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ItemKind {
+    Airport,
+    Vor,
+    Ndb,
+    Fix,
+    Runway,
+    Lines(LineCategory),
+    Region,
+    Label,
+}
+impl ItemKind {
+    /// The name used for the kind in JSON output.
+    fn json_name(&self) -> &'static str {
+        match self {
+            Self::Airport => "airport",
+            Self::Vor => "vor",
+            Self::Ndb => "ndb",
+            Self::Fix => "fix",
+            Self::Runway => "runway",
+            Self::Lines(LineCategory::Artcc) => "artcc",
+            Self::Lines(LineCategory::ArtccHigh) => "artcc_high",
+            Self::Lines(LineCategory::ArtccLow) => "artcc_low",
+            Self::Lines(LineCategory::LowAirway) => "low_airway",
+            Self::Lines(LineCategory::HighAirway) => "high_airway",
+            Self::Lines(LineCategory::Sid) => "sid",
+            Self::Lines(LineCategory::Star) => "star",
+            Self::Lines(LineCategory::Geo) => "geo",
+            Self::Region => "region",
+            Self::Label => "label",
+        }
+    }
+}
+impl Display for ItemKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Airport => write!(f, "Airport"),
+            Self::Vor => write!(f, "VOR"),
+            Self::Ndb => write!(f, "NDB"),
+            Self::Fix => write!(f, "Fix"),
+            Self::Runway => write!(f, "Runway"),
+            Self::Lines(category) => write!(f, "{}", category),
+            Self::Region => write!(f, "Region"),
+            Self::Label => write!(f, "Label"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Difference {
+    /// Line groups and regions have no single position.
+    Added {
+        position: Option<Position<Valid>>,
+    },
+    Removed {
+        position: Option<Position<Valid>>,
+    },
+    Moved {
+        from: Position<Valid>,
+        to: Position<Valid>,
+    },
+    Frequency {
+        from: String,
+        to: String,
+    },
+    Recoloured {
+        from: Colour,
+        to: Colour,
+    },
+    /// Lines or regions added to and removed from a group that's in both sectors. Lines are the
+    /// same whichever way round they are drawn.
+    Modified {
+        added: usize,
+        removed: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SectorChange {
+    pub kind: ItemKind,
+    /// The identifier or group name. Runways are named by their airport and end, e.g. `EGKK 08R`,
+    /// and labels by their group and text, e.g. `SCT2/Gatwick Tower`.
+    pub name: String,
+    pub difference: Difference,
+}
+impl Display for SectorChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} ", self.kind, self.name)?;
+        match &self.difference {
+            Difference::Added { position: None } => write!(f, "added"),
+            Difference::Added {
+                position: Some(position),
+            } => {
+                let (lat, lon) = position.to_es();
+                write!(f, "added at {} {}", lat, lon)
+            }
+            Difference::Removed { .. } => write!(f, "removed"),
+            Difference::Moved { from, to } => write!(f, "{}", from.describe_move(to)),
+            Difference::Frequency { from, to } => {
+                write!(f, "frequency changed from {} to {}", from, to)
+            }
+            Difference::Recoloured { from, to } => {
+                write!(f, "recoloured from {} to {}", hex(*from), hex(*to))
+            }
+            Difference::Modified { added, removed } => {
+                let items = match (self.kind, added) {
+                    (ItemKind::Region, 1) => "region",
+                    (ItemKind::Region, _) => "regions",
+                    (_, 1) => "line",
+                    _ => "lines",
+                };
+                write!(
+                    f,
+                    "modified, with {} {} added and {} removed",
+                    added, items, removed
+                )
+            }
+        }
+    }
+}
+
+/// Everything that differs between two sectors, in the order the items appear in the older one,
+/// followed by anything added.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SectorDiff {
+    pub changes: Vec<SectorChange>,
+}
+impl SectorDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// The changes as a JSON object, with positions given as `[lon, lat]` like GeoJSON.
+    ///
+    /// ```text
+    /// {"changes":[{"kind":"vor","name":"MAY","change":"frequency","from":"117.900","to":"117.950"}]}
+    /// ```
+    pub fn to_json(&self) -> String {
+        let changes = self
+            .changes
+            .iter()
+            .map(|change| {
+                let mut json = format!(
+                    "{{\"kind\":\"{}\",\"name\":{},",
+                    change.kind.json_name(),
+                    string(&change.name)
+                );
+                let position = |position: &Option<Position<Valid>>| {
+                    position.as_ref().map_or("null".to_owned(), coordinate)
+                };
+                match &change.difference {
+                    Difference::Added { position: at } => {
+                        write!(json, "\"change\":\"added\",\"position\":{}", position(at))
+                    }
+                    Difference::Removed { position: at } => {
+                        write!(json, "\"change\":\"removed\",\"position\":{}", position(at))
+                    }
+                    Difference::Moved { from, to } => write!(
+                        json,
+                        "\"change\":\"moved\",\"from\":{},\"to\":{},\"distance_m\":{:.1}",
+                        coordinate(from),
+                        coordinate(to),
+                        from.distance_m(to)
+                    ),
+                    Difference::Frequency { from, to } => write!(
+                        json,
+                        "\"change\":\"frequency\",\"from\":{},\"to\":{}",
+                        string(from),
+                        string(to)
+                    ),
+                    Difference::Recoloured { from, to } => write!(
+                        json,
+                        "\"change\":\"recoloured\",\"from\":{},\"to\":{}",
+                        colour(Some(*from)),
+                        colour(Some(*to))
+                    ),
+                    Difference::Modified { added, removed } => write!(
+                        json,
+                        "\"change\":\"modified\",\"added\":{},\"removed\":{}",
+                        added, removed
+                    ),
+                }
+                .unwrap();
+                json.push('}');
+                json
+            })
+            .collect::<Vec<_>>();
+        format!("{{\"changes\":[{}]}}", changes.join(","))
+    }
+}
+impl Display for SectorDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+impl Sector {
+    /// What changed from this sector to `newer`.
+    pub fn diff(&self, newer: &Sector) -> SectorDiff {
+        let mut diff = Differ::default();
+
+        let airports = pair(&self.airports, &newer.airports, waypoint);
+        airports.compare(ItemKind::Airport, &mut diff, |diff, old, new| {
+            diff.position(
+                ItemKind::Airport,
+                &old.identifier,
+                old.position,
+                new.position,
+            );
+            diff.frequency(
+                ItemKind::Airport,
+                &old.identifier,
+                &old.tower_frequency,
+                &new.tower_frequency,
+            );
+            diff.runways(&old.identifier, &old.runways, &new.runways);
+        });
+        let vors = pair(&self.vors, &newer.vors, waypoint);
+        vors.compare(ItemKind::Vor, &mut diff, |diff, old, new| {
+            diff.position(ItemKind::Vor, &old.identifier, old.position, new.position);
+            diff.frequency(
+                ItemKind::Vor,
+                &old.identifier,
+                &old.frequency,
+                &new.frequency,
+            );
+        });
+        let ndbs = pair(&self.ndbs, &newer.ndbs, waypoint);
+        ndbs.compare(ItemKind::Ndb, &mut diff, |diff, old, new| {
+            diff.position(ItemKind::Ndb, &old.identifier, old.position, new.position);
+            diff.frequency(
+                ItemKind::Ndb,
+                &old.identifier,
+                &old.frequency,
+                &new.frequency,
+            );
+        });
+        let fixes = pair(&self.fixes, &newer.fixes, waypoint);
+        fixes.compare(ItemKind::Fix, &mut diff, |diff, old, new| {
+            diff.position(ItemKind::Fix, &old.identifier, old.position, new.position);
+        });
+
+        for category in LineCategory::ALL {
+            let old = lines_by_group(self.line_groups(category));
+            let new = lines_by_group(newer.line_groups(category));
+            diff.groups(ItemKind::Lines(category), &old, &new);
+        }
+        let regions = |sector: &Sector| {
+            let mut groups: Groups<(u32, Vec<PositionKey>)> = Groups::default();
+            for group in &sector.regions {
+                let keys = group.regions.iter().map(|region| {
+                    let vertices = region.vertices.iter().map(|vertex| key(*vertex));
+                    (u32::from(region.colour), vertices.collect())
+                });
+                groups.add(&group.name, keys);
+            }
+            groups
+        };
+        diff.groups(ItemKind::Region, &regions(self), &regions(newer));
+
+        let (old_labels, new_labels) = (labels(self), labels(newer));
+        let labels = pair(&old_labels, &new_labels, |(group, label)| {
+            (format!("{}/{}", group, label.name), label.position)
+        });
+        labels.compare(
+            ItemKind::Label,
+            &mut diff,
+            |diff, (group, old), (_, new)| {
+                let name = format!("{}/{}", group, old.name);
+                diff.position(ItemKind::Label, &name, old.position, new.position);
+                if old.colour != new.colour {
+                    diff.push(
+                        ItemKind::Label,
+                        &name,
+                        Difference::Recoloured {
+                            from: old.colour,
+                            to: new.colour,
+                        },
+                    );
+                }
+            },
+        );
+
+        SectorDiff {
+            changes: diff.changes,
+        }
+    }
+}
+
+/// Every label, with the name of its group.
+fn labels(sector: &Sector) -> Vec<(&String, &Label)> {
+    sector
+        .labels
+        .iter()
+        .flat_map(|group| group.labels.iter().map(move |label| (&group.name, label)))
+        .collect()
+}
+
+fn waypoint(item: &impl Waypoint) -> (String, Position<Valid>) {
+    (item.identifier().clone(), item.position())
+}
+
+/// Items from two sectors, paired up by identifier. Where an identifier is used more than once,
+/// each item is paired with the nearest unpaired one.
+struct Pairing<'a, T> {
+    /// Each old item, with the new item it's paired with if there is one.
+    old: Vec<(&'a T, String, Position<Valid>, Option<&'a T>)>,
+    added: Vec<(&'a T, String, Position<Valid>)>,
+}
+impl<'a, T> Pairing<'a, T> {
+    /// Records each removed item and compares each pair, in the order of the old items, then
+    /// records the added items.
+    fn compare(
+        &self,
+        kind: ItemKind,
+        diff: &mut Differ,
+        mut compare: impl FnMut(&mut Differ, &T, &T),
+    ) {
+        for (old, name, position, new) in &self.old {
+            match new {
+                Some(new) => compare(diff, old, new),
+                None => diff.push(
+                    kind,
+                    name,
+                    Difference::Removed {
+                        position: Some(*position),
+                    },
+                ),
+            }
+        }
+        for (_, name, position) in &self.added {
+            diff.push(
+                kind,
+                name,
+                Difference::Added {
+                    position: Some(*position),
+                },
+            );
+        }
+    }
+}
+
+fn pair<'a, T>(
+    old: &'a [T],
+    new: &'a [T],
+    identify: impl Fn(&T) -> (String, Position<Valid>),
+) -> Pairing<'a, T> {
+    let mut unpaired: HashMap<String, Vec<(usize, Position<Valid>)>> = HashMap::new();
+    for (i, item) in new.iter().enumerate() {
+        let (name, position) = identify(item);
+        unpaired.entry(name).or_default().push((i, position));
+    }
+    let mut paired = vec![false; new.len()];
+    let old = old
+        .iter()
+        .map(|item| {
+            let (name, position) = identify(item);
+            let candidates = unpaired.get_mut(&name);
+            let nearest = candidates.and_then(|candidates| {
+                let (index, distance) = candidates
+                    .iter()
+                    .enumerate()
+                    .map(|(index, (_, other))| (index, position.distance_n_mi(other)))
+                    .min_by(|(_, a), (_, b)| a.total_cmp(b))?;
+                // A fix renamed to an identifier used far away shows as one removed and one
+                // added.
+                (distance <= MATCH_RADIUS_N_MI).then(|| candidates.swap_remove(index).0)
+            });
+            if let Some(i) = nearest {
+                paired[i] = true;
+            }
+            (item, name, position, nearest.map(|i| &new[i]))
+        })
+        .collect();
+    let added = new
+        .iter()
+        .zip(paired)
+        .filter(|(_, paired)| !paired)
+        .map(|(item, _)| {
+            let (name, position) = identify(item);
+            (item, name, position)
+        })
+        .collect();
+    Pairing { old, added }
+}
+
+/// Groups by name, each holding how many times each item appears in it. Groups that share a name
+/// are combined.
+#[derive(Default)]
+struct Groups<K> {
+    names: Vec<String>,
+    items: HashMap<String, HashMap<K, usize>>,
+}
+impl<K: std::hash::Hash + Eq> Groups<K> {
+    fn add(&mut self, name: &str, keys: impl Iterator<Item = K>) {
+        if !self.items.contains_key(name) {
+            self.names.push(name.to_owned());
+        }
+        let items = self.items.entry(name.to_owned()).or_default();
+        for key in keys {
+            *items.entry(key).or_default() += 1;
+        }
+    }
+}
+
+type LineKey = (PositionKey, PositionKey, Option<Colour>);
+
+fn lines_by_group(groups: &[crate::line::LineGroup<ColouredLine>]) -> Groups<LineKey> {
+    let mut by_group = Groups::default();
+    for group in groups {
+        let keys = group.lines.iter().map(|line| {
+            let (start, end) = (key(line.start()), key(line.end()));
+            (start.min(end), start.max(end), line.colour)
+        });
+        by_group.add(&group.name, keys);
+    }
+    by_group
+}
+
+#[derive(Default)]
+struct Differ {
+    changes: Vec<SectorChange>,
+}
+impl Differ {
+    fn push(&mut self, kind: ItemKind, name: &str, difference: Difference) {
+        self.changes.push(SectorChange {
+            kind,
+            name: name.to_owned(),
+            difference,
+        });
+    }
+
+    fn position(&mut self, kind: ItemKind, name: &str, from: Position<Valid>, to: Position<Valid>) {
+        if from.distance_m(&to) >= MOVE_TOLERANCE_M {
+            self.push(kind, name, Difference::Moved { from, to });
+        }
+    }
+
+    fn frequency(&mut self, kind: ItemKind, name: &str, from: &str, to: &str) {
+        if from != to {
+            self.push(
+                kind,
+                name,
+                Difference::Frequency {
+                    from: from.to_owned(),
+                    to: to.to_owned(),
+                },
+            );
+        }
+    }
+
+    /// Runways are matched by the identifiers of both ends, so a renumbered runway shows as one
+    /// removed and one added.
+    fn runways(&mut self, airport: &str, old: &[RunwayStrip], new: &[RunwayStrip]) {
+        let ends = |runway: &RunwayStrip| {
+            let (a, b) = (runway.end_a.identifier(), runway.end_b.identifier());
+            if a <= b {
+                (a, b)
+            } else {
+                (b, a)
+            }
+        };
+        let name = |runway: &RunwayStrip| {
+            let (a, b) = ends(runway);
+            format!("{} {}/{}", airport, a, b)
+        };
+        for runway in old {
+            let Some(other) = new.iter().find(|other| ends(other) == ends(runway)) else {
+                self.push(
+                    ItemKind::Runway,
+                    &name(runway),
+                    Difference::Removed { position: None },
+                );
+                continue;
+            };
+            for end in [&runway.end_a, &runway.end_b] {
+                let other_end = [&other.end_a, &other.end_b]
+                    .into_iter()
+                    .find(|other_end| other_end.identifier() == end.identifier())
+                    .unwrap();
+                let name = format!("{} {}", airport, end.identifier());
+                self.position(
+                    ItemKind::Runway,
+                    &name,
+                    end.td_threshold_pos,
+                    other_end.td_threshold_pos,
+                );
+            }
+        }
+        for runway in new {
+            if !old.iter().any(|other| ends(other) == ends(runway)) {
+                self.push(
+                    ItemKind::Runway,
+                    &name(runway),
+                    Difference::Added { position: None },
+                );
+            }
+        }
+    }
+
+    fn groups<K: std::hash::Hash + Eq>(
+        &mut self,
+        kind: ItemKind,
+        old: &Groups<K>,
+        new: &Groups<K>,
+    ) {
+        for name in &old.names {
+            let Some(new_items) = new.items.get(name) else {
+                self.push(kind, name, Difference::Removed { position: None });
+                continue;
+            };
+            let old_items = &old.items[name];
+            let missing = |from: &HashMap<K, usize>, to: &HashMap<K, usize>| {
+                from.iter()
+                    .map(|(key, count)| count.saturating_sub(to.get(key).copied().unwrap_or(0)))
+                    .sum::<usize>()
+            };
+            let added = missing(new_items, old_items);
+            let removed = missing(old_items, new_items);
+            if added > 0 || removed > 0 {
+                self.push(kind, name, Difference::Modified { added, removed });
+            }
+        }
+        for name in &new.names {
+            if !old.items.contains_key(name) {
+                self.push(kind, name, Difference::Added { position: None });
+            }
+        }
+    }
+}
+
+fn hex(colour: Colour) -> String {
+    format!("#{:02x}{:02x}{:02x}", colour.r, colour.g, colour.b)
+}
+
+#[test]
+fn test_sector_diff() {
+    use crate::reader::test_sector;
+
+    let colours = "#define COLOR_Red 255\n#define COLOR_Blue 16711680\n";
+    let old = format!(
+        "{}{}",
+        colours,
+        "\
+[VOR]
+MAY 117.900 N051.01.02.000 E000.06.58.000
+[AIRPORT]
+EGKK 126.830 N051.08.53.000 W000.11.25.000 D
+[RUNWAY]
+08R 26L 077 257 N051.08.48.020 W000.12.57.930 N051.09.21.650 W000.09.31.990 EGKK
+[FIXES]
+BIG N051.19.49.000 E000.02.46.000
+GONE N051.00.00.000 E000.00.00.000
+[SID]
+EGKK SID                  N051.00.00.000 E000.00.00.000 N051.01.00.000 E000.00.00.000
+                          N051.01.00.000 E000.00.00.000 N051.02.00.000 E000.00.00.000
+[REGIONS]
+REGIONNAME Apron
+COLOR_Red N051.00.00.000 E000.00.00.000
+N051.01.00.000 E000.00.00.000
+N051.01.00.000 E000.01.00.000
+[LABELS]
+\"Gatwick\" N051.08.53.000 W000.11.25.000 COLOR_Red
+"
+    );
+    let new = format!(
+        "{}{}",
+        colours,
+        "\
+[VOR]
+MAY 117.950 N051.01.02.000 E000.06.58.000
+[AIRPORT]
+EGKK 126.830 N051.08.53.000 W000.11.25.000 D
+[RUNWAY]
+08R 26L 077 257 N051.08.49.000 W000.12.57.930 N051.09.21.650 W000.09.31.990 EGKK
+[FIXES]
+BIG N051.19.50.000 E000.02.46.000
+NEW N051.00.00.000 E000.00.00.000
+[SID]
+EGKK SID                  N051.02.00.000 E000.00.00.000 N051.01.00.000 E000.00.00.000
+                          N051.02.00.000 E000.00.00.000 N051.03.00.000 E000.00.00.000
+[REGIONS]
+REGIONNAME Apron
+COLOR_Red N051.00.00.000 E000.00.00.000
+N051.01.00.000 E000.00.00.000
+N051.01.00.000 E000.01.00.000
+[LABELS]
+\"Gatwick\" N051.08.53.000 W000.11.25.000 COLOR_Blue
+"
+    );
+    let old = test_sector("Diff test", &old);
+    let new = test_sector("Diff test", &new);
+
+    assert!(old.diff(&old).is_empty());
+    let diff = old.diff(&new);
+    assert_eq!(
+        diff.to_string(),
+        "\
+Runway EGKK 08R moved 30 m from N051.08.48.020 W000.12.57.930 to N051.08.49.000 W000.12.57.930
+VOR MAY frequency changed from 117.900 to 117.950
+Fix BIG moved 31 m from N051.19.49.000 E000.02.46.000 to N051.19.50.000 E000.02.46.000
+Fix GONE removed
+Fix NEW added at N051.00.00.000 E000.00.00.000
+[SID] EGKK SID modified, with 1 line added and 1 removed
+Label SCT2/Gatwick recoloured from #ff0000 to #0000ff
+"
+    );
+    assert!(diff.to_json().contains(
+        "{\"kind\":\"vor\",\"name\":\"MAY\",\"change\":\"frequency\",\"from\":\"117.900\",\"to\":\"117.950\"}"
+    ));
+    assert!(diff.to_json().contains(
+        "{\"kind\":\"fix\",\"name\":\"GONE\",\"change\":\"removed\",\"position\":[0.0000000,51.0000000]}"
+    ));
+}
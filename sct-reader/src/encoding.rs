@@ -0,0 +1,152 @@
+use std::io::BufRead;
+
+/// The text encoding of a sector file.
+///
+/// EuroScope itself reads sector files as Windows-1252, but files edited with modern tools are
+/// often saved as UTF-8, sometimes with a byte order mark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    /// Each line is read as UTF-8 if it is valid, and as Windows-1252 otherwise.
+    #[default]
+    Auto,
+    Utf8,
+    Windows1252,
+    Latin1,
+}
+impl Encoding {
+    /// Decodes a single line. Only fails for [`Encoding::Utf8`], as every byte sequence is valid
+    /// in the single-byte encodings.
+    pub fn decode(&self, bytes: &[u8]) -> Option<String> {
+        match self {
+            Self::Auto => Some(
+                std::str::from_utf8(bytes)
+                    .map(str::to_owned)
+                    .unwrap_or_else(|_| decode_windows_1252(bytes)),
+            ),
+            Self::Utf8 => std::str::from_utf8(bytes).ok().map(str::to_owned),
+            Self::Windows1252 => Some(decode_windows_1252(bytes)),
+            Self::Latin1 => Some(bytes.iter().map(|&byte| byte as char).collect()),
+        }
+    }
+}
+
+/// Characters for bytes 0x80 to 0x9F, which is the only range where Windows-1252 differs from
+/// Latin-1. The five unassigned bytes map to the C1 control character with the same value.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+fn decode_windows_1252(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&byte| match byte {
+            0x80..=0x9F => WINDOWS_1252_HIGH[(byte - 0x80) as usize],
+            _ => byte as char,
+        })
+        .collect()
+}
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+pub(crate) enum DecodedLine {
+    Text(String),
+    /// The line could not be decoded. Holds a lossy UTF-8 decoding for use in diagnostics.
+    Invalid(String),
+}
+
+/// Reads lines from a source as raw bytes and decodes them, stripping line endings and any
+/// byte order mark at the start of the file.
+pub(crate) struct DecodedLines<R: BufRead> {
+    source: R,
+    encoding: Encoding,
+    at_start: bool,
+    buffer: Vec<u8>,
+}
+impl<R: BufRead> DecodedLines<R> {
+    pub fn new(source: R, encoding: Encoding) -> Self {
+        Self {
+            source,
+            encoding,
+            at_start: true,
+            buffer: vec![],
+        }
+    }
+}
+impl<R: BufRead> Iterator for DecodedLines<R> {
+    type Item = std::io::Result<DecodedLine>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.buffer.clear();
+        match self.source.read_until(b'\n', &mut self.buffer) {
+            Ok(0) => return None,
+            Ok(_) => {}
+            Err(e) => return Some(Err(e)),
+        }
+
+        let mut bytes = self.buffer.as_slice();
+        if std::mem::take(&mut self.at_start) {
+            bytes = bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes);
+        }
+        bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+        bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+
+        Some(Ok(match self.encoding.decode(bytes) {
+            Some(line) => DecodedLine::Text(line),
+            None => DecodedLine::Invalid(String::from_utf8_lossy(bytes).into_owned()),
+        }))
+    }
+}
+
+#[test]
+fn test_decode_windows_1252() {
+    let bytes = b"\"Z\xfcrich \x80\" N047.27.53.000 E008.32.57.000 COLOR_Label";
+    assert_eq!(
+        Encoding::Auto.decode(bytes).unwrap(),
+        "\"Zürich €\" N047.27.53.000 E008.32.57.000 COLOR_Label"
+    );
+    assert_eq!(Encoding::Utf8.decode(bytes), None);
+    assert_eq!(
+        Encoding::Latin1.decode(b"Z\xfcrich").unwrap(),
+        Encoding::Auto.decode("Zürich".as_bytes()).unwrap()
+    );
+}
+
+#[test]
+fn test_read_encodings() {
+    use crate::{error::Error, reader::SctReader};
+
+    let mut sct = b"\xef\xbb\xbf[INFO]
+Encoding test
+EGTT_CTR
+EGLL
+N051.28.39.000
+W000.27.41.000
+60
+38.5
+-1.5
+1
+[FIXES]
+BPK N051.44.58.000 W000.06.25.000
+"
+    .to_vec();
+    sct.extend_from_slice(b"Z\xfcRICH N047.27.53.000 E008.32.57.000\n");
+
+    // The byte order mark doesn't stop `[INFO]` being read as a section header.
+    let sector = SctReader::new(sct.as_slice())
+        .with_encoding(Encoding::Utf8)
+        .try_read()
+        .unwrap();
+    assert_eq!(sector.sector_info.name, "Encoding test");
+    assert_eq!(sector.fixes.len(), 1);
+    assert_eq!(sector.non_critical_errors.len(), 1);
+    let diagnostic = &sector.non_critical_errors[0];
+    assert_eq!(diagnostic.error, Error::InvalidEncoding);
+    assert_eq!(diagnostic.line_number, 13);
+    assert_eq!(diagnostic.section.as_deref(), Some("[FIXES]"));
+
+    let sector = SctReader::new(sct.as_slice()).try_read().unwrap();
+    assert!(sector.non_critical_errors.is_empty());
+    assert_eq!(sector.fixes[1].identifier, "ZüRICH");
+}
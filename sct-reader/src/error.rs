@@ -1,13 +1,17 @@
 use std::fmt::Display;
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+use crate::sector::SectorInfoField;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Error {
     MissingMetadata,
     IoError,
     InvalidColourDefinition,
     InvalidFileSection,
     InvalidCoordinate,
-    SectorInfoError,
+    SectorInfoError(SectorInfoField),
+    UnexpectedSectorInfoLine,
     InvalidAirspaceClass,
     InvalidWaypoint,
     InvalidPosition,
@@ -20,34 +24,77 @@ pub enum Error {
     InvalidGeoEntry,
     InvalidRegion,
     InvalidLabel,
+    InvalidControllerPosition,
+    InvalidSectorLine,
+    InvalidAirspaceSector,
+    InvalidFreeText,
+    InvalidProcedure,
+    UnknownWaypoint(String),
+    UnknownAirport(String),
+    InvalidEncoding,
+    InvalidRoute,
+    InvalidArincRecord,
+    UnknownAirway(String),
+    NotOnAirway {
+        waypoint: String,
+        airway: String,
+    },
+    BrokenAirway {
+        airway: String,
+        from: String,
+        to: String,
+    },
+    InvalidOverlay(String),
+    /// A dotted coordinate, e.g. `N051.75.99.000`, with 60 or more minutes or seconds.
+    MinutesOrSecondsOutOfRange(String),
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Self::MissingMetadata => "Missing metadata",
-                Self::IoError => "Unable to read the source",
-                Self::InvalidColourDefinition => "Invalid colour definition",
-                Self::InvalidFileSection => "Invalid file section",
-                Self::InvalidCoordinate => "Invalid coordinate",
-                Self::SectorInfoError => "Sector information error",
-                Self::InvalidAirspaceClass => "Invalid airspace class",
-                Self::InvalidWaypoint => "Invalid waypoint",
-                Self::InvalidPosition => "Invalid position",
-                Self::InvalidRunway => "Invalid runway",
-                Self::InvalidHeading => "Invalid heading",
-                Self::InvalidVorOrNdb => "Invalid VOR or NDB",
-                Self::InvalidFix => "Invalid fix",
-                Self::InvalidArtccEntry => "Invalid ARTCC entry",
-                Self::InvalidSidStarEntry => "Invalid SID / STAR entry",
-                Self::InvalidGeoEntry => "Invalid geo Entry",
-                Self::InvalidRegion => "Invalid region",
-                Self::InvalidLabel => "Invalid label",
+        match self {
+            Self::MissingMetadata => write!(f, "Missing metadata"),
+            Self::IoError => write!(f, "Unable to read the source"),
+            Self::InvalidColourDefinition => write!(f, "Invalid colour definition"),
+            Self::InvalidFileSection => write!(f, "Invalid file section"),
+            Self::InvalidCoordinate => write!(f, "Invalid coordinate"),
+            Self::SectorInfoError(field) => write!(f, "Missing or invalid [INFO] field: {}", field),
+            Self::UnexpectedSectorInfoLine => write!(f, "Unexpected line in [INFO]"),
+            Self::InvalidAirspaceClass => write!(f, "Invalid airspace class"),
+            Self::InvalidWaypoint => write!(f, "Invalid waypoint"),
+            Self::InvalidPosition => write!(f, "Invalid position"),
+            Self::InvalidRunway => write!(f, "Invalid runway"),
+            Self::InvalidHeading => write!(f, "Invalid heading"),
+            Self::InvalidVorOrNdb => write!(f, "Invalid VOR or NDB"),
+            Self::InvalidFix => write!(f, "Invalid fix"),
+            Self::InvalidArtccEntry => write!(f, "Invalid ARTCC entry"),
+            Self::InvalidSidStarEntry => write!(f, "Invalid SID / STAR entry"),
+            Self::InvalidGeoEntry => write!(f, "Invalid geo Entry"),
+            Self::InvalidRegion => write!(f, "Invalid region"),
+            Self::InvalidLabel => write!(f, "Invalid label"),
+            Self::InvalidControllerPosition => write!(f, "Invalid controller position"),
+            Self::InvalidSectorLine => write!(f, "Invalid sector line"),
+            Self::InvalidAirspaceSector => write!(f, "Invalid airspace sector"),
+            Self::InvalidFreeText => write!(f, "Invalid free text"),
+            Self::InvalidProcedure => write!(f, "Invalid SID / STAR procedure"),
+            Self::UnknownWaypoint(identifier) => write!(f, "Unknown fix {}", identifier),
+            Self::UnknownAirport(identifier) => write!(f, "Unknown airport {}", identifier),
+            Self::InvalidEncoding => write!(f, "Line could not be decoded"),
+            Self::InvalidRoute => write!(f, "Invalid route"),
+            Self::InvalidArincRecord => write!(f, "Invalid ARINC 424 record"),
+            Self::UnknownAirway(airway) => write!(f, "Unknown airway {}", airway),
+            Self::NotOnAirway { waypoint, airway } => {
+                write!(f, "{} is not on airway {}", waypoint, airway)
+            }
+            Self::BrokenAirway { airway, from, to } => write!(
+                f,
+                "Airway {} is not continuous from {} to {}",
+                airway, from, to
+            ),
+            Self::InvalidOverlay(reason) => write!(f, "Invalid overlay: {}", reason),
+            Self::MinutesOrSecondsOutOfRange(coordinate) => {
+                write!(f, "Minutes or seconds out of range in {}", coordinate)
             }
-        )
+        }
     }
 }
 
@@ -0,0 +1,562 @@
+use std::{collections::HashMap, io::BufRead};
+
+use crate::{
+    diagnostic::{Diagnostic, Severity},
+    encoding::{DecodedLine, DecodedLines, Encoding},
+    error::Error,
+    position::{Position, Valid, METRES_PER_N_MI},
+    sector::Sector,
+    waypoint::Waypoint,
+    SectorResult,
+};
+
+/// Reads a EuroScope `.ese` companion file.
+pub struct EseReader<R: BufRead> {
+    source: R,
+    file_name: Option<String>,
+    encoding: Encoding,
+    current_section: EseSection,
+    partial: PartialExtendedSector,
+}
+impl<R: BufRead> EseReader<R> {
+    pub fn new(source: R) -> Self {
+        Self {
+            source,
+            file_name: None,
+            encoding: Encoding::Auto,
+            current_section: EseSection::Other,
+            partial: PartialExtendedSector::default(),
+        }
+    }
+
+    /// Sets the file name reported in diagnostics.
+    pub fn with_file_name(mut self, file_name: impl Into<String>) -> Self {
+        self.file_name = Some(file_name.into());
+        self
+    }
+
+    /// Sets how the source is decoded. Defaults to [`Encoding::Auto`].
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Allows `CIRCLE_SECTORLINE` entries to be centred on a navaid, fix or airport from the `.sct`.
+    pub fn with_sector(mut self, sector: &Sector) -> Self {
+        fn add<W: Waypoint>(map: &mut HashMap<String, Position<Valid>>, waypoints: &[W]) {
+            for waypoint in waypoints {
+                map.entry(waypoint.identifier().clone())
+                    .or_insert(waypoint.position());
+            }
+        }
+        add(&mut self.partial.waypoints, &sector.fixes);
+        add(&mut self.partial.waypoints, &sector.vors);
+        add(&mut self.partial.waypoints, &sector.ndbs);
+        add(&mut self.partial.waypoints, &sector.airports);
+        self
+    }
+
+    pub fn try_read(mut self) -> SectorResult<ExtendedSector> {
+        let lines = DecodedLines::new(self.source, self.encoding);
+        for (mut line_number, line) in lines.enumerate() {
+            line_number += 1;
+            let line = match line? {
+                DecodedLine::Text(line) => line,
+                DecodedLine::Invalid(line) => {
+                    self.partial.extended_sector.non_critical_errors.push(
+                        Diagnostic::new(
+                            line_number,
+                            line.trim_end(),
+                            self.current_section.name(),
+                            Severity::Error,
+                            Error::InvalidEncoding,
+                        )
+                        .with_file(self.file_name.as_deref()),
+                    );
+                    continue;
+                }
+            };
+            let mut line = line.trim_end();
+
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+            if line.contains(';') {
+                let mut line_split = line.split(';');
+                line = line_split.next().unwrap().trim_end();
+            }
+            if line.starts_with('[') {
+                self.current_section = parse_ese_section(line);
+                continue;
+            }
+
+            let result = match self.current_section {
+                EseSection::Positions => self.partial.parse_position_line(line),
+                EseSection::Airspace => self.partial.parse_airspace_line(line),
+                EseSection::FreeText => self.partial.parse_free_text_line(line),
+                EseSection::SidsStars => self.partial.parse_procedure_line(line),
+                EseSection::Other => Ok(()),
+            };
+            if let Err(e) = result {
+                self.partial.extended_sector.non_critical_errors.push(
+                    Diagnostic::new(
+                        line_number,
+                        line,
+                        self.current_section.name(),
+                        Severity::Error,
+                        e,
+                    )
+                    .with_file(self.file_name.as_deref()),
+                );
+            }
+        }
+        Ok(self.partial.extended_sector)
+    }
+}
+
+#[derive(Debug, Default)]
+struct PartialExtendedSector {
+    waypoints: HashMap<String, Position<Valid>>,
+    extended_sector: ExtendedSector,
+}
+impl PartialExtendedSector {
+    fn parse_position_line(&mut self, value: &str) -> SectorResult<()> {
+        let sections = value.split(':').map(str::trim).collect::<Vec<_>>();
+        if sections.len() < 7 {
+            return Err(Error::InvalidControllerPosition);
+        }
+        let squawk_range = match (sections.get(9), sections.get(10)) {
+            (Some(start), Some(end)) if !start.is_empty() && !end.is_empty() => {
+                Some((start.to_string(), end.to_string()))
+            }
+            _ => None,
+        };
+        let coordinates = sections.get(11..).unwrap_or_default().chunks_exact(2);
+        if !coordinates.remainder().is_empty() {
+            return Err(Error::InvalidControllerPosition);
+        }
+        let visibility_points = coordinates
+            .map(|pair| {
+                Position::try_new_from_es(pair[0], pair[1])
+                    .and_then(|position| position.validate())
+                    .map_err(|_| Error::InvalidControllerPosition)
+            })
+            .collect::<SectorResult<Vec<_>>>()?;
+
+        self.extended_sector.positions.push(ControllerPosition {
+            name: sections[0].to_owned(),
+            radio_callsign: sections[1].to_owned(),
+            frequency: sections[2].to_owned(),
+            identifier: sections[3].to_owned(),
+            middle_letter: sections[4].to_owned(),
+            prefix: sections[5].to_owned(),
+            suffix: sections[6].to_owned(),
+            squawk_range,
+            visibility_points,
+        });
+        Ok(())
+    }
+
+    fn parse_airspace_line(&mut self, value: &str) -> SectorResult<()> {
+        let sections = value.split(':').map(str::trim).collect::<Vec<_>>();
+        let extended_sector = &mut self.extended_sector;
+        match sections[0].to_uppercase().as_str() {
+            "SECTORLINE" => {
+                let name = sections.get(1).ok_or(Error::InvalidSectorLine)?;
+                extended_sector.sector_lines.push(SectorLine {
+                    name: name.to_string(),
+                    shape: SectorLineShape::Line(vec![]),
+                });
+            }
+            "CIRCLE_SECTORLINE" => {
+                let (name, centre, radius) = match sections.len() {
+                    4 => {
+                        let centre = self
+                            .waypoints
+                            .get(sections[2])
+                            .copied()
+                            .ok_or(Error::InvalidSectorLine)?;
+                        (sections[1], centre, sections[3])
+                    }
+                    5 => {
+                        let centre = Position::try_new_from_es(sections[2], sections[3])
+                            .and_then(|position| position.validate())
+                            .map_err(|_| Error::InvalidSectorLine)?;
+                        (sections[1], centre, sections[4])
+                    }
+                    _ => return Err(Error::InvalidSectorLine),
+                };
+                let radius_n_mi = radius.parse().map_err(|_| Error::InvalidSectorLine)?;
+                extended_sector.sector_lines.push(SectorLine {
+                    name: name.to_owned(),
+                    shape: SectorLineShape::Circle {
+                        centre,
+                        radius_n_mi,
+                    },
+                });
+            }
+            "COORD" => {
+                let lat = sections.get(1).ok_or(Error::InvalidSectorLine)?;
+                let lon = sections.get(2).ok_or(Error::InvalidSectorLine)?;
+                let position = Position::try_new_from_es(lat, lon)?.validate()?;
+                match extended_sector.sector_lines.last_mut() {
+                    Some(SectorLine {
+                        shape: SectorLineShape::Line(vertices),
+                        ..
+                    }) => vertices.push(position),
+                    _ => return Err(Error::InvalidSectorLine),
+                }
+            }
+            "DISPLAY" => {
+                if extended_sector.sector_lines.is_empty() {
+                    return Err(Error::InvalidSectorLine);
+                }
+            }
+            "SECTOR" => {
+                if sections.len() < 4 {
+                    return Err(Error::InvalidAirspaceSector);
+                }
+                let floor_ft = sections[2]
+                    .parse()
+                    .map_err(|_| Error::InvalidAirspaceSector)?;
+                let ceiling_ft = sections[3]
+                    .parse()
+                    .map_err(|_| Error::InvalidAirspaceSector)?;
+                extended_sector.sectors.push(AirspaceSector {
+                    name: sections[1].to_owned(),
+                    floor_ft,
+                    ceiling_ft,
+                    ..Default::default()
+                });
+            }
+            keyword @ ("OWNER" | "ALTOWNER" | "BORDER" | "ACTIVE" | "DEPAPT" | "ARRAPT") => {
+                let sector = extended_sector
+                    .sectors
+                    .last_mut()
+                    .ok_or(Error::InvalidAirspaceSector)?;
+                let values = sections[1..]
+                    .iter()
+                    .filter(|value| !value.is_empty())
+                    .map(|value| value.to_string());
+                match keyword {
+                    "OWNER" => sector.owners.extend(values),
+                    "BORDER" => sector.borders.extend(values),
+                    "DEPAPT" => sector.departure_airports.extend(values),
+                    "ARRAPT" => sector.arrival_airports.extend(values),
+                    "ACTIVE" => sector.active.push(sections[1..].join(":")),
+                    _ => {
+                        let mut values = values;
+                        let name = values.next().ok_or(Error::InvalidAirspaceSector)?;
+                        sector.alt_owners.push((name, values.collect()));
+                    }
+                }
+            }
+            _ => return Err(Error::InvalidAirspaceSector),
+        }
+        Ok(())
+    }
+
+    fn parse_free_text_line(&mut self, value: &str) -> SectorResult<()> {
+        let mut sections = value.splitn(4, ':');
+        let lat = sections.next().ok_or(Error::InvalidFreeText)?;
+        let lon = sections.next().ok_or(Error::InvalidFreeText)?;
+        let group = sections.next().ok_or(Error::InvalidFreeText)?;
+        let text = sections.next().ok_or(Error::InvalidFreeText)?;
+        let position = Position::try_new_from_es(lat, lon)?.validate()?;
+
+        let free_text = &mut self.extended_sector.free_text;
+        let entry = FreeText {
+            text: text.to_owned(),
+            position,
+        };
+        if let Some(group) = free_text.iter_mut().find(|entry| entry.name == group) {
+            group.entries.push(entry);
+        } else {
+            free_text.push(FreeTextGroup {
+                name: group.to_owned(),
+                entries: vec![entry],
+            });
+        }
+        Ok(())
+    }
+
+    fn parse_procedure_line(&mut self, value: &str) -> SectorResult<()> {
+        let sections = value.split(':').map(str::trim).collect::<Vec<_>>();
+        if sections.len() < 5 {
+            return Err(Error::InvalidProcedure);
+        }
+        let procedure_type = match sections[0].to_uppercase().as_str() {
+            "SID" => ProcedureType::Sid,
+            "STAR" => ProcedureType::Star,
+            _ => return Err(Error::InvalidProcedure),
+        };
+        self.extended_sector.procedures.push(Procedure {
+            procedure_type,
+            airport: sections[1].to_owned(),
+            runway: sections[2].to_owned(),
+            name: sections[3].to_owned(),
+            route: sections[4].split_whitespace().map(str::to_owned).collect(),
+        });
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EseSection {
+    Positions,
+    Airspace,
+    FreeText,
+    SidsStars,
+    Other,
+}
+impl EseSection {
+    fn name(&self) -> Option<&'static str> {
+        match self {
+            Self::Positions => Some("[POSITIONS]"),
+            Self::Airspace => Some("[AIRSPACE]"),
+            Self::FreeText => Some("[FREETEXT]"),
+            Self::SidsStars => Some("[SIDSSTARS]"),
+            Self::Other => None,
+        }
+    }
+}
+
+fn parse_ese_section(value: &str) -> EseSection {
+    match value.to_uppercase().as_str() {
+        "[POSITIONS]" => EseSection::Positions,
+        "[AIRSPACE]" => EseSection::Airspace,
+        "[FREETEXT]" => EseSection::FreeText,
+        "[SIDSSTARS]" => EseSection::SidsStars,
+        _ => EseSection::Other,
+    }
+}
+
+/// The contents of a `.ese` file.
+#[derive(Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtendedSector {
+    pub positions: Vec<ControllerPosition>,
+    pub sector_lines: Vec<SectorLine>,
+    pub sectors: Vec<AirspaceSector>,
+    pub free_text: Vec<FreeTextGroup>,
+    pub procedures: Vec<Procedure>,
+
+    pub non_critical_errors: Vec<Diagnostic>,
+}
+impl ExtendedSector {
+    pub fn position(&self, identifier: &str) -> Option<&ControllerPosition> {
+        self.positions
+            .iter()
+            .find(|position| position.identifier == identifier)
+    }
+
+    pub fn sector_line(&self, name: &str) -> Option<&SectorLine> {
+        self.sector_lines.iter().find(|line| line.name == name)
+    }
+
+    /// The procedures available at an airport, optionally restricted to a single runway.
+    pub fn procedures_for<'a>(
+        &'a self,
+        airport: &'a str,
+        runway: Option<&'a str>,
+    ) -> impl Iterator<Item = &'a Procedure> + 'a {
+        self.procedures.iter().filter(move |procedure| {
+            procedure.airport == airport && runway.is_none_or(|runway| procedure.runway == runway)
+        })
+    }
+
+    /// Joins the border lines of a sector into a single polygon.
+    ///
+    /// Lines are chained by matching their end points, reversing them where needed. Returns `None`
+    /// if a border line is missing or the lines do not join up.
+    pub fn sector_polygon(&self, sector: &AirspaceSector) -> Option<Vec<Position<Valid>>> {
+        let mut remaining = Vec::with_capacity(sector.borders.len());
+        for border in &sector.borders {
+            match &self.sector_line(border)?.shape {
+                SectorLineShape::Line(vertices) if !vertices.is_empty() => {
+                    remaining.push(vertices.clone())
+                }
+                SectorLineShape::Circle {
+                    centre,
+                    radius_n_mi,
+                } if sector.borders.len() == 1 => {
+                    return Some(circle_vertices(*centre, *radius_n_mi))
+                }
+                _ => return None,
+            }
+        }
+
+        let mut polygon = if remaining.is_empty() {
+            return None;
+        } else {
+            remaining.remove(0)
+        };
+        while !remaining.is_empty() {
+            let end = *polygon.last()?;
+            let (index, reverse) = remaining.iter().enumerate().find_map(|(i, line)| {
+                if line.first() == Some(&end) {
+                    Some((i, false))
+                } else if line.last() == Some(&end) {
+                    Some((i, true))
+                } else {
+                    None
+                }
+            })?;
+            let mut line = remaining.remove(index);
+            if reverse {
+                line.reverse();
+            }
+            polygon.extend(line.into_iter().skip(1));
+        }
+        if polygon.len() > 1 && polygon.first() == polygon.last() {
+            polygon.pop();
+        }
+        Some(polygon)
+    }
+}
+
+fn circle_vertices(centre: Position<Valid>, radius_n_mi: f64) -> Vec<Position<Valid>> {
+    let radius_m = radius_n_mi * METRES_PER_N_MI;
+    (0..72)
+        .map(|i| centre.destination(radius_m, i as f64 * 5.0))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ControllerPosition {
+    pub name: String,
+    pub radio_callsign: String,
+    pub frequency: String,
+    pub identifier: String,
+    pub middle_letter: String,
+    pub prefix: String,
+    pub suffix: String,
+    pub squawk_range: Option<(String, String)>,
+    pub visibility_points: Vec<Position<Valid>>,
+}
+impl ControllerPosition {
+    pub fn callsign(&self) -> String {
+        if self.middle_letter.is_empty() || self.middle_letter == "-" {
+            format!("{}_{}", self.prefix, self.suffix)
+        } else {
+            format!("{}_{}_{}", self.prefix, self.middle_letter, self.suffix)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SectorLine {
+    pub name: String,
+    pub shape: SectorLineShape,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SectorLineShape {
+    Line(Vec<Position<Valid>>),
+    Circle {
+        centre: Position<Valid>,
+        radius_n_mi: f64,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AirspaceSector {
+    pub name: String,
+    pub floor_ft: u32,
+    pub ceiling_ft: u32,
+    pub owners: Vec<String>,
+    pub alt_owners: Vec<(String, Vec<String>)>,
+    pub borders: Vec<String>,
+    pub active: Vec<String>,
+    pub departure_airports: Vec<String>,
+    pub arrival_airports: Vec<String>,
+}
+impl AirspaceSector {
+    pub fn contains_altitude(&self, altitude_ft: u32) -> bool {
+        (self.floor_ft..=self.ceiling_ft).contains(&altitude_ft)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FreeTextGroup {
+    pub name: String,
+    pub entries: Vec<FreeText>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FreeText {
+    pub text: String,
+    pub position: Position<Valid>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Procedure {
+    pub procedure_type: ProcedureType,
+    pub airport: String,
+    pub runway: String,
+    pub name: String,
+    pub route: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProcedureType {
+    Sid,
+    Star,
+}
+
+#[test]
+fn test_read_ese() {
+    let ese = "\
+[POSITIONS]
+EGLL_N_APP:Heathrow Approach:119.730:LLN:N:EGLL:APP:-:-:0401:0477:N051.28.39.000:W000.27.41.000
+EGLL_S_APP:Heathrow Approach:120.400:LLS:S:EGLL:APP:-:-:0401:0477:N051.28.39.000
+
+[SIDSSTARS]
+SID:EGLL:27L:BPK7F:BPK
+STAR:EGLL:27L:BIG1E:SPEAR BIG
+
+[AIRSPACE]
+SECTORLINE:A
+COORD:N051.00.00.000:W001.00.00.000
+COORD:N052.00.00.000:W001.00.00.000
+COORD:N052.00.00.000:W000.00.00.000
+SECTORLINE:B
+COORD:N051.00.00.000:W001.00.00.000
+COORD:N052.00.00.000:W000.00.00.000
+
+SECTOR:LON_S·LTC:0:24500
+OWNER:LLN:LTC
+BORDER:A:B
+
+[FREETEXT]
+N051.28.39.000:W000.27.41.000:EGLL:Heathrow
+";
+    let extended_sector = EseReader::new(ese.as_bytes()).try_read().unwrap();
+    assert_eq!(extended_sector.non_critical_errors.len(), 1);
+    assert_eq!(extended_sector.non_critical_errors[0].line_number, 3);
+    assert!(extended_sector.position("LLS").is_none());
+
+    let position = extended_sector.position("LLN").unwrap();
+    assert_eq!(position.callsign(), "EGLL_N_APP");
+    assert_eq!(position.visibility_points.len(), 1);
+
+    assert_eq!(
+        extended_sector.procedures_for("EGLL", Some("27L")).count(),
+        2
+    );
+
+    let sector = &extended_sector.sectors[0];
+    assert_eq!(sector.ceiling_ft, 24500);
+    assert_eq!(sector.owners, vec!["LLN", "LTC"]);
+    assert_eq!(extended_sector.sector_polygon(sector).unwrap().len(), 3);
+
+    assert_eq!(extended_sector.free_text[0].entries[0].text, "Heathrow");
+}
@@ -0,0 +1,360 @@
+use std::fmt::{Display, Write};
+
+use crate::{
+    colour::Colour,
+    json::string,
+    line::{ColouredLine, Line, LineGroup},
+    position::{Position, Valid},
+    sector::{LineCategory, Sector},
+    waypoint::Waypoint,
+};
+
+/// Decimal places written for each coordinate, which is about a centimetre and finer than the
+/// thousandths of a second used in sector files.
+const COORDINATE_PRECISION: usize = 7;
+
+/// The kinds of feature written by [`Sector::to_geojson`]. Each feature has a `layer` property
+/// naming its layer, and [`Sector::to_geojson_layers`] gives each layer a collection of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GeoJsonLayer {
+    Airports,
+    Vors,
+    Ndbs,
+    Fixes,
+    Runways,
+    Lines(LineCategory),
+    Regions,
+    Labels,
+}
+impl GeoJsonLayer {
+    pub fn all() -> impl Iterator<Item = GeoJsonLayer> {
+        [
+            Self::Airports,
+            Self::Vors,
+            Self::Ndbs,
+            Self::Fixes,
+            Self::Runways,
+        ]
+        .into_iter()
+        .chain(LineCategory::ALL.into_iter().map(Self::Lines))
+        .chain([Self::Regions, Self::Labels])
+    }
+}
+impl Display for GeoJsonLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Airports => "airports",
+                Self::Vors => "vors",
+                Self::Ndbs => "ndbs",
+                Self::Fixes => "fixes",
+                Self::Runways => "runways",
+                Self::Lines(LineCategory::Artcc) => "artcc",
+                Self::Lines(LineCategory::ArtccHigh) => "artcc_high",
+                Self::Lines(LineCategory::ArtccLow) => "artcc_low",
+                Self::Lines(LineCategory::LowAirway) => "low_airways",
+                Self::Lines(LineCategory::HighAirway) => "high_airways",
+                Self::Lines(LineCategory::Sid) => "sids",
+                Self::Lines(LineCategory::Star) => "stars",
+                Self::Lines(LineCategory::Geo) => "geo",
+                Self::Regions => "regions",
+                Self::Labels => "labels",
+            }
+        )
+    }
+}
+
+impl Sector {
+    /// The whole sector as a single GeoJSON `FeatureCollection`.
+    pub fn to_geojson(&self) -> String {
+        let features = GeoJsonLayer::all()
+            .flat_map(|layer| self.geojson_features(layer))
+            .collect::<Vec<_>>();
+        feature_collection(&features)
+    }
+
+    /// A GeoJSON `FeatureCollection` for each layer, for tools that expect one kind of feature
+    /// per file.
+    pub fn to_geojson_layers(&self) -> Vec<(GeoJsonLayer, String)> {
+        GeoJsonLayer::all()
+            .map(|layer| (layer, feature_collection(&self.geojson_features(layer))))
+            .collect()
+    }
+
+    fn geojson_features(&self, layer: GeoJsonLayer) -> Vec<String> {
+        let mut features = vec![];
+        match layer {
+            GeoJsonLayer::Airports => {
+                for airport in &self.airports {
+                    features.push(feature(
+                        layer,
+                        &point(&airport.position),
+                        &[
+                            ("identifier", string(&airport.identifier)),
+                            ("tower_frequency", string(&airport.tower_frequency)),
+                            (
+                                "airspace_class",
+                                string(&airport.airspace_class.to_string()),
+                            ),
+                        ],
+                    ));
+                }
+            }
+            GeoJsonLayer::Vors => {
+                for vor in &self.vors {
+                    features.push(beacon(layer, vor, &vor.frequency));
+                }
+            }
+            GeoJsonLayer::Ndbs => {
+                for ndb in &self.ndbs {
+                    features.push(beacon(layer, ndb, &ndb.frequency));
+                }
+            }
+            GeoJsonLayer::Fixes => {
+                for fix in &self.fixes {
+                    features.push(feature(
+                        layer,
+                        &point(&fix.position),
+                        &[("identifier", string(&fix.identifier))],
+                    ));
+                }
+            }
+            GeoJsonLayer::Runways => {
+                for airport in &self.airports {
+                    for runway in &airport.runways {
+                        let (end_a, end_b) = (&runway.end_a, &runway.end_b);
+                        features.push(feature(
+                            layer,
+                            &line_string(&[end_a.td_threshold_pos, end_b.td_threshold_pos]),
+                            &[
+                                ("airport", string(&airport.identifier)),
+                                ("end_a", string(&end_a.identifier())),
+                                ("end_b", string(&end_b.identifier())),
+                                ("heading_a", end_a.magnetic_hdg.value().to_string()),
+                                ("heading_b", end_b.magnetic_hdg.value().to_string()),
+                            ],
+                        ));
+                    }
+                }
+            }
+            GeoJsonLayer::Lines(category) => {
+                for group in self.line_groups(category) {
+                    features.extend(line_group(layer, group));
+                }
+            }
+            GeoJsonLayer::Regions => {
+                for group in &self.regions {
+                    for region in &group.regions {
+                        let Some(geometry) = polygon(&region.vertices) else {
+                            continue;
+                        };
+                        features.push(feature(
+                            layer,
+                            &geometry,
+                            &[
+                                ("name", string(&group.name)),
+                                ("colour", colour(Some(region.colour))),
+                            ],
+                        ));
+                    }
+                }
+            }
+            GeoJsonLayer::Labels => {
+                for group in &self.labels {
+                    for label in &group.labels {
+                        features.push(feature(
+                            layer,
+                            &point(&label.position),
+                            &[
+                                ("group", string(&group.name)),
+                                ("text", string(&label.name)),
+                                ("colour", colour(Some(label.colour))),
+                            ],
+                        ));
+                    }
+                }
+            }
+        }
+        features
+    }
+}
+
+fn beacon(layer: GeoJsonLayer, beacon: &impl Waypoint, frequency: &str) -> String {
+    feature(
+        layer,
+        &point(&beacon.position()),
+        &[
+            ("identifier", string(beacon.identifier())),
+            ("frequency", string(frequency)),
+        ],
+    )
+}
+
+/// A `MultiLineString` for each colour used in the group, so the colours survive the trip.
+fn line_group(layer: GeoJsonLayer, group: &LineGroup<ColouredLine>) -> Vec<String> {
+    let mut by_colour: Vec<(Option<Colour>, Vec<&ColouredLine>)> = vec![];
+    for line in &group.lines {
+        match by_colour
+            .iter_mut()
+            .find(|(colour, _)| *colour == line.colour)
+        {
+            Some((_, lines)) => lines.push(line),
+            None => by_colour.push((line.colour, vec![line])),
+        }
+    }
+    by_colour
+        .into_iter()
+        .map(|(line_colour, lines)| {
+            let coordinates = lines
+                .iter()
+                .map(|line| coordinate_list(&[line.start(), line.end()]))
+                .collect::<Vec<_>>()
+                .join(",");
+            feature(
+                layer,
+                &format!(
+                    "{{\"type\":\"MultiLineString\",\"coordinates\":[{}]}}",
+                    coordinates
+                ),
+                &[
+                    ("name", string(&group.name)),
+                    ("colour", colour(line_colour)),
+                ],
+            )
+        })
+        .collect()
+}
+
+fn feature_collection(features: &[String]) -> String {
+    format!(
+        "{{\"type\":\"FeatureCollection\",\"features\":[{}]}}",
+        features.join(",")
+    )
+}
+
+/// `properties` are written as given, so each value must already be JSON.
+fn feature(layer: GeoJsonLayer, geometry: &str, properties: &[(&str, String)]) -> String {
+    let mut json = format!(
+        "{{\"type\":\"Feature\",\"geometry\":{},\"properties\":{{\"layer\":\"{}\"",
+        geometry, layer
+    );
+    for (name, value) in properties {
+        write!(json, ",{}:{}", string(name), value).unwrap();
+    }
+    json.push_str("}}");
+    json
+}
+
+fn point(position: &Position<Valid>) -> String {
+    format!(
+        "{{\"type\":\"Point\",\"coordinates\":{}}}",
+        coordinate(position)
+    )
+}
+
+fn line_string(positions: &[Position<Valid>]) -> String {
+    format!(
+        "{{\"type\":\"LineString\",\"coordinates\":{}}}",
+        coordinate_list(positions)
+    )
+}
+
+/// Regions are closed and wound anticlockwise, as GeoJSON expects of an outer ring. Regions with
+/// fewer than three vertices aren't areas, so give `None`.
+fn polygon(vertices: &[Position<Valid>]) -> Option<String> {
+    if vertices.len() < 3 {
+        return None;
+    }
+    let mut ring = vertices.to_vec();
+    if ring.first() != ring.last() {
+        ring.push(ring[0]);
+    }
+    let twice_area = ring
+        .windows(2)
+        .map(|pair| pair[0].lon * pair[1].lat - pair[1].lon * pair[0].lat)
+        .sum::<f64>();
+    if twice_area < 0.0 {
+        ring.reverse();
+    }
+    Some(format!(
+        "{{\"type\":\"Polygon\",\"coordinates\":[{}]}}",
+        coordinate_list(&ring)
+    ))
+}
+
+fn coordinate_list(positions: &[Position<Valid>]) -> String {
+    let coordinates = positions.iter().map(coordinate).collect::<Vec<_>>();
+    format!("[{}]", coordinates.join(","))
+}
+
+/// GeoJSON puts the longitude first.
+pub(crate) fn coordinate(position: &Position<Valid>) -> String {
+    format!(
+        "[{:.*},{:.*}]",
+        COORDINATE_PRECISION, position.lon, COORDINATE_PRECISION, position.lat
+    )
+}
+
+pub(crate) fn colour(colour: Option<Colour>) -> String {
+    match colour {
+        Some(Colour { r, g, b, a: 255 }) => format!("\"#{:02x}{:02x}{:02x}\"", r, g, b),
+        Some(Colour { r, g, b, a }) => format!("\"#{:02x}{:02x}{:02x}{:02x}\"", r, g, b, a),
+        None => "null".to_owned(),
+    }
+}
+
+#[test]
+fn test_geojson() {
+    use crate::reader::test_sector;
+
+    let sector = test_sector(
+        "GeoJSON test",
+        "\
+#define COLOR_Coast 8421504
+[VOR]
+MAY 117.900 N051.01.02.000 E000.06.58.000
+[AIRPORT]
+EGKK 126.830 N051.08.53.000 W000.11.25.000 D
+[RUNWAY]
+08R 26L 077 257 N051.08.48.020 W000.12.57.930 N051.09.21.650 W000.09.31.990 EGKK
+[GEO]
+Coast N051.00.00.000 E000.00.00.000 N051.01.00.000 E000.01.00.000 COLOR_Coast
+Coast N051.01.00.000 E000.01.00.000 N051.02.00.000 E000.01.00.000
+[REGIONS]
+REGIONNAME Apron
+COLOR_Coast N051.00.00.000 E000.00.00.000
+N051.01.00.000 E000.00.00.000
+N051.01.00.000 E000.01.00.000
+[LABELS]
+\"Gatwick Tower\" N051.08.53.000 W000.11.25.000 COLOR_Coast
+",
+    );
+    let json = sector.to_geojson();
+
+    assert!(json.starts_with("{\"type\":\"FeatureCollection\",\"features\":[{\"type\":\"Feature\""));
+    assert!(json.contains(
+        "{\"type\":\"Point\",\"coordinates\":[-0.1902778,51.1480556]},\
+         \"properties\":{\"layer\":\"airports\",\"identifier\":\"EGKK\""
+    ));
+    assert!(json.contains(
+        "\"layer\":\"runways\",\"airport\":\"EGKK\",\"end_a\":\"08R\",\"end_b\":\"26L\""
+    ));
+    // The two GEO lines are different colours, so are written as two features.
+    assert_eq!(json.matches("\"layer\":\"geo\"").count(), 2);
+    assert!(json.contains("\"layer\":\"geo\",\"name\":\"Coast\",\"colour\":\"#808080\""));
+    assert!(json.contains("\"layer\":\"geo\",\"name\":\"Coast\",\"colour\":null"));
+    // The region is given clockwise, so is reversed and closed.
+    assert!(json.contains(
+        "{\"type\":\"Polygon\",\"coordinates\":[[[0.0000000,51.0000000],[0.0166667,51.0166667],\
+         [0.0000000,51.0166667],[0.0000000,51.0000000]]]}"
+    ));
+    assert!(json.contains("\"group\":\"SCT2\",\"text\":\"Gatwick Tower\""));
+
+    let layers = sector.to_geojson_layers();
+    assert_eq!(layers.len(), 15);
+    let (layer, vors) = &layers[1];
+    assert_eq!(layer.to_string(), "vors");
+    assert_eq!(vors.matches("\"type\":\"Feature\"").count(), 1);
+}
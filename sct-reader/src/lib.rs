@@ -1,20 +1,38 @@
 #![allow(unused)]
 
-use std::str::FromStr;
+use std::{fmt::Display, str::FromStr};
 
 use error::Error;
+pub mod airspace;
+pub mod airway;
+pub mod approach;
+pub mod arinc;
 pub mod colour;
+pub mod coordinate;
+pub mod diagnostic;
+pub mod diff;
+pub mod encoding;
 pub mod error;
+pub mod ese;
+pub mod geojson;
+mod json;
 pub mod line;
+pub mod lint;
+pub mod merge;
+pub mod overlay;
 mod partial;
 pub mod position;
 pub mod reader;
 pub mod sector;
+pub mod spatial;
 pub mod waypoint;
+pub mod writer;
+mod xml;
 
 pub type SectorResult<T> = std::result::Result<T, error::Error>;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AirspaceClass {
     A,
     B,
@@ -40,3 +58,20 @@ impl FromStr for AirspaceClass {
         Ok(result)
     }
 }
+impl Display for AirspaceClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                AirspaceClass::A => "A",
+                AirspaceClass::B => "B",
+                AirspaceClass::C => "C",
+                AirspaceClass::D => "D",
+                AirspaceClass::E => "E",
+                AirspaceClass::F => "F",
+                AirspaceClass::G => "G",
+            }
+        )
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use crate::{
     colour::Colour,
     position::{Position, Valid},
@@ -9,6 +11,7 @@ pub trait Line {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SimpleLine {
     pub start: Position<Valid>,
     pub end: Position<Valid>,
@@ -27,10 +30,13 @@ impl Line for SimpleLine {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ColouredLine {
     pub line: SimpleLine,
     pub colour: Option<Colour>,
+    /// The `#define` name the colour was given by, as written where it was defined.
+    pub colour_name: Option<Arc<str>>,
 }
 impl ColouredLine {
     pub fn new(
@@ -41,8 +47,13 @@ impl ColouredLine {
         ColouredLine {
             line: SimpleLine::new(start, end),
             colour,
+            colour_name: None,
         }
     }
+    pub fn with_colour_name(mut self, colour_name: Option<Arc<str>>) -> Self {
+        self.colour_name = colour_name;
+        self
+    }
     pub fn colour(&self) -> Option<Colour> {
         self.colour
     }
@@ -56,7 +67,8 @@ impl Line for ColouredLine {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LineGroup<L: Line> {
     pub name: String,
     pub lines: Vec<L>,
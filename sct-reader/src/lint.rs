@@ -0,0 +1,482 @@
+//! Checks a sector for mistakes that don't stop it loading, but are almost certainly wrong.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use crate::{
+    airspace::segments_cross,
+    airway::key,
+    diagnostic::{Diagnostic, Severity},
+    error::Error,
+    line::Line,
+    position::{MagneticHeading, Position, Valid},
+    sector::{LineCategory, Sector},
+    spatial::BoundingBox,
+    waypoint::Waypoint,
+};
+
+/// Waypoints sharing an identifier this close together are the same place, such as a fix on top
+/// of the VOR it's named after.
+const DUPLICATE_TOLERANCE_M: f64 = 10.0;
+
+/// How far beyond the airports, navaids and fixes a label can be before it looks misplaced.
+const EXTENT_MARGIN_N_MI: f64 = 50.0;
+
+/// How far the `[INFO]` centre point can be from the nearest airport, navaid or fix.
+const CENTRE_DISTANCE_N_MI: f64 = 100.0;
+
+/// How far a runway's declared magnetic heading can be from the one worked out from its
+/// thresholds and the `[INFO]` variation. Headings are only given to the nearest degree.
+const HEADING_TOLERANCE_DEG: f32 = 3.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lint {
+    /// Waypoints with the same identifier in different places, in the order they appear.
+    DuplicateIdentifier {
+        identifier: String,
+        positions: Vec<Position<Valid>>,
+    },
+    /// A runway naming an airport that isn't in `[AIRPORT]`, so it was left out.
+    UnknownAirport(Diagnostic),
+    /// A SID or STAR line using a fix that isn't defined, so it was left out.
+    UnknownFix(Diagnostic),
+    /// Any other problem found while reading the file.
+    Parse(Diagnostic),
+    ZeroLengthSegments {
+        category: LineCategory,
+        group: String,
+        count: usize,
+    },
+    TooFewVertices {
+        group: String,
+        vertices: usize,
+    },
+    SelfIntersectingRegion {
+        group: String,
+        at: Position<Valid>,
+    },
+    /// A `#define` that no line, region or label refers to by name. Items given a colour as a
+    /// number don't use any `#define`, even one with the same value.
+    UnusedColour(String),
+    LabelOutsideExtent {
+        group: String,
+        text: String,
+        position: Position<Valid>,
+    },
+    DistantCentre {
+        centre: Position<Valid>,
+        distance_n_mi: f64,
+    },
+    /// A runway whose magnetic heading doesn't match the direction between its thresholds.
+    RunwayHeadingMismatch {
+        airport: String,
+        runway: String,
+        declared: MagneticHeading,
+        computed: MagneticHeading,
+    },
+}
+impl Lint {
+    /// A short name for the check, e.g. `duplicate-identifier`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::DuplicateIdentifier { .. } => "duplicate-identifier",
+            Self::UnknownAirport(_) => "unknown-airport",
+            Self::UnknownFix(_) => "unknown-fix",
+            Self::Parse(_) => "parse",
+            Self::ZeroLengthSegments { .. } => "zero-length-segment",
+            Self::TooFewVertices { .. } => "too-few-vertices",
+            Self::SelfIntersectingRegion { .. } => "self-intersecting-region",
+            Self::UnusedColour(_) => "unused-colour",
+            Self::LabelOutsideExtent { .. } => "label-outside-extent",
+            Self::DistantCentre { .. } => "distant-centre",
+            Self::RunwayHeadingMismatch { .. } => "runway-heading-mismatch",
+        }
+    }
+
+    /// Problems that lose or misdraw data are errors; the rest are warnings.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::Parse(diagnostic) => diagnostic.severity,
+            Self::UnknownAirport(_) | Self::UnknownFix(_) | Self::TooFewVertices { .. } => {
+                Severity::Error
+            }
+            _ => Severity::Warning,
+        }
+    }
+
+    /// The diagnostic the lint came from, for lints found while reading the file.
+    pub fn diagnostic(&self) -> Option<&Diagnostic> {
+        match self {
+            Self::UnknownAirport(diagnostic)
+            | Self::UnknownFix(diagnostic)
+            | Self::Parse(diagnostic) => Some(diagnostic),
+            _ => None,
+        }
+    }
+
+    fn from_diagnostic(diagnostic: &Diagnostic) -> Lint {
+        let procedure = matches!(diagnostic.section.as_deref(), Some("[SID]" | "[STAR]"));
+        match diagnostic.error {
+            Error::UnknownAirport(_) => Self::UnknownAirport(diagnostic.clone()),
+            Error::UnknownWaypoint(_) if procedure => Self::UnknownFix(diagnostic.clone()),
+            _ => Self::Parse(diagnostic.clone()),
+        }
+    }
+}
+impl Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let es = |position: &Position<Valid>| {
+            let (lat, lon) = position.to_es();
+            format!("{} {}", lat, lon)
+        };
+        match self {
+            Self::DuplicateIdentifier {
+                identifier,
+                positions,
+            } => {
+                let positions = positions.iter().map(es).collect::<Vec<_>>();
+                write!(
+                    f,
+                    "{} is defined in {} places: {}",
+                    identifier,
+                    positions.len(),
+                    positions.join(", ")
+                )
+            }
+            Self::UnknownAirport(diagnostic)
+            | Self::UnknownFix(diagnostic)
+            | Self::Parse(diagnostic) => write!(f, "{}", diagnostic),
+            Self::ZeroLengthSegments {
+                category,
+                group,
+                count,
+            } => write!(
+                f,
+                "{} {} has {} zero-length segment{}",
+                category,
+                group,
+                count,
+                if *count == 1 { "" } else { "s" }
+            ),
+            Self::TooFewVertices { group, vertices } => write!(
+                f,
+                "Region {} has {} vertices, so has no area",
+                group, vertices
+            ),
+            Self::SelfIntersectingRegion { group, at } => {
+                write!(f, "Region {} crosses itself at {}", group, es(at))
+            }
+            Self::UnusedColour(name) => write!(f, "Colour {} is never used", name),
+            Self::LabelOutsideExtent {
+                group,
+                text,
+                position,
+            } => write!(
+                f,
+                "Label \"{}\" in {} is far from the rest of the sector, at {}",
+                text,
+                group,
+                es(position)
+            ),
+            Self::DistantCentre {
+                centre,
+                distance_n_mi,
+            } => write!(
+                f,
+                "[INFO] centre point {} is {:.0} nm from the nearest airport, navaid or fix",
+                es(centre),
+                distance_n_mi
+            ),
+            Self::RunwayHeadingMismatch {
+                airport,
+                runway,
+                declared,
+                computed,
+            } => write!(
+                f,
+                "Runway {} {} has magnetic heading {:03} but its thresholds give {:03}",
+                airport,
+                runway,
+                declared.value_u16(),
+                computed.value_u16()
+            ),
+        }
+    }
+}
+
+impl Sector {
+    /// Everything found while reading the sector, followed by the checks on the sector itself.
+    pub fn lint(&self) -> Vec<Lint> {
+        let mut lints = self
+            .non_critical_errors
+            .iter()
+            .map(Lint::from_diagnostic)
+            .collect::<Vec<_>>();
+        let waypoints = self
+            .airports
+            .iter()
+            .map(waypoint)
+            .chain(self.vors.iter().map(waypoint))
+            .chain(self.ndbs.iter().map(waypoint))
+            .chain(self.fixes.iter().map(waypoint))
+            .collect::<Vec<_>>();
+
+        lints.extend(duplicate_identifiers(&waypoints));
+        for airport in &self.airports {
+            for end in airport.runway_ends().filter(|end| {
+                end.magnetic_heading_error(&self.sector_info).abs() > HEADING_TOLERANCE_DEG
+            }) {
+                lints.push(Lint::RunwayHeadingMismatch {
+                    airport: airport.identifier.clone(),
+                    runway: end.identifier(),
+                    declared: end.magnetic_hdg,
+                    computed: end
+                        .true_bearing()
+                        .to_magnetic(&self.sector_info, end.td_threshold_pos),
+                });
+            }
+        }
+        for category in LineCategory::ALL {
+            for group in self.line_groups(category) {
+                let count = group
+                    .lines
+                    .iter()
+                    .filter(|line| key(line.start()) == key(line.end()))
+                    .count();
+                if count > 0 {
+                    lints.push(Lint::ZeroLengthSegments {
+                        category,
+                        group: group.name.clone(),
+                        count,
+                    });
+                }
+            }
+        }
+        for group in &self.regions {
+            for region in &group.regions {
+                if region.vertices.len() < 3 {
+                    lints.push(Lint::TooFewVertices {
+                        group: group.name.clone(),
+                        vertices: region.vertices.len(),
+                    });
+                } else if let Some(at) = self_intersection(&region.vertices) {
+                    lints.push(Lint::SelfIntersectingRegion {
+                        group: group.name.clone(),
+                        at,
+                    });
+                }
+            }
+        }
+        lints.extend(self.unused_colours());
+
+        let positions = waypoints.iter().map(|(_, position)| position);
+        if let Some(extent) = BoundingBox::from_points(positions) {
+            let corner = |lat, lon| Position::new(lat, lon).validate().unwrap();
+            let extent =
+                BoundingBox::around(corner(extent.min_lat, extent.min_lon), EXTENT_MARGIN_N_MI)
+                    .union(&BoundingBox::around(
+                        corner(extent.max_lat, extent.max_lon),
+                        EXTENT_MARGIN_N_MI,
+                    ));
+            for group in &self.labels {
+                for label in group
+                    .labels
+                    .iter()
+                    .filter(|label| !extent.contains(label.position))
+                {
+                    lints.push(Lint::LabelOutsideExtent {
+                        group: group.name.clone(),
+                        text: label.name.clone(),
+                        position: label.position,
+                    });
+                }
+            }
+
+            let centre = self.sector_info.default_centre_pt;
+            let nearest = waypoints
+                .iter()
+                .map(|(_, position)| centre.distance_n_mi(position))
+                .fold(f64::INFINITY, f64::min);
+            if nearest > CENTRE_DISTANCE_N_MI {
+                lints.push(Lint::DistantCentre {
+                    centre,
+                    distance_n_mi: nearest,
+                });
+            }
+        }
+        lints
+    }
+
+    fn unused_colours(&self) -> Vec<Lint> {
+        let mut used = LineCategory::ALL
+            .into_iter()
+            .flat_map(|category| self.line_groups(category))
+            .flat_map(|group| group.lines.iter().map(|line| &line.colour_name))
+            .flatten()
+            .map(|name| name.to_lowercase())
+            .collect::<HashSet<_>>();
+        used.extend(
+            self.regions
+                .iter()
+                .flat_map(|group| group.regions.iter())
+                .filter_map(|region| region.colour_name.as_ref())
+                .map(|name| name.to_lowercase()),
+        );
+        used.extend(
+            self.labels
+                .iter()
+                .flat_map(|group| group.labels.iter())
+                .filter_map(|label| label.colour_name.as_ref())
+                .map(|name| name.to_lowercase()),
+        );
+
+        let mut unused = self
+            .colours
+            .keys()
+            .filter(|name| !used.contains(*name))
+            .cloned()
+            .collect::<Vec<_>>();
+        unused.sort();
+        unused.into_iter().map(Lint::UnusedColour).collect()
+    }
+}
+
+fn waypoint(item: &impl Waypoint) -> (&String, Position<Valid>) {
+    (item.identifier(), item.position())
+}
+
+fn duplicate_identifiers(waypoints: &[(&String, Position<Valid>)]) -> Vec<Lint> {
+    let mut order = vec![];
+    let mut by_identifier: HashMap<&String, Vec<Position<Valid>>> = HashMap::new();
+    for (identifier, position) in waypoints {
+        let positions = by_identifier.entry(identifier).or_insert_with(|| {
+            order.push(*identifier);
+            vec![]
+        });
+        if positions
+            .iter()
+            .all(|other| other.distance_m(position) > DUPLICATE_TOLERANCE_M)
+        {
+            positions.push(*position);
+        }
+    }
+    order
+        .into_iter()
+        .filter(|identifier| by_identifier[identifier].len() > 1)
+        .map(|identifier| Lint::DuplicateIdentifier {
+            identifier: identifier.clone(),
+            positions: by_identifier[identifier].clone(),
+        })
+        .collect()
+}
+
+/// Where the first pair of edges that aren't next to each other cross, treating the region as
+/// closed.
+fn self_intersection(vertices: &[Position<Valid>]) -> Option<Position<Valid>> {
+    let n = vertices.len();
+    let edge = |i: usize| (vertices[i], vertices[(i + 1) % n]);
+    for i in 0..n {
+        for j in i + 2..n {
+            if i == 0 && j == n - 1 {
+                continue;
+            }
+            let ((a_1, a_2), (b_1, b_2)) = (edge(i), edge(j));
+            if segments_cross(a_1, a_2, b_1, b_2) {
+                // Where the crossing is along the first edge, treating the coordinates as flat.
+                let cross =
+                    |x_lon: f64, x_lat: f64, y_lon: f64, y_lat: f64| x_lon * y_lat - x_lat * y_lon;
+                let (d_lon, d_lat) = (a_2.lon - a_1.lon, a_2.lat - a_1.lat);
+                let (e_lon, e_lat) = (b_2.lon - b_1.lon, b_2.lat - b_1.lat);
+                let t = cross(b_1.lon - a_1.lon, b_1.lat - a_1.lat, e_lon, e_lat)
+                    / cross(d_lon, d_lat, e_lon, e_lat);
+                return Position::new(a_1.lat + t * d_lat, a_1.lon + t * d_lon)
+                    .validate()
+                    .ok();
+            }
+        }
+    }
+    None
+}
+
+#[test]
+fn test_lint() {
+    use crate::reader::test_sector;
+
+    let mut sector = test_sector(
+        "Lint test",
+        "\
+#define COLOR_Red 255
+#define COLOR_Unused 65280
+#define COLOR_Twin 255
+[AIRPORT]
+EGKK 126.830 N051.08.53.000 W000.11.25.000 D
+[RUNWAY]
+08R 26L 077 257 N051.08.48.020 W000.12.57.930 N051.09.21.650 W000.09.31.990 EGXX
+08L 26R 090 270 N051.08.48.020 W000.12.57.930 N051.09.21.650 W000.09.31.990 EGKK
+[FIXES]
+BIG N051.19.49.000 E000.02.46.000
+BIG N052.19.49.000 E000.02.46.000
+[SID]
+EGKK SID                  BIG BIG NOPE NOPE
+                          BIG BIG BIG BIG
+[GEO]
+Coast N051.00.00.000 E000.00.00.000 N051.00.00.000 E000.00.00.000 COLOR_Red
+[REGIONS]
+REGIONNAME Bowtie
+COLOR_Red N051.00.00.000 E000.00.00.000
+N051.01.00.000 E000.01.00.000
+N051.01.00.000 E000.00.00.000
+N051.00.00.000 E000.01.00.000
+REGIONNAME Sliver
+COLOR_Red N051.00.00.000 E000.00.00.000
+N051.01.00.000 E000.00.00.000
+[LABELS]
+\"Lost\" N040.00.00.000 W000.11.25.000 COLOR_Red
+",
+    );
+    // Far enough north of everything to be a distant centre.
+    sector.sector_info.default_centre_pt = Position::new(55.0, -0.19).validate().unwrap();
+    let lints = sector.lint();
+    let names = lints.iter().map(Lint::name).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec![
+            "unknown-airport",
+            "unknown-fix",
+            "duplicate-identifier",
+            "runway-heading-mismatch",
+            "runway-heading-mismatch",
+            "zero-length-segment",
+            "zero-length-segment",
+            "self-intersecting-region",
+            "too-few-vertices",
+            "unused-colour",
+            "unused-colour",
+            "label-outside-extent",
+            "distant-centre",
+        ]
+    );
+    assert_eq!(lints[0].severity(), Severity::Error);
+    assert_eq!(
+        lints[0].to_string(),
+        "Unknown airport EGXX in [RUNWAY] line 17"
+    );
+    assert_eq!(
+        lints[2].to_string(),
+        "BIG is defined in 2 places: N051.19.49.000 E000.02.46.000, N052.19.49.000 E000.02.46.000"
+    );
+    assert_eq!(
+        lints[3].to_string(),
+        "Runway EGKK 08L has magnetic heading 090 but its thresholds give 076"
+    );
+    assert_eq!(
+        lints[7].to_string(),
+        "Region Bowtie crosses itself at N051.00.30.000 E000.00.30.000"
+    );
+    // Sharing its value with a colour in use doesn't make a `#define` used.
+    assert_eq!(lints[9].to_string(), "Colour color_twin is never used");
+    assert_eq!(lints[10].to_string(), "Colour color_unused is never used");
+    assert_eq!(lints[12].severity(), Severity::Warning);
+}
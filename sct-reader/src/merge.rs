@@ -0,0 +1,443 @@
+//! Combines sectors, e.g. a national sector with the high-detail files for the aerodromes of an
+//! event.
+
+use std::collections::HashMap;
+
+use crate::{
+    diff::ItemKind,
+    line::{ColouredLine, LineGroup},
+    sector::{LabelGroup, LineCategory, Region, RegionGroup, Sector},
+    waypoint::{Waypoint, MATCH_RADIUS_N_MI},
+};
+
+/// What to do with a waypoint from the other sector when this sector already has one with the
+/// same identifier nearby.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    KeepExisting,
+    #[default]
+    Replace,
+    KeepBoth,
+}
+
+/// What to do with a line group or region group from the other sector when this sector already
+/// has one with the same name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GroupPolicy {
+    /// Add the other sector's lines or regions to the group.
+    Concatenate,
+    /// Use the other sector's lines or regions instead.
+    #[default]
+    Replace,
+}
+
+/// How [`Sector::merge`] resolves conflicts. The default lets the sector being merged in take
+/// precedence, as it's usually the more detailed one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergePolicy {
+    /// Also decides whose colour definition is kept when a name is defined in both sectors.
+    pub duplicates: DuplicatePolicy,
+    pub groups: GroupPolicy,
+}
+
+/// Where the items of a merged sector came from.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Provenance {
+    /// The `[INFO]` names of the merged sectors, in the order they were merged. Empty until the
+    /// sector has been merged with another.
+    pub sources: Vec<String>,
+    /// Indices into `sources` for each item, named as in [`SectorChange`](crate::diff::SectorChange).
+    /// Runways aren't listed, as they come with their airport.
+    items: HashMap<(ItemKind, String), Vec<usize>>,
+}
+impl Provenance {
+    /// The sources an item came from. There can be more than one for a group that was
+    /// concatenated, or for an identifier used by waypoints from different sources.
+    pub fn sources_of(&self, kind: ItemKind, name: &str) -> Vec<&str> {
+        self.items
+            .get(&(kind, name.to_owned()))
+            .into_iter()
+            .flatten()
+            .map(|index| self.sources[*index].as_str())
+            .collect()
+    }
+
+    /// The provenance of a sector that might not have been merged yet, where everything comes
+    /// from the sector itself.
+    fn of(sector: &mut Sector) -> Provenance {
+        if !sector.provenance.sources.is_empty() {
+            return std::mem::take(&mut sector.provenance);
+        }
+        let mut provenance = Provenance {
+            sources: vec![sector.sector_info.name.clone()],
+            items: HashMap::new(),
+        };
+        let mut add = |kind: ItemKind, name: &str| provenance.add(kind, name, &[0]);
+        for airport in &sector.airports {
+            add(ItemKind::Airport, &airport.identifier);
+        }
+        for vor in &sector.vors {
+            add(ItemKind::Vor, &vor.identifier);
+        }
+        for ndb in &sector.ndbs {
+            add(ItemKind::Ndb, &ndb.identifier);
+        }
+        for fix in &sector.fixes {
+            add(ItemKind::Fix, &fix.identifier);
+        }
+        for category in LineCategory::ALL {
+            for group in sector.line_groups(category) {
+                add(ItemKind::Lines(category), &group.name);
+            }
+        }
+        for group in &sector.regions {
+            add(ItemKind::Region, &group.name);
+        }
+        for group in &sector.labels {
+            for label in &group.labels {
+                add(ItemKind::Label, &format!("{}/{}", group.name, label.name));
+            }
+        }
+        provenance
+    }
+
+    fn add(&mut self, kind: ItemKind, name: &str, sources: &[usize]) {
+        let existing = self.items.entry((kind, name.to_owned())).or_default();
+        for source in sources {
+            if !existing.contains(source) {
+                existing.push(*source);
+            }
+        }
+    }
+    fn replace(&mut self, kind: ItemKind, name: &str, sources: &[usize]) {
+        self.items.insert((kind, name.to_owned()), sources.to_vec());
+    }
+}
+
+impl Sector {
+    /// Adds everything from `other` to this sector, recording where each item came from in
+    /// [`Sector::provenance`].
+    ///
+    /// Waypoints are the same waypoint if they share an identifier and are within 100 nm of each
+    /// other, and labels are the same label if they have the same text and position. Line groups,
+    /// region groups and label groups are matched by name. Runways come with their airport. The
+    /// `[INFO]` section stays as it is, as does the `.ese` data unless only `other` has any.
+    pub fn merge(&mut self, mut other: Sector, policy: MergePolicy) {
+        let mut provenance = Provenance::of(self);
+        let other_provenance = Provenance::of(&mut other);
+        let offset = provenance.sources.len();
+        provenance
+            .sources
+            .extend(other_provenance.sources.iter().cloned());
+        let mut merger = Merger {
+            policy,
+            provenance,
+            other: other_provenance,
+            offset,
+        };
+
+        for category in LineCategory::ALL {
+            let groups = std::mem::take(other.line_groups_mut(category));
+            merger.groups(
+                ItemKind::Lines(category),
+                self.line_groups_mut(category),
+                groups,
+            );
+        }
+        merger.groups(ItemKind::Region, &mut self.regions, other.regions);
+        merger.waypoints(ItemKind::Airport, &mut self.airports, other.airports);
+        merger.waypoints(ItemKind::Vor, &mut self.vors, other.vors);
+        merger.waypoints(ItemKind::Ndb, &mut self.ndbs, other.ndbs);
+        merger.waypoints(ItemKind::Fix, &mut self.fixes, other.fixes);
+
+        for group in other.labels {
+            let existing = match self
+                .labels
+                .iter()
+                .position(|existing| existing.name == group.name)
+            {
+                Some(index) => &mut self.labels[index],
+                None => {
+                    self.labels.push(LabelGroup {
+                        name: group.name.clone(),
+                        labels: vec![],
+                    });
+                    self.labels.last_mut().unwrap()
+                }
+            };
+            for label in group.labels {
+                let name = format!("{}/{}", group.name, label.name);
+                let sources = merger.other_sources(ItemKind::Label, &name);
+                let duplicate = existing.labels.iter().position(|existing| {
+                    existing.name == label.name && existing.position == label.position
+                });
+                match (duplicate, policy.duplicates) {
+                    (Some(_), DuplicatePolicy::KeepExisting) => {}
+                    (Some(index), DuplicatePolicy::Replace) => {
+                        // As with waypoints, other labels sharing the text keep their sources.
+                        let sharing = existing
+                            .labels
+                            .iter()
+                            .filter(|existing| existing.name == label.name)
+                            .count();
+                        existing.labels[index] = label;
+                        if sharing == 1 {
+                            merger.provenance.replace(ItemKind::Label, &name, &sources);
+                        } else {
+                            merger.provenance.add(ItemKind::Label, &name, &sources);
+                        }
+                    }
+                    _ => {
+                        existing.labels.push(label);
+                        merger.provenance.add(ItemKind::Label, &name, &sources);
+                    }
+                }
+            }
+        }
+
+        for (name, colour) in other.colours {
+            let keep_existing = policy.duplicates != DuplicatePolicy::Replace;
+            if !(keep_existing && self.colours.contains_key(&name)) {
+                self.colours.insert(name, colour);
+            }
+        }
+        if self.extended_sector.is_none() {
+            self.extended_sector = other.extended_sector;
+        }
+        self.non_critical_errors.extend(other.non_critical_errors);
+        self.provenance = merger.provenance;
+    }
+}
+
+struct Merger {
+    policy: MergePolicy,
+    provenance: Provenance,
+    /// The provenance of the sector being merged in, with indices into its own sources.
+    other: Provenance,
+    /// Where the other sector's sources start in the merged list.
+    offset: usize,
+}
+impl Merger {
+    fn other_sources(&self, kind: ItemKind, name: &str) -> Vec<usize> {
+        let sources = self.other.items.get(&(kind, name.to_owned()));
+        sources
+            .into_iter()
+            .flatten()
+            .map(|source| source + self.offset)
+            .collect()
+    }
+
+    fn waypoints<T: Waypoint>(&mut self, kind: ItemKind, existing: &mut Vec<T>, other: Vec<T>) {
+        let mut by_identifier: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, item) in existing.iter().enumerate() {
+            by_identifier
+                .entry(item.identifier().clone())
+                .or_default()
+                .push(index);
+        }
+        for item in other {
+            let name = item.identifier().clone();
+            let sources = self.other_sources(kind, &name);
+            let candidates = by_identifier.entry(name.clone()).or_default();
+            let duplicate = candidates
+                .iter()
+                .map(|index| {
+                    (
+                        *index,
+                        existing[*index].position().distance_n_mi(&item.position()),
+                    )
+                })
+                .filter(|(_, distance)| *distance <= MATCH_RADIUS_N_MI)
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(index, _)| index);
+            match (duplicate, self.policy.duplicates) {
+                (Some(_), DuplicatePolicy::KeepExisting) => {}
+                (Some(index), DuplicatePolicy::Replace) => {
+                    existing[index] = item;
+                    // Other waypoints sharing the identifier keep their sources.
+                    if candidates.len() == 1 {
+                        self.provenance.replace(kind, &name, &sources);
+                    } else {
+                        self.provenance.add(kind, &name, &sources);
+                    }
+                }
+                _ => {
+                    candidates.push(existing.len());
+                    existing.push(item);
+                    self.provenance.add(kind, &name, &sources);
+                }
+            }
+        }
+    }
+
+    fn groups<G: Group>(&mut self, kind: ItemKind, existing: &mut Vec<G>, other: Vec<G>) {
+        // Only the first group of a name from the other sector replaces this sector's contents,
+        // so that any more with the same name add to it.
+        let mut merged: Vec<String> = vec![];
+        for mut group in other {
+            let name = group.name().to_owned();
+            let sources = self.other_sources(kind, &name);
+            let Some(target) = existing.iter_mut().find(|existing| existing.name() == name) else {
+                self.provenance.replace(kind, &name, &sources);
+                merged.push(name);
+                existing.push(group);
+                continue;
+            };
+            if self.policy.groups == GroupPolicy::Replace && !merged.contains(&name) {
+                target.contents().clear();
+                self.provenance.replace(kind, &name, &sources);
+            } else {
+                self.provenance.add(kind, &name, &sources);
+            }
+            merged.push(name);
+            target.contents().append(group.contents());
+        }
+    }
+}
+
+/// A named group of lines or regions.
+trait Group {
+    type Item;
+    fn name(&self) -> &str;
+    fn contents(&mut self) -> &mut Vec<Self::Item>;
+}
+impl Group for LineGroup<ColouredLine> {
+    type Item = ColouredLine;
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn contents(&mut self) -> &mut Vec<ColouredLine> {
+        &mut self.lines
+    }
+}
+impl Group for RegionGroup {
+    type Item = Region;
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn contents(&mut self) -> &mut Vec<Region> {
+        &mut self.regions
+    }
+}
+
+#[test]
+fn test_merge() {
+    use crate::{colour::Colour, reader::test_sector};
+
+    let national = || {
+        test_sector(
+            "National",
+            "\
+#define COLOR_Red 255
+[AIRPORT]
+EGKK 126.830 N051.08.53.000 W000.11.25.000 D
+[FIXES]
+BIG N051.19.49.000 E000.02.46.000
+DET N051.18.14.000 E000.35.50.000
+[GEO]
+Coast N051.00.00.000 E000.00.00.000 N051.01.00.000 E000.01.00.000 COLOR_Red
+EGKK Taxiways N051.08.00.000 W000.11.00.000 N051.09.00.000 W000.11.00.000 COLOR_Red
+[LABELS]
+\"Gatwick\" N051.08.53.000 W000.11.25.000 COLOR_Red
+",
+        )
+    };
+    let aerodrome = || {
+        test_sector(
+            "EGKK detail",
+            "\
+#define COLOR_Red 16711680
+#define COLOR_Taxiway 65280
+[AIRPORT]
+EGKK 126.830 N051.08.53.000 W000.11.25.000 D
+[RUNWAY]
+08R 26L 077 257 N051.08.48.020 W000.12.57.930 N051.09.21.650 W000.09.31.990 EGKK
+[FIXES]
+BIG N051.19.50.000 E000.02.46.000
+DET N045.00.00.000 E010.00.00.000
+[GEO]
+EGKK Taxiways N051.08.10.000 W000.11.00.000 N051.08.20.000 W000.11.00.000 COLOR_Taxiway
+EGKK Taxiways N051.08.20.000 W000.11.00.000 N051.08.30.000 W000.11.00.000 COLOR_Taxiway
+EGKK Stands N051.09.00.000 W000.10.00.000 N051.09.10.000 W000.10.00.000 COLOR_Taxiway
+[LABELS]
+\"Gatwick\" N051.08.53.000 W000.11.25.000 COLOR_Red
+\"Pier 1\" N051.09.00.000 W000.10.00.000 COLOR_Red
+",
+        )
+    };
+
+    let mut concatenated = national();
+    concatenated.merge(
+        aerodrome(),
+        MergePolicy {
+            duplicates: DuplicatePolicy::KeepExisting,
+            groups: GroupPolicy::Concatenate,
+        },
+    );
+    let mut national = national();
+    national.merge(aerodrome(), MergePolicy::default());
+
+    // The nearby BIG is the same fix, but the DET 500 nm away isn't.
+    let fixes = |sector: &Sector| {
+        let fixes = sector.fixes.iter().map(|fix| fix.position.to_es().0);
+        fixes.collect::<Vec<_>>()
+    };
+    assert_eq!(
+        fixes(&national),
+        vec!["N051.19.50.000", "N051.18.14.000", "N045.00.00.000"]
+    );
+    assert_eq!(
+        fixes(&concatenated),
+        vec!["N051.19.49.000", "N051.18.14.000", "N045.00.00.000"]
+    );
+    assert_eq!(national.airports[0].runways.len(), 1);
+    assert!(concatenated.airports[0].runways.is_empty());
+
+    let geo = |sector: &Sector| {
+        let groups = sector.geo_entries.iter();
+        groups
+            .map(|group| (group.name.clone(), group.lines.len()))
+            .collect::<Vec<_>>()
+    };
+    let expected = |taxiways: usize| {
+        vec![
+            ("Coast".to_owned(), 1),
+            ("EGKK Taxiways".to_owned(), taxiways),
+            ("EGKK Stands".to_owned(), 1),
+        ]
+    };
+    assert_eq!(geo(&national), expected(2));
+    assert_eq!(geo(&concatenated), expected(3));
+
+    assert_eq!(national.labels[0].labels.len(), 2);
+    assert_eq!(national.labels[0].labels[0].colour, Colour::from(16711680));
+    assert_eq!(national.colours["color_red"], Colour::from(16711680));
+    assert_eq!(concatenated.colours["color_red"], Colour::from(255));
+    assert_eq!(concatenated.colours["color_taxiway"], Colour::from(65280));
+
+    let provenance = &national.provenance;
+    assert_eq!(provenance.sources, vec!["National", "EGKK detail"]);
+    assert_eq!(
+        provenance.sources_of(ItemKind::Fix, "BIG"),
+        vec!["EGKK detail"]
+    );
+    assert_eq!(
+        provenance.sources_of(ItemKind::Fix, "DET"),
+        vec!["National", "EGKK detail"]
+    );
+    assert_eq!(
+        provenance.sources_of(ItemKind::Lines(LineCategory::Geo), "Coast"),
+        vec!["National"]
+    );
+    assert_eq!(
+        concatenated
+            .provenance
+            .sources_of(ItemKind::Lines(LineCategory::Geo), "EGKK Taxiways"),
+        vec!["National", "EGKK detail"]
+    );
+    assert_eq!(
+        provenance.sources_of(ItemKind::Label, "SCT2/Gatwick"),
+        vec!["EGKK detail"]
+    );
+}
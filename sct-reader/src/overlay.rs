@@ -0,0 +1,603 @@
+use std::{collections::HashMap, path::Path};
+
+use crate::{
+    colour::Colour,
+    error::Error,
+    geojson::GeoJsonLayer,
+    json::Json,
+    line::{ColouredLine, LineGroup},
+    position::{Position, Valid},
+    sector::{Label, LabelGroup, LineCategory, Region, RegionGroup, Sector},
+    xml::Element,
+    SectorResult,
+};
+
+/// Given to regions and labels that don't say what colour they are.
+pub const DEFAULT_OVERLAY_COLOUR: Colour = Colour::new(255, 255, 255);
+
+/// Extra video map content read from GeoJSON or KML, ready to be added to a [`Sector`] with
+/// [`Sector::add_overlay`].
+///
+/// Lines become [`LineGroup`]s, polygons become [`RegionGroup`]s and named points become
+/// [`LabelGroup`]s. Only the outer ring of a polygon is used, as regions can't have holes.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Overlay {
+    pub name: String,
+    pub lines: Vec<(LineCategory, LineGroup<ColouredLine>)>,
+    pub regions: Vec<RegionGroup>,
+    pub labels: Vec<LabelGroup>,
+}
+impl Overlay {
+    /// Reads a `.geojson`, `.json` or `.kml` file, named after the file.
+    pub fn read_file(path: impl AsRef<Path>) -> SectorResult<Overlay> {
+        let path = path.as_ref();
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let text = std::fs::read_to_string(path)?;
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("geojson" | "json") => Overlay::from_geojson(&name, &text),
+            Some("kml") => Overlay::from_kml(&name, &text),
+            _ => Err(Error::InvalidOverlay(format!(
+                "{} is not a GeoJSON or KML file",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Reads a GeoJSON `FeatureCollection`, `Feature` or geometry.
+    ///
+    /// A feature's `name` property names the line or region group it goes into, and `text` (or
+    /// failing that `name`) gives a point's label text. Points without either are skipped. Labels
+    /// go into the group given by the `group` property, otherwise one named after the overlay,
+    /// as do lines and regions without a name. Colours are read from `colour`, `color`, `stroke`
+    /// or `fill` as `#rrggbb` or `#rrggbbaa`. A `layer` property as written by
+    /// [`Sector::to_geojson`] puts lines back in their original section; other lines go into
+    /// `[GEO]`.
+    pub fn from_geojson(name: &str, text: &str) -> SectorResult<Overlay> {
+        let json = Json::parse(text).map_err(Error::InvalidOverlay)?;
+        let mut builder = OverlayBuilder::new(name);
+        let features = match json.get("type").and_then(Json::as_str) {
+            Some("FeatureCollection") => json
+                .get("features")
+                .and_then(Json::as_array)
+                .ok_or_else(|| invalid("a FeatureCollection without features"))?
+                .iter()
+                .collect(),
+            Some(_) => vec![&json],
+            None => return Err(invalid("a GeoJSON object without a type")),
+        };
+        for feature in features {
+            let (geometry, properties) = match feature.get("type").and_then(Json::as_str) {
+                Some("Feature") => (feature.get("geometry"), feature.get("properties")),
+                _ => (Some(feature), None),
+            };
+            let property = |key: &str| properties?.get(key)?.as_str();
+            let colour = ["colour", "color", "stroke", "fill"]
+                .into_iter()
+                .find_map(|key| property(key).and_then(hex_colour));
+            let category = property("layer")
+                .and_then(|layer| {
+                    LineCategory::ALL
+                        .into_iter()
+                        .find(|category| GeoJsonLayer::Lines(*category).to_string() == layer)
+                })
+                .unwrap_or(LineCategory::Geo);
+            let style = Style {
+                category,
+                group: property("name").map(str::to_owned),
+                label_group: property("group").map(str::to_owned),
+                label: property("text").or(property("name")).map(str::to_owned),
+                line_colour: colour,
+                fill_colour: colour,
+            };
+            if let Some(geometry) = geometry.filter(|geometry| **geometry != Json::Null) {
+                builder.add_geojson_geometry(geometry, &style)?;
+            }
+        }
+        Ok(builder.finish())
+    }
+
+    /// Reads the placemarks of a KML document.
+    ///
+    /// Each placemark's `<name>` names its line or region group, or gives the text of a point's
+    /// label. Labels are grouped by the folder they're in, or into a group named after the
+    /// overlay. Colours come from the placemark's `<Style>`, whether inline or shared through
+    /// `<styleUrl>`, and keep KML's alpha channel.
+    pub fn from_kml(name: &str, text: &str) -> SectorResult<Overlay> {
+        let kml = Element::parse(text).map_err(Error::InvalidOverlay)?;
+        if kml.name != "kml" {
+            return Err(invalid("a document that isn't KML"));
+        }
+
+        let mut styles = HashMap::new();
+        for element in kml.descendants() {
+            let Some(id) = element.attribute("id") else {
+                continue;
+            };
+            if element.name == "Style" {
+                styles.insert(id.to_owned(), kml_style(element));
+            }
+        }
+        // Style maps switch between styles on hover, so take the normal one.
+        for element in kml.descendants() {
+            let Some(id) = element.attribute("id") else {
+                continue;
+            };
+            if element.name == "StyleMap" {
+                let normal = element
+                    .children_named("Pair")
+                    .find(|pair| pair.child_text("key") == Some("normal"))
+                    .and_then(|pair| pair.child_text("styleUrl"))
+                    .and_then(|url| styles.get(url.trim_start_matches('#')).copied());
+                if let Some(style) = normal {
+                    styles.insert(id.to_owned(), style);
+                }
+            }
+        }
+
+        let mut builder = OverlayBuilder::new(name);
+        builder.add_kml_container(&kml, None, &styles)?;
+        Ok(builder.finish())
+    }
+}
+
+impl Sector {
+    /// Adds an overlay's content. Groups with the same name as one already in the sector are
+    /// added to it.
+    pub fn add_overlay(&mut self, overlay: &Overlay) {
+        for (category, group) in &overlay.lines {
+            let groups = self.line_groups_mut(*category);
+            match groups
+                .iter_mut()
+                .find(|existing| existing.name == group.name)
+            {
+                Some(existing) => existing.lines.extend(group.lines.iter().cloned()),
+                None => groups.push(group.clone()),
+            }
+        }
+        for group in &overlay.regions {
+            match self
+                .regions
+                .iter_mut()
+                .find(|existing| existing.name == group.name)
+            {
+                Some(existing) => existing.regions.extend(group.regions.iter().cloned()),
+                None => self.regions.push(group.clone()),
+            }
+        }
+        for group in &overlay.labels {
+            match self
+                .labels
+                .iter_mut()
+                .find(|existing| existing.name == group.name)
+            {
+                Some(existing) => existing.labels.extend(group.labels.iter().cloned()),
+                None => self.labels.push(group.clone()),
+            }
+        }
+    }
+}
+
+fn invalid(what: &str) -> Error {
+    Error::InvalidOverlay(format!("found {}", what))
+}
+
+/// How the geometry of a feature or placemark is turned into sector content.
+#[derive(Debug, Clone)]
+struct Style {
+    category: LineCategory,
+    group: Option<String>,
+    label_group: Option<String>,
+    label: Option<String>,
+    line_colour: Option<Colour>,
+    fill_colour: Option<Colour>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct KmlStyle {
+    line_colour: Option<Colour>,
+    fill_colour: Option<Colour>,
+}
+
+struct OverlayBuilder {
+    overlay: Overlay,
+}
+impl OverlayBuilder {
+    fn new(name: &str) -> OverlayBuilder {
+        OverlayBuilder {
+            overlay: Overlay {
+                name: name.to_owned(),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn finish(self) -> Overlay {
+        self.overlay
+    }
+
+    fn group_name(&self, style: &Style) -> String {
+        style
+            .group
+            .clone()
+            .unwrap_or_else(|| self.overlay.name.clone())
+    }
+
+    fn add_line(&mut self, style: &Style, points: &[Position<Valid>]) {
+        let name = self.group_name(style);
+        let lines = &mut self.overlay.lines;
+        let index = match lines
+            .iter()
+            .position(|(category, group)| *category == style.category && group.name == name)
+        {
+            Some(index) => index,
+            None => {
+                lines.push((style.category, LineGroup::new(name, vec![])));
+                lines.len() - 1
+            }
+        };
+        lines[index].1.lines.extend(
+            points
+                .windows(2)
+                .map(|pair| ColouredLine::new(pair[0], pair[1], style.line_colour)),
+        );
+    }
+
+    fn add_region(&mut self, style: &Style, mut vertices: Vec<Position<Valid>>) {
+        if vertices.len() > 1 && vertices.first() == vertices.last() {
+            vertices.pop();
+        }
+        if vertices.len() < 3 {
+            return;
+        }
+        let name = self.group_name(style);
+        let region = Region {
+            colour: style
+                .fill_colour
+                .or(style.line_colour)
+                .unwrap_or(DEFAULT_OVERLAY_COLOUR),
+            colour_name: None,
+            vertices,
+        };
+        match self
+            .overlay
+            .regions
+            .iter_mut()
+            .find(|group| group.name == name)
+        {
+            Some(group) => group.regions.push(region),
+            None => {
+                let mut group = RegionGroup::new(name);
+                group.regions.push(region);
+                self.overlay.regions.push(group);
+            }
+        }
+    }
+
+    fn add_label(&mut self, style: &Style, position: Position<Valid>) {
+        let Some(text) = style.label.clone() else {
+            return;
+        };
+        let name = style
+            .label_group
+            .clone()
+            .unwrap_or_else(|| self.overlay.name.clone());
+        let label = Label {
+            name: text,
+            position,
+            colour: style
+                .line_colour
+                .or(style.fill_colour)
+                .unwrap_or(DEFAULT_OVERLAY_COLOUR),
+            colour_name: None,
+        };
+        match self
+            .overlay
+            .labels
+            .iter_mut()
+            .find(|group| group.name == name)
+        {
+            Some(group) => group.labels.push(label),
+            None => self.overlay.labels.push(LabelGroup {
+                name,
+                labels: vec![label],
+            }),
+        }
+    }
+
+    fn add_geojson_geometry(&mut self, geometry: &Json, style: &Style) -> SectorResult<()> {
+        let coordinates = || {
+            geometry
+                .get("coordinates")
+                .ok_or_else(|| invalid("a geometry without coordinates"))
+        };
+        match geometry.get("type").and_then(Json::as_str) {
+            Some("Point") => self.add_label(style, geojson_position(coordinates()?)?),
+            Some("MultiPoint") => {
+                for position in geojson_positions(coordinates()?)? {
+                    self.add_label(style, position);
+                }
+            }
+            Some("LineString") => self.add_line(style, &geojson_positions(coordinates()?)?),
+            Some("MultiLineString") => {
+                for line in json_array(coordinates()?)? {
+                    self.add_line(style, &geojson_positions(line)?);
+                }
+            }
+            Some("Polygon") => self.add_geojson_polygon(style, coordinates()?)?,
+            Some("MultiPolygon") => {
+                for polygon in json_array(coordinates()?)? {
+                    self.add_geojson_polygon(style, polygon)?;
+                }
+            }
+            Some("GeometryCollection") => {
+                let geometries = geometry
+                    .get("geometries")
+                    .ok_or_else(|| invalid("a GeometryCollection without geometries"))?;
+                for geometry in json_array(geometries)? {
+                    self.add_geojson_geometry(geometry, style)?;
+                }
+            }
+            Some(other) => return Err(invalid(&format!("an unknown geometry type {}", other))),
+            None => return Err(invalid("a geometry without a type")),
+        }
+        Ok(())
+    }
+
+    fn add_geojson_polygon(&mut self, style: &Style, rings: &Json) -> SectorResult<()> {
+        if let Some(outer) = json_array(rings)?.first() {
+            self.add_region(style, geojson_positions(outer)?);
+        }
+        Ok(())
+    }
+
+    fn add_kml_container(
+        &mut self,
+        container: &Element,
+        folder: Option<&str>,
+        styles: &HashMap<String, KmlStyle>,
+    ) -> SectorResult<()> {
+        for child in &container.children {
+            match child.name.as_str() {
+                "Document" => self.add_kml_container(child, folder, styles)?,
+                "Folder" => self.add_kml_container(child, child.child_text("name"), styles)?,
+                "Placemark" => {
+                    let kml_style = child
+                        .child("Style")
+                        .map(kml_style)
+                        .or_else(|| {
+                            let url = child.child_text("styleUrl")?;
+                            styles.get(url.trim_start_matches('#')).copied()
+                        })
+                        .unwrap_or_default();
+                    let name = child.child_text("name").map(str::to_owned);
+                    let style = Style {
+                        category: LineCategory::Geo,
+                        group: name.clone().or(folder.map(str::to_owned)),
+                        label_group: folder.map(str::to_owned),
+                        label: name,
+                        line_colour: kml_style.line_colour,
+                        fill_colour: kml_style.fill_colour,
+                    };
+                    self.add_kml_geometries(child, &style)?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn add_kml_geometries(&mut self, element: &Element, style: &Style) -> SectorResult<()> {
+        for child in &element.children {
+            match child.name.as_str() {
+                "Point" => {
+                    if let Some(position) = kml_coordinates(child)?.first() {
+                        self.add_label(style, *position);
+                    }
+                }
+                "LineString" | "LinearRing" => {
+                    let mut points = kml_coordinates(child)?;
+                    if child.name == "LinearRing" && points.first() != points.last() {
+                        points.push(points[0]);
+                    }
+                    self.add_line(style, &points);
+                }
+                "Polygon" => {
+                    let ring = child
+                        .child("outerBoundaryIs")
+                        .and_then(|outer| outer.child("LinearRing"))
+                        .ok_or_else(|| invalid("a Polygon without an outer boundary"))?;
+                    self.add_region(style, kml_coordinates(ring)?);
+                }
+                "MultiGeometry" => self.add_kml_geometries(child, style)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+fn json_array(value: &Json) -> SectorResult<&[Json]> {
+    value
+        .as_array()
+        .ok_or_else(|| invalid("coordinates that aren't an array"))
+}
+
+/// GeoJSON puts the longitude first, and may follow it with an altitude.
+fn geojson_position(value: &Json) -> SectorResult<Position<Valid>> {
+    match json_array(value)? {
+        [lon, lat, ..] => {
+            let (lon, lat) = lon
+                .as_f64()
+                .zip(lat.as_f64())
+                .ok_or_else(|| invalid("a coordinate that isn't a number"))?;
+            Position::new(lat, lon).validate()
+        }
+        _ => Err(invalid("a position without a latitude and longitude")),
+    }
+}
+
+fn geojson_positions(value: &Json) -> SectorResult<Vec<Position<Valid>>> {
+    json_array(value)?.iter().map(geojson_position).collect()
+}
+
+/// KML coordinates are `lon,lat[,alt]` tuples separated by whitespace.
+fn kml_coordinates(element: &Element) -> SectorResult<Vec<Position<Valid>>> {
+    let text = element
+        .child("coordinates")
+        .ok_or_else(|| invalid(&format!("a {} without coordinates", element.name)))?;
+    text.text
+        .split_whitespace()
+        .map(|tuple| {
+            let mut values = tuple.split(',').map(str::parse::<f64>);
+            match (values.next(), values.next()) {
+                (Some(Ok(lon)), Some(Ok(lat))) => Position::new(lat, lon).validate(),
+                _ => Err(invalid(&format!("an invalid coordinate {}", tuple))),
+            }
+        })
+        .collect()
+}
+
+fn kml_style(style: &Element) -> KmlStyle {
+    let colour = |name: &str| {
+        style
+            .child(name)
+            .and_then(|element| element.child_text("color"))
+            .and_then(kml_colour)
+    };
+    KmlStyle {
+        line_colour: colour("LineStyle"),
+        fill_colour: colour("PolyStyle"),
+    }
+}
+
+/// KML colours are `aabbggrr`.
+fn kml_colour(text: &str) -> Option<Colour> {
+    let value = u32::from_str_radix(text.trim_start_matches('#'), 16).ok()?;
+    let [a, b, g, r] = value.to_be_bytes();
+    (text.trim_start_matches('#').len() == 8).then_some(Colour::new(r, g, b).with_alpha(a))
+}
+
+/// `#rrggbb` or `#rrggbbaa`, with or without the `#`.
+fn hex_colour(text: &str) -> Option<Colour> {
+    let hex = text.strip_prefix('#').unwrap_or(text);
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    match hex.len() {
+        6 => {
+            let [_, r, g, b] = value.to_be_bytes();
+            Some(Colour::new(r, g, b))
+        }
+        8 => {
+            let [r, g, b, a] = value.to_be_bytes();
+            Some(Colour::new(r, g, b).with_alpha(a))
+        }
+        _ => None,
+    }
+}
+
+#[test]
+fn test_overlays() {
+    use crate::reader::test_sector;
+
+    let geojson = r##"{"type": "FeatureCollection", "features": [
+        {"type": "Feature", "properties": {"name": "Vectoring", "stroke": "#ff8000"},
+         "geometry": {"type": "LineString", "coordinates": [[-0.5, 51.0], [-0.4, 51.1], [-0.3, 51.1]]}},
+        {"type": "Feature", "properties": {"name": "Danger area", "fill": "#800000"},
+         "geometry": {"type": "Polygon", "coordinates": [
+             [[0.0, 51.0], [0.1, 51.0], [0.1, 51.1], [0.0, 51.0]],
+             [[0.02, 51.02], [0.03, 51.02], [0.03, 51.03], [0.02, 51.02]]]}},
+        {"type": "Feature", "properties": {"text": "D123", "group": "Danger areas"},
+         "geometry": {"type": "Point", "coordinates": [0.05, 51.05, 1200]}},
+        {"type": "Feature", "properties": {"name": "LTMA", "layer": "artcc_high"},
+         "geometry": {"type": "MultiLineString", "coordinates": [[[1.0, 52.0], [1.5, 52.0]]]}},
+        {"type": "Feature", "properties": {}, "geometry": {"type": "Point", "coordinates": [1.0, 52.0]}},
+        {"type": "Feature", "properties": null, "geometry": null}
+    ]}"##;
+    let overlay = Overlay::from_geojson("Training", geojson).unwrap();
+    assert_eq!(overlay.lines.len(), 2);
+    let (category, vectoring) = &overlay.lines[0];
+    assert_eq!(*category, LineCategory::Geo);
+    assert_eq!(vectoring.name, "Vectoring");
+    assert_eq!(vectoring.lines.len(), 2);
+    assert_eq!(vectoring.lines[0].colour, Some(Colour::new(255, 128, 0)));
+    assert_eq!(overlay.lines[1].0, LineCategory::ArtccHigh);
+    let danger = &overlay.regions[0];
+    assert_eq!(danger.name, "Danger area");
+    assert_eq!(danger.regions.len(), 1);
+    assert_eq!(danger.regions[0].vertices.len(), 3);
+    assert_eq!(danger.regions[0].colour, Colour::new(128, 0, 0));
+    assert_eq!(overlay.labels.len(), 1);
+    assert_eq!(overlay.labels[0].name, "Danger areas");
+    assert_eq!(overlay.labels[0].labels[0].name, "D123");
+    assert_eq!(overlay.labels[0].labels[0].colour, DEFAULT_OVERLAY_COLOUR);
+
+    let kml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<kml xmlns="http://www.opengis.net/kml/2.2">
+<Document>
+  <Style id="red"><LineStyle><color>ff0000ff</color></LineStyle></Style>
+  <StyleMap id="redMap">
+    <Pair><key>normal</key><styleUrl>#red</styleUrl></Pair>
+    <Pair><key>highlight</key><styleUrl>#other</styleUrl></Pair>
+  </StyleMap>
+  <Folder>
+    <name>Patterns</name>
+    <Placemark>
+      <name>Downwind</name>
+      <styleUrl>#redMap</styleUrl>
+      <LineString><coordinates>-0.2,51.2,0 -0.1,51.2,0</coordinates></LineString>
+    </Placemark>
+    <Placemark>
+      <name>BASE</name>
+      <Point><coordinates>-0.1,51.2</coordinates></Point>
+    </Placemark>
+    <Placemark>
+      <name>Area</name>
+      <Style><PolyStyle><color>8000ff00</color></PolyStyle></Style>
+      <MultiGeometry>
+        <Polygon><outerBoundaryIs><LinearRing><coordinates>
+          0,50 1,50 1,51 0,50
+        </coordinates></LinearRing></outerBoundaryIs></Polygon>
+      </MultiGeometry>
+    </Placemark>
+  </Folder>
+</Document>
+</kml>"#;
+    let overlay = Overlay::from_kml("Circuits", kml).unwrap();
+    let (_, downwind) = &overlay.lines[0];
+    assert_eq!(downwind.name, "Downwind");
+    assert_eq!(downwind.lines[0].colour, Some(Colour::new(255, 0, 0)));
+    assert_eq!(overlay.labels[0].name, "Patterns");
+    assert_eq!(overlay.labels[0].labels[0].name, "BASE");
+    assert_eq!(overlay.regions[0].name, "Area");
+    assert_eq!(
+        overlay.regions[0].regions[0].colour,
+        Colour::new(0, 255, 0).with_alpha(128)
+    );
+
+    let mut sector = test_sector(
+        "Overlay test",
+        "\
+[GEO]
+Downwind N051.00.00.000 E000.00.00.000 N051.01.00.000 E000.01.00.000
+",
+    );
+    sector.add_overlay(&overlay);
+    assert_eq!(sector.geo_entries.len(), 1);
+    assert_eq!(sector.geo_entries[0].lines.len(), 2);
+    assert_eq!(sector.regions.len(), 1);
+    assert_eq!(sector.labels.len(), 2);
+
+    assert!(matches!(
+        Overlay::from_geojson("Broken", "{\"type\": \"Feature\""),
+        Err(Error::InvalidOverlay(_))
+    ));
+    assert!(matches!(
+        Overlay::from_kml("Broken", "<gpx></gpx>"),
+        Err(Error::InvalidOverlay(_))
+    ));
+}
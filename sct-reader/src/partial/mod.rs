@@ -1,11 +1,18 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    str::FromStr,
+    sync::Arc,
+};
 
 use crate::{
     colour::Colour,
+    coordinate::{self, Axis, CoordinateFormat},
+    diagnostic::Diagnostic,
     error::Error,
     line::{ColouredLine, LineGroup},
-    position::{self, Heading, Position},
-    sector::{Label, LabelGroup},
+    position::{self, Heading, Position, Valid},
+    reader::SctItem,
+    sector::{Label, LabelGroup, LineCategory, SectorInfoField},
     waypoint::{self, Airport, Fix, Ndb, RunwayEnd, RunwayModifier, RunwayStrip, Vor},
     AirspaceClass, SectorResult,
 };
@@ -18,6 +25,7 @@ use self::{
 pub mod region;
 pub mod sector_info;
 
+/// Collects [`SctItem`]s into everything needed to build a [`Sector`](crate::sector::Sector).
 #[derive(Debug, Default)]
 pub struct PartialSector {
     pub colours: HashMap<String, Colour>,
@@ -36,67 +44,297 @@ pub struct PartialSector {
     pub geo_entries: Vec<LineGroup<ColouredLine>>,
     pub regions: Vec<PartialRegionGroup>,
     pub labels: Vec<LabelGroup>,
+    pub non_critical_errors: Vec<Diagnostic>,
 
-    current_region_name: String,
+    /// Index into `airports` by identifier, for attaching runways.
+    airport_index: HashMap<String, usize>,
 }
 
 impl PartialSector {
     pub fn new() -> PartialSector {
         PartialSector {
-            current_region_name: String::from("noname"),
-            labels: vec![LabelGroup { name: String::from("SCT2"), labels: vec![] }],
+            labels: vec![LabelGroup {
+                name: String::from("SCT2"),
+                labels: vec![],
+            }],
             ..Default::default()
         }
     }
 
-    fn try_fetch_or_decode_colour(&self, value: &str) -> Option<Colour> {
-        if let Ok(colour) = Colour::from_str(value) {
-            return Some(colour);
-        };
-        self.colours.get(&value.to_lowercase()).map(|x| *x)
-    }
-    fn try_fetch_or_decode_lat_lon(&self, lat: &str, lon: &str) -> Option<Position> {
-        if let Ok(position) = Position::try_new_from_es(lat, lon) {
-            return Some(position.into());
+    fn line_groups_mut(&mut self, category: LineCategory) -> &mut Vec<LineGroup<ColouredLine>> {
+        match category {
+            LineCategory::Artcc => &mut self.artcc_entries,
+            LineCategory::ArtccHigh => &mut self.artcc_high_entries,
+            LineCategory::ArtccLow => &mut self.artcc_low_entries,
+            LineCategory::LowAirway => &mut self.low_airways,
+            LineCategory::HighAirway => &mut self.high_airways,
+            LineCategory::Sid => &mut self.sid_entries,
+            LineCategory::Star => &mut self.star_entries,
+            LineCategory::Geo => &mut self.geo_entries,
         }
+    }
 
-        for fix in &self.fixes {
-            if fix.identifier == lat {
-                return Some((fix.position.into()));
+    pub fn add(&mut self, item: SctItem) {
+        match item {
+            SctItem::Colour { name, colour } => {
+                self.colours.insert(name.to_lowercase(), colour);
             }
-        }
-        for vor in &self.vors {
-            if vor.identifier == lat {
-                return Some((vor.position.into()));
+            SctItem::InfoField { field, value } => {
+                // Values from a `LineParser` have already been checked. Any other bad value leaves
+                // the field missing, which is reported when the sector is built.
+                let _ = self.sector_info.set(field, &value);
             }
-        }
-        for ndb in &self.ndbs {
-            if ndb.identifier == lat {
-                return Some((ndb.position.into()));
+            SctItem::Airport(airport) => {
+                self.airport_index
+                    .entry(airport.identifier.clone())
+                    .or_insert(self.airports.len());
+                self.airports.push(airport);
+            }
+            SctItem::Vor(vor) => self.vors.push(vor),
+            SctItem::Ndb(ndb) => self.ndbs.push(ndb),
+            SctItem::Fix(fix) => self.fixes.push(fix),
+            SctItem::RunwayStrip { airport, strip } => {
+                if let Some(index) = self.airport_index.get(&airport) {
+                    self.airports[*index].runways.push(strip);
+                }
+            }
+            SctItem::Group { section, name } => {
+                self.line_groups_mut(section)
+                    .push(LineGroup::new(name, vec![]));
+            }
+            SctItem::Segment {
+                section,
+                group,
+                line,
+            } => {
+                // Group names can repeat in the SID and STAR sections, where the latest is meant.
+                let groups = self.line_groups_mut(section);
+                match groups.iter_mut().rfind(|existing| existing.name == group) {
+                    Some(existing) => existing.lines.push(line),
+                    None => groups.push(LineGroup::new(group, vec![line])),
+                }
             }
+            SctItem::Region {
+                group,
+                colour,
+                colour_name,
+            } => {
+                let region = PartialRegion {
+                    colour: Some(colour),
+                    colour_name,
+                    vertices: vec![],
+                };
+                match self
+                    .regions
+                    .iter_mut()
+                    .find(|existing| existing.name == group)
+                {
+                    Some(existing) => existing.regions.push(region),
+                    None => self.regions.push(PartialRegionGroup {
+                        name: group,
+                        regions: vec![region],
+                    }),
+                }
+            }
+            SctItem::RegionVertex { group, position } => {
+                let region = self
+                    .regions
+                    .iter_mut()
+                    .find(|existing| existing.name == group)
+                    .and_then(|existing| existing.regions.last_mut());
+                match region {
+                    Some(region) => region.vertices.push(position),
+                    // Left without a colour, so building the sector fails.
+                    None => self.regions.push(PartialRegionGroup {
+                        name: group,
+                        regions: vec![PartialRegion {
+                            colour: None,
+                            colour_name: None,
+                            vertices: vec![position],
+                        }],
+                    }),
+                }
+            }
+            SctItem::Label(label) => self.labels.last_mut().unwrap().labels.push(label),
+            SctItem::CoordinateFormat(format) => self.sector_info.coordinate_format = Some(format),
+            SctItem::Diagnostic(diagnostic) => self.non_critical_errors.push(diagnostic),
+        }
+    }
+}
+
+/// Turns the lines of a sector file into [`SctItem`]s. Only what is needed to make sense of later
+/// lines is kept: colour definitions, waypoint positions and the names of the groups so far.
+#[derive(Debug, Default)]
+pub struct LineParser {
+    /// Each `#define` by lower case name, with the name as it was written.
+    colours: HashMap<String, (Arc<str>, Colour)>,
+    /// How many `[INFO]` lines have been seen.
+    info_lines: usize,
+    airports: HashSet<String>,
+    /// Every fix, VOR, NDB and airport position, by identifier. Identifiers aren't unique, so each
+    /// entry holds all the candidates, in order of preference.
+    waypoint_index: HashMap<String, Vec<(WaypointKind, Position<Valid>)>>,
+    /// The most recently resolved position, used to choose between waypoints that share an
+    /// identifier.
+    previous_position: Option<Position<Valid>>,
+    groups: HashMap<LineCategory, GroupNames>,
+    current_region_name: String,
+    /// The names of the region groups that have had a colour line, so can take vertices.
+    started_regions: HashSet<String>,
+    /// How many positions have been written in each notation.
+    coordinate_formats: HashMap<CoordinateFormat, usize>,
+    pending: VecDeque<SctItem>,
+}
+
+#[derive(Debug, Default)]
+struct GroupNames {
+    names: HashSet<String>,
+    /// The most recently started group, which unnamed lines continue.
+    last: Option<String>,
+}
+
+impl LineParser {
+    pub fn new() -> LineParser {
+        LineParser {
+            current_region_name: String::from("noname"),
+            ..Default::default()
         }
-        for airport in &self.airports {
-            if airport.identifier == lat {
-                return Some((airport.position.into()));
+    }
+
+    pub fn emit(&mut self, item: SctItem) {
+        self.pending.push_back(item);
+    }
+    /// The oldest item that hasn't been taken yet. A line can give any number of items.
+    pub fn next_item(&mut self) -> Option<SctItem> {
+        self.pending.pop_front()
+    }
+    /// Gives the items that can only be known once every line has been read.
+    pub fn finish(&mut self) {
+        // Ties, and a file without any coordinates, go to the earliest in the list.
+        let count = |format: &CoordinateFormat| self.coordinate_formats.get(format).copied();
+        let format = CoordinateFormat::ALL
+            .into_iter()
+            .rev()
+            .max_by_key(|format| count(format).unwrap_or(0))
+            .unwrap();
+        self.emit(SctItem::CoordinateFormat(format));
+    }
+
+    /// A colour given as a number or a `#define` name, along with the name as it was defined.
+    fn try_fetch_or_decode_colour(&self, value: &str) -> Option<(Colour, Option<Arc<str>>)> {
+        if let Ok(colour) = Colour::from_str(value) {
+            return Some((colour, None));
+        };
+        self.colours
+            .get(&value.to_lowercase())
+            .map(|(name, colour)| (*colour, Some(name.clone())))
+    }
+    /// Reads a position written out in full, counting which notation it's in.
+    fn read_position(&mut self, lat: &str, lon: &str) -> SectorResult<Position> {
+        let (position, format) = coordinate::read_sector_pair(lat, lon)?;
+        *self.coordinate_formats.entry(format).or_default() += 1;
+        Ok(position)
+    }
+    fn try_fetch_or_decode_lat_lon(&mut self, lat: &str, lon: &str) -> SectorResult<Position> {
+        match self.read_position(lat, lon) {
+            Ok(position) => {
+                if let Ok(valid) = position.validate() {
+                    self.previous_position = Some(valid);
+                }
+                return Ok(position);
             }
+            Err(e @ Error::MinutesOrSecondsOutOfRange(_)) => return Err(e),
+            Err(_) => {}
         }
 
-        return None;
+        if let Some(candidates) = self.waypoint_index.get(lat) {
+            // Where an identifier is used more than once, the candidate nearest the previous point
+            // is taken, as a route or boundary rarely jumps across the map. Ties, or having no
+            // previous point, fall back to the order of preference.
+            let position = match (candidates.as_slice(), self.previous_position) {
+                ([_], _) | (_, None) => candidates[0].1,
+                (_, Some(previous)) => {
+                    candidates
+                        .iter()
+                        .min_by(|(_, a), (_, b)| {
+                            approx_distance_sq(previous, *a)
+                                .total_cmp(&approx_distance_sq(previous, *b))
+                        })
+                        .unwrap()
+                        .1
+                }
+            };
+            self.previous_position = Some(position);
+            return Ok(position.into());
+        }
+
+        if coordinate::read_sector_field(lat, Axis::Lat).is_ok() {
+            return Err(Error::InvalidCoordinate);
+        }
+        Err(Error::UnknownWaypoint(lat.to_owned()))
+    }
+    fn index_waypoint(&mut self, identifier: &str, kind: WaypointKind, position: Position<Valid>) {
+        let candidates = self
+            .waypoint_index
+            .entry(identifier.to_owned())
+            .or_default();
+        let index = candidates.partition_point(|(existing, _)| *existing <= kind);
+        candidates.insert(index, (kind, position));
+    }
+
+    /// Joins the group with this name, starting it if there isn't one yet.
+    fn join_group(&mut self, section: LineCategory, name: String) -> String {
+        let groups = self.groups.entry(section).or_default();
+        if !groups.names.contains(&name) {
+            groups.names.insert(name.clone());
+            groups.last = Some(name.clone());
+            self.pending.push_back(SctItem::Group {
+                section,
+                name: name.clone(),
+            });
+        }
+        name
+    }
+    /// Starts a new group, even if one with the same name has been seen before.
+    fn start_group(&mut self, section: LineCategory, name: String) -> String {
+        let groups = self.groups.entry(section).or_default();
+        groups.names.insert(name.clone());
+        groups.last = Some(name.clone());
+        self.pending.push_back(SctItem::Group {
+            section,
+            name: name.clone(),
+        });
+        name
+    }
+    /// The group that a line without a name continues.
+    fn last_group(&self, section: LineCategory) -> Option<String> {
+        self.groups.get(&section)?.last.clone()
     }
 
     pub fn parse_colour_line(&mut self, value: &str) -> SectorResult<()> {
         let mut sections = value.split_whitespace();
-        let colour_name = sections
-            .nth(1)
-            .ok_or(Error::InvalidColourDefinition)?
-            .to_lowercase();
+        let colour_name = sections.nth(1).ok_or(Error::InvalidColourDefinition)?;
         let colour_def = sections.next().ok_or(Error::InvalidColourDefinition)?;
         let colour = colour_def.parse::<Colour>()?;
-        self.colours.insert(colour_name, colour);
+        self.colours
+            .insert(colour_name.to_lowercase(), (colour_name.into(), colour));
+        self.emit(SctItem::Colour {
+            name: colour_name.to_owned(),
+            colour,
+        });
         Ok(())
     }
     pub fn parse_sector_info_line(&mut self, value: &str) -> SectorResult<()> {
-        self.sector_info.parse_line(value)
+        self.info_lines += 1;
+        let field =
+            SectorInfoField::from_line(self.info_lines).ok_or(Error::UnexpectedSectorInfoLine)?;
+        // Checked here, so that a bad value is reported against its line.
+        PartialSectorInfo::default().set(field, value)?;
+        self.emit(SctItem::InfoField {
+            field,
+            value: value.to_owned(),
+        });
+        Ok(())
     }
     pub fn parse_airport_line(&mut self, value: &str) -> SectorResult<()> {
         let mut sections = value.split_whitespace();
@@ -104,10 +342,13 @@ impl PartialSector {
         let tower_frequency = sections.next().ok_or(Error::InvalidWaypoint)?.to_owned();
         let lat = sections.next().ok_or(Error::InvalidWaypoint)?;
         let lon = sections.next().ok_or(Error::InvalidWaypoint)?;
-        let position = Position::try_new_from_es(lat, lon)?.validate()?;
+        let position = self.read_position(lat, lon)?.validate()?;
         let airspace_class: AirspaceClass =
             sections.next().ok_or(Error::InvalidWaypoint)?.parse()?;
 
+        self.index_waypoint(&identifier, WaypointKind::Airport, position);
+        self.airports.insert(identifier.clone());
+
         let airport = Airport {
             identifier,
             position,
@@ -116,8 +357,7 @@ impl PartialSector {
             runways: vec![],
         };
 
-        self.airports.push(airport);
-
+        self.emit(SctItem::Airport(airport));
         Ok(())
     }
 
@@ -149,15 +389,13 @@ impl PartialSector {
         let lat_b = sections.next().ok_or(Error::InvalidRunway)?;
         let lon_b = sections.next().ok_or(Error::InvalidRunway)?;
 
-        let pos_a = Position::try_new_from_es(lat_a, lon_a)?.validate()?;
-        let pos_b = Position::try_new_from_es(lat_b, lon_b)?.validate()?;
+        let pos_a = self.read_position(lat_a, lon_a)?.validate()?;
+        let pos_b = self.read_position(lat_b, lon_b)?.validate()?;
 
         let airport = sections.next().ok_or(Error::InvalidRunway)?;
-        let airport = self
-            .airports
-            .iter_mut()
-            .find(|entry| entry.identifier == airport)
-            .ok_or(Error::InvalidRunway)?;
+        if !self.airports.contains(airport) {
+            return Err(Error::UnknownAirport(airport.to_owned()));
+        }
 
         let mut runway_end_a = RunwayEnd {
             number: number_a,
@@ -179,12 +417,13 @@ impl PartialSector {
             std::mem::swap(&mut runway_end_a, &mut runway_end_b);
         }
 
-        let runway_strip = RunwayStrip {
-            end_a: runway_end_a,
-            end_b: runway_end_b,
-        };
-
-        airport.runways.push(runway_strip);
+        self.emit(SctItem::RunwayStrip {
+            airport: airport.to_owned(),
+            strip: RunwayStrip {
+                end_a: runway_end_a,
+                end_b: runway_end_b,
+            },
+        });
         Ok(())
     }
 
@@ -198,24 +437,26 @@ impl PartialSector {
         let frequency = sections.next().ok_or(Error::InvalidVorOrNdb)?.to_owned();
         let lat = sections.next().ok_or(Error::InvalidVorOrNdb)?;
         let lon = sections.next().ok_or(Error::InvalidVorOrNdb)?;
-        let position = Position::try_new_from_es(lat, lon)?.validate()?;
+        let position = self.read_position(lat, lon)?.validate()?;
 
         match beacon_type {
             BeaconType::Ndb => {
+                self.index_waypoint(&identifier, WaypointKind::Ndb, position);
                 let ndb = Ndb {
                     identifier,
                     position,
                     frequency,
                 };
-                self.ndbs.push(ndb);
+                self.emit(SctItem::Ndb(ndb));
             }
             BeaconType::Vor => {
+                self.index_waypoint(&identifier, WaypointKind::Vor, position);
                 let vor = Vor {
                     identifier,
                     position,
                     frequency,
                 };
-                self.vors.push(vor);
+                self.emit(SctItem::Vor(vor));
             }
         }
         Ok(())
@@ -226,26 +467,29 @@ impl PartialSector {
         let identifier = sections.next().ok_or(Error::InvalidFix)?.to_owned();
         let lat = sections.next().ok_or(Error::InvalidFix)?;
         let lon = sections.next().ok_or(Error::InvalidFix)?;
-        let position = Position::try_new_from_es(lat, lon)?.validate()?;
+        let position = self.read_position(lat, lon)?.validate()?;
+        self.index_waypoint(&identifier, WaypointKind::Fix, position);
         let fix = Fix {
             identifier,
             position,
         };
-        self.fixes.push(fix);
+        self.emit(SctItem::Fix(fix));
         Ok(())
     }
 
     pub fn parse_artcc_or_airway_line(
         &mut self,
         value: &str,
-        line_type: ArtccOrAirwayLineType,
+        section: LineCategory,
     ) -> SectorResult<()> {
         let mut sections = value.split_whitespace().collect::<Vec<_>>();
 
         // Get the colour from the last section. If there is one, remove that element.
-        let colour = sections
+        let (colour, colour_name) = sections
             .last()
-            .and_then(|section| self.try_fetch_or_decode_colour(section));
+            .and_then(|section| self.try_fetch_or_decode_colour(section))
+            .unzip();
+        let colour_name = colour_name.flatten();
         if colour.is_some() {
             sections.pop();
         };
@@ -262,52 +506,38 @@ impl PartialSector {
             return Err(Error::InvalidArtccEntry);
         };
 
-        let pos_a = self
-            .try_fetch_or_decode_lat_lon(
-                sections[first_coord_index],
-                sections[first_coord_index + 1],
-            )
-            .ok_or(Error::InvalidArtccEntry)?;
-        let pos_b = self
-            .try_fetch_or_decode_lat_lon(
-                sections[first_coord_index + 2],
-                sections[first_coord_index + 3],
-            )
-            .ok_or(Error::InvalidArtccEntry)?;
-
-        // Determine which storage to use.
-        let storage = match line_type {
-            ArtccOrAirwayLineType::Artcc => &mut self.artcc_entries,
-            ArtccOrAirwayLineType::ArtccLow => &mut self.artcc_low_entries,
-            ArtccOrAirwayLineType::ArtccHigh => &mut self.artcc_high_entries,
-            ArtccOrAirwayLineType::LowAirway => &mut self.low_airways,
-            ArtccOrAirwayLineType::HighAirway => &mut self.high_airways,
-        };
+        let pos_a = self.try_fetch_or_decode_lat_lon(
+            sections[first_coord_index],
+            sections[first_coord_index + 1],
+        )?;
+        let pos_b = self.try_fetch_or_decode_lat_lon(
+            sections[first_coord_index + 2],
+            sections[first_coord_index + 3],
+        )?;
 
         let name_exists = name.is_some();
 
-        let element = if let Some(name) = name {
-            if let Some(element) = storage.iter_mut().find(|element| element.name == name) {
-                element
-            } else {
-                storage.push(LineGroup::new(name, Vec::new()));
-                storage.last_mut().unwrap()
-            }
+        let group = if let Some(name) = name {
+            self.join_group(section, name)
         } else {
-            storage.last_mut().ok_or(Error::InvalidArtccEntry)?
+            self.last_group(section).ok_or(Error::InvalidArtccEntry)?
         };
 
         let line = pos_a
             .validate()
             .and_then(|pos_a| {
-                pos_b
-                    .validate()
-                    .map(|pos_b| ColouredLine::new(pos_a, pos_b, colour))
+                pos_b.validate().map(|pos_b| {
+                    ColouredLine::new(pos_a, pos_b, colour).with_colour_name(colour_name)
+                })
             })
             .ok();
 
         if let Some(line) = line {
-            element.lines.push(line);
+            self.emit(SctItem::Segment {
+                section,
+                group,
+                line,
+            });
         } else {
             if !name_exists {
                 return Err(Error::InvalidArtccEntry);
@@ -316,54 +546,48 @@ impl PartialSector {
         Ok(())
     }
 
-    pub fn parse_sid_star_line(
-        &mut self,
-        value: &str,
-        sid_star_type: SidStarType,
-    ) -> SectorResult<()> {
-        let name = value.get(0..26).ok_or(Error::InvalidSidStarEntry)?.trim();
-        let mut sections = value
-            .get(26..)
-            .ok_or(Error::InvalidSidStarEntry)?
-            .trim()
-            .split_whitespace();
+    pub fn parse_sid_star_line(&mut self, value: &str, section: LineCategory) -> SectorResult<()> {
+        // The name occupies the first 26 characters, which may be more than 26 bytes if the file
+        // contained accented characters.
+        let name_end = value
+            .char_indices()
+            .nth(26)
+            .map(|(index, _)| index)
+            .ok_or(Error::InvalidSidStarEntry)?;
+        let name = value[..name_end].trim();
+        let mut sections = value[name_end..].split_whitespace();
         let lat_a = sections.next().ok_or(Error::InvalidSidStarEntry)?;
         let lon_a = sections.next().ok_or(Error::InvalidSidStarEntry)?;
         let lat_b = sections.next().ok_or(Error::InvalidSidStarEntry)?;
         let lon_b = sections.next().ok_or(Error::InvalidSidStarEntry)?;
-        let colour = sections
+        let (colour, colour_name) = sections
             .next()
-            .and_then(|x| self.try_fetch_or_decode_colour(x));
+            .and_then(|x| self.try_fetch_or_decode_colour(x))
+            .unzip();
+        let colour_name = colour_name.flatten();
         let line = self
             .try_fetch_or_decode_lat_lon(lat_a, lon_a)
-            .and_then(|pos| pos.validate().ok())
+            .and_then(|pos| pos.validate())
             .and_then(|start_pos| {
                 self.try_fetch_or_decode_lat_lon(lat_b, lon_b)
-                    .and_then(|pos| pos.validate().ok())
-                    .and_then(|end_pos| Some(ColouredLine::new(start_pos, end_pos, colour)))
+                    .and_then(|pos| pos.validate())
+                    .map(|end_pos| {
+                        ColouredLine::new(start_pos, end_pos, colour).with_colour_name(colour_name)
+                    })
             });
 
-        let vec = match sid_star_type {
-            SidStarType::Sid => &mut self.sid_entries,
-            SidStarType::Star => &mut self.star_entries,
-        };
-
-        if name.is_empty() {
-            let in_progress_entry = vec.last_mut().ok_or(Error::InvalidSidStarEntry)?;
-            if let Some(line) = line {
-                in_progress_entry.lines.push(line);
-            }
+        // The entry is still created if the first line can't be resolved, so that any continuation
+        // lines have somewhere to go.
+        let group = if name.is_empty() {
+            self.last_group(section).ok_or(Error::InvalidSidStarEntry)?
         } else {
-            let new_entry = LineGroup::new(
-                name.to_owned(),
-                if let Some(line) = line {
-                    vec![line]
-                } else {
-                    vec![]
-                },
-            );
-            vec.push(new_entry);
-        }
+            self.start_group(section, name.to_owned())
+        };
+        self.emit(SctItem::Segment {
+            section,
+            group,
+            line: line?,
+        });
         Ok(())
     }
 
@@ -372,9 +596,11 @@ impl PartialSector {
         let mut sections = value.split_whitespace().collect::<Vec<_>>();
 
         // Get the colour, if there is one
-        let colour = sections
+        let (colour, colour_name) = sections
             .last()
-            .and_then(|section| self.try_fetch_or_decode_colour(section));
+            .and_then(|section| self.try_fetch_or_decode_colour(section))
+            .unzip();
+        let colour_name = colour_name.flatten();
 
         // And pop the colour off the end
         if colour.is_some() {
@@ -394,49 +620,41 @@ impl PartialSector {
         };
 
         // Deserialise the positions, but we're not checking to see if they are valid lat / longs yet - only that they're formatted correctly
-        let pos_a = self
-            .try_fetch_or_decode_lat_lon(
-                sections[first_coord_index],
-                sections[first_coord_index + 1],
-            )
-            .ok_or(Error::InvalidGeoEntry)?;
-        let pos_b = self
-            .try_fetch_or_decode_lat_lon(
-                sections[first_coord_index + 2],
-                sections[first_coord_index + 3],
-            )
-            .ok_or(Error::InvalidGeoEntry)?;
+        let pos_a = self.try_fetch_or_decode_lat_lon(
+            sections[first_coord_index],
+            sections[first_coord_index + 1],
+        )?;
+        let pos_b = self.try_fetch_or_decode_lat_lon(
+            sections[first_coord_index + 2],
+            sections[first_coord_index + 3],
+        )?;
 
-        let storage = &mut self.geo_entries;
         let name_exists = name.is_some();
 
-        let element = if let Some(name) = name {
-            if let Some(element) = storage.iter_mut().find(|element| element.name == name) {
-                element
-            } else {
-                storage.push(LineGroup::new(name, Vec::new()));
-                storage.last_mut().unwrap()
-            }
+        let group = if let Some(name) = name {
+            self.join_group(LineCategory::Geo, name)
         } else {
-            if let Some(entry) = storage.last_mut() {
-                entry
-            } else {
-                storage.push(LineGroup::new("DEFAULT".to_owned(), vec![]));
-                storage.last_mut().unwrap()
+            match self.last_group(LineCategory::Geo) {
+                Some(group) => group,
+                None => self.join_group(LineCategory::Geo, "DEFAULT".to_owned()),
             }
         };
 
         let line = pos_a
             .validate()
             .and_then(|pos_a| {
-                pos_b
-                    .validate()
-                    .map(|pos_b| ColouredLine::new(pos_a, pos_b, colour))
+                pos_b.validate().map(|pos_b| {
+                    ColouredLine::new(pos_a, pos_b, colour).with_colour_name(colour_name)
+                })
             })
             .ok();
 
         if let Some(line) = line {
-            element.lines.push(line);
+            self.emit(SctItem::Segment {
+                section: LineCategory::Geo,
+                group,
+                line,
+            });
         } else {
             if !name_exists {
                 return Err(Error::InvalidGeoEntry);
@@ -458,44 +676,34 @@ impl PartialSector {
             self.current_region_name = name.clone();
             return Ok(());
         }
-        // If a colour is defined, this is a new region. We see if any with the same name already exist, otherwise create it.
+        // If a colour is defined, this is a new region, added to any others with the same name.
         else if sections.len() == 3 {
-            let colour = self
+            let (colour, colour_name) = self
                 .try_fetch_or_decode_colour(sections[0])
                 .ok_or(Error::InvalidRegion)?;
-            if let Some(region_group) = self
-                .regions
-                .iter_mut()
-                .find(|region_group| region_group.name == self.current_region_name)
-            {
-                region_group.regions.push(PartialRegion {
-                    colour: Some(colour),
-                    vertices: vec![],
-                });
-            } else {
-                self.regions.push(PartialRegionGroup {
-                    name: self.current_region_name.clone(),
-                    regions: vec![],
-                });
-            }
+            self.started_regions
+                .insert(self.current_region_name.clone());
+            self.emit(SctItem::Region {
+                group: self.current_region_name.clone(),
+                colour,
+                colour_name,
+            });
         }
 
         // Finally we try to get some valid coords
-        if let Some(position) = self
-            .try_fetch_or_decode_lat_lon(sections[sections.len() - 2], sections[sections.len() - 1])
-            .map(|pos| pos.validate().ok())
-            .flatten()
-        {
-            self.regions
-                .iter_mut()
-                .find(|region_group| region_group.name == self.current_region_name)
-                .ok_or(Error::InvalidRegion)?
-                .regions
-                .last_mut()
-                .ok_or(Error::InvalidRegion)?
-                .vertices
-                .push(position);
+        let position = self
+            .try_fetch_or_decode_lat_lon(
+                sections[sections.len() - 2],
+                sections[sections.len() - 1],
+            )?
+            .validate()?;
+        if !self.started_regions.contains(&self.current_region_name) {
+            return Err(Error::InvalidRegion);
         }
+        self.emit(SctItem::RegionVertex {
+            group: self.current_region_name.clone(),
+            position,
+        });
 
         return Ok(());
     }
@@ -505,24 +713,33 @@ impl PartialSector {
         if sections.len() < 4 {
             return Err(Error::InvalidLabel);
         }
-        let colour = self
+        let (colour, colour_name) = self
             .try_fetch_or_decode_colour(sections[sections.len() - 1])
             .ok_or(Error::InvalidLabel)?;
-        let position =
-            Position::try_new_from_es(sections[sections.len() - 3], sections[sections.len() - 2])
-                .and_then(|position| position.validate())?;
+        let position = self
+            .read_position(sections[sections.len() - 3], sections[sections.len() - 2])
+            .and_then(|position| position.validate())?;
         let name = sections[0..sections.len() - 3].join(" ");
         let name = name.trim_matches('"');
         let label = Label {
             name: name.to_owned(),
             position,
             colour,
+            colour_name,
         };
-        self.labels.last_mut().unwrap().labels.push(label);
+        self.emit(SctItem::Label(label));
         Ok(())
     }
 }
 
+/// A cheap stand-in for distance that preserves ordering over the short ranges involved in
+/// choosing between duplicate identifiers.
+fn approx_distance_sq(a: Position<Valid>, b: Position<Valid>) -> f64 {
+    let d_lat = b.lat - a.lat;
+    let d_lon = (b.lon - a.lon) * ((a.lat + b.lat) / 2.0).to_radians().cos();
+    d_lat * d_lat + d_lon * d_lon
+}
+
 fn parse_runway_identifier(value: &str) -> SectorResult<(u8, RunwayModifier)> {
     let modifier = if value.ends_with('L') {
         RunwayModifier::Left
@@ -549,20 +766,46 @@ fn parse_runway_identifier(value: &str) -> SectorResult<(u8, RunwayModifier)> {
     Ok((number, modifier))
 }
 
-pub enum BeaconType {
+/// The kinds of waypoint that can be referred to by identifier, in order of preference when one is
+/// used more than once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum WaypointKind {
+    Fix,
     Vor,
     Ndb,
+    Airport,
 }
 
-pub enum ArtccOrAirwayLineType {
-    Artcc,
-    ArtccHigh,
-    ArtccLow,
-    LowAirway,
-    HighAirway,
+pub enum BeaconType {
+    Vor,
+    Ndb,
 }
 
-pub enum SidStarType {
-    Sid,
-    Star,
+#[test]
+fn test_duplicate_identifiers() {
+    let mut parser = LineParser::new();
+    parser
+        .parse_vor_or_ndb_line("DET 117.300 N051.18.14.000 E000.35.50.000", BeaconType::Vor)
+        .unwrap();
+    parser
+        .parse_fixes_line("DET N045.00.00.000 E010.00.00.000")
+        .unwrap();
+    parser
+        .parse_fixes_line("LAM N051.38.46.000 E000.09.07.000")
+        .unwrap();
+
+    // With nothing to go on, the fix is preferred over the VOR.
+    let position = parser.try_fetch_or_decode_lat_lon("DET", "DET").unwrap();
+    assert_eq!(position.lat, 45.0);
+
+    // Following on from LAM, the nearby VOR is taken instead.
+    parser
+        .parse_artcc_or_airway_line("L6 LAM LAM DET DET", LineCategory::LowAirway)
+        .unwrap();
+    let mut partial = PartialSector::new();
+    while let Some(item) = parser.next_item() {
+        partial.add(item);
+    }
+    let line = &partial.low_airways[0].lines[0];
+    assert!((line.line.end.lat - 51.30389).abs() < 1e-4);
 }
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use crate::{
     colour::Colour,
     position::{Position, Valid},
@@ -20,5 +22,6 @@ impl PartialRegionGroup {
 #[derive(Debug, Default)]
 pub struct PartialRegion {
     pub colour: Option<Colour>,
+    pub colour_name: Option<Arc<str>>,
     pub vertices: Vec<Position<Valid>>,
 }
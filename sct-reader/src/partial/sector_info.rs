@@ -1,4 +1,9 @@
-use crate::{error::Error, position, SectorResult};
+use crate::{
+    coordinate::{self, Axis, CoordinateFormat},
+    error::Error,
+    sector::SectorInfoField::{self, *},
+    SectorResult,
+};
 
 #[derive(Debug, Clone, Default)]
 pub struct PartialSectorInfo {
@@ -11,37 +16,42 @@ pub struct PartialSectorInfo {
     pub n_mi_per_deg_lon: Option<f32>,
     pub magnetic_variation: Option<f32>,
     pub sector_scale: Option<f32>,
-
-    current_line: usize,
+    pub coordinate_format: Option<CoordinateFormat>,
 }
 impl PartialSectorInfo {
-    pub fn parse_line(&mut self, value: &str) -> SectorResult<()> {
-        self.current_line += 1;
-        //println!("Line {}: |{value}|", self.current_line);
-        match self.current_line {
-            1 => self.name = Some(value.to_owned()),
-            2 => self.default_callsign = Some(value.to_owned()),
-            3 => self.default_airport = Some(value.to_owned()),
-            4 => self.default_centre_pt_lat = position::coord_from_es(value),
-            5 => self.default_centre_pt_lon = position::coord_from_es(value),
-            6 => {
-                self.n_mi_per_deg_lat =
-                    Some(value.parse::<f32>().map_err(|_| Error::SectorInfoError)?)
-            }
-            7 => {
-                self.n_mi_per_deg_lon =
-                    Some(value.parse::<f32>().map_err(|_| Error::SectorInfoError)?)
+    pub fn set(&mut self, field: SectorInfoField, value: &str) -> SectorResult<()> {
+        match field {
+            Name => self.name = Some(value.to_owned()),
+            DefaultCallsign => self.default_callsign = Some(value.to_owned()),
+            DefaultAirport => self.default_airport = Some(value.to_owned()),
+            DefaultCentrePointLat => {
+                self.default_centre_pt_lat = Some(parse_coordinate(value, Axis::Lat, field)?)
             }
-            8 => {
-                self.magnetic_variation =
-                    Some(value.parse::<f32>().map_err(|_| Error::SectorInfoError)?)
+            DefaultCentrePointLon => {
+                self.default_centre_pt_lon = Some(parse_coordinate(value, Axis::Lon, field)?)
             }
-            9 => {
-                self.sector_scale = Some(value.parse::<f32>().map_err(|_| Error::SectorInfoError)?)
+            NMiPerDegLat => self.n_mi_per_deg_lat = Some(parse_f32(value, NMiPerDegLat)?),
+            NMiPerDegLon => self.n_mi_per_deg_lon = Some(parse_f32(value, NMiPerDegLon)?),
+            MagneticVariation => {
+                self.magnetic_variation = Some(parse_f32(value, MagneticVariation)?)
             }
-            _ => return Err(Error::SectorInfoError),
+            SectorScale => self.sector_scale = Some(parse_f32(value, SectorScale)?),
         }
 
         Ok(())
     }
 }
+
+fn parse_coordinate(value: &str, axis: Axis, field: SectorInfoField) -> SectorResult<f64> {
+    match coordinate::read_sector_field(value, axis) {
+        Ok((coordinate, _)) => Ok(coordinate),
+        Err(e @ Error::MinutesOrSecondsOutOfRange(_)) => Err(e),
+        Err(_) => Err(Error::SectorInfoError(field)),
+    }
+}
+
+fn parse_f32(value: &str, field: SectorInfoField) -> SectorResult<f32> {
+    value
+        .parse::<f32>()
+        .map_err(|_| Error::SectorInfoError(field))
+}
@@ -1,6 +1,10 @@
 use std::{fmt::Display, marker::PhantomData};
 
-use crate::{error::Error, SectorResult};
+use crate::{
+    coordinate::{self, CoordinateFormat},
+    error::Error,
+    SectorResult,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Position<Status = MaybeValid> {
@@ -16,14 +20,10 @@ impl Position {
             status: PhantomData,
         }
     }
+    /// Reads a position from the latitude and longitude fields of a sector file. As well as
+    /// EuroScope's `N051.07.25.010`, VRC's `N51.7.25.010` and decimal degrees are accepted.
     pub fn try_new_from_es(lat: &str, lon: &str) -> SectorResult<Position> {
-        let lat = coord_from_es(lat).ok_or(Error::InvalidPosition)?;
-        let lon = coord_from_es(lon).ok_or(Error::InvalidPosition)?;
-        Ok(Position {
-            lat,
-            lon,
-            status: PhantomData,
-        })
+        coordinate::read_sector_pair(lat, lon).map(|(position, _)| position)
     }
     pub fn validate(self) -> SectorResult<Position<Valid>> {
         let valid = (-90.0..=90.0).contains(&self.lat) && (-180.0..=180.0).contains(&self.lon);
@@ -37,6 +37,201 @@ impl Position {
             Err(Error::InvalidPosition)
         };
     }
+    /// Reads a latitude and longitude pair written in any of the [`CoordinateFormat`]s.
+    pub fn parse(text: &str) -> SectorResult<Position<Valid>> {
+        CoordinateFormat::detect(text)
+            .ok_or(Error::InvalidCoordinate)?
+            .parse(text)
+    }
+}
+impl<Status> Position<Status> {
+    /// The position as a EuroScope latitude and longitude pair.
+    pub fn to_es(&self) -> (String, String) {
+        (lat_to_es(self.lat), lon_to_es(self.lon))
+    }
+}
+
+impl Position<Valid> {
+    pub fn format(&self, format: CoordinateFormat) -> String {
+        format.format(self)
+    }
+
+    /// Describes a move from here to `to`, e.g.
+    /// `moved 25 m from N051.08.53.000 W000.11.25.000 to N051.08.53.800 W000.11.25.000`.
+    pub(crate) fn describe_move(&self, to: &Position<Valid>) -> String {
+        let (from_lat, from_lon) = self.to_es();
+        let (to_lat, to_lon) = to.to_es();
+        format!(
+            "moved {:.0} m from {} {} to {} {}",
+            self.distance_m(to),
+            from_lat,
+            from_lon,
+            to_lat,
+            to_lon
+        )
+    }
+}
+
+/// Mean radius of the Earth, as used by the spherical calculations on [`Position<Valid>`].
+pub const EARTH_RADIUS_M: f64 = 6_371_000.0;
+pub const METRES_PER_N_MI: f64 = 1852.0;
+pub const METRES_PER_FOOT: f64 = 0.3048;
+
+/// Great circle and rhumb line calculations on a spherical Earth. Distances are in metres and
+/// bearings in degrees true, from 0 up to (but not including) 360.
+impl Position<Valid> {
+    /// Builds a position from calculated values, normalising the longitude to ±180°.
+    fn from_radians(lat: f64, lon: f64) -> Position<Valid> {
+        let lon = (lon.to_degrees() + 540.0) % 360.0 - 180.0;
+        Position {
+            lat: lat.to_degrees().clamp(-90.0, 90.0),
+            lon,
+            status: PhantomData,
+        }
+    }
+
+    /// Great circle distance, using the haversine formula.
+    pub fn distance_m(&self, other: &Position<Valid>) -> f64 {
+        self.angular_distance(other) * EARTH_RADIUS_M
+    }
+    pub fn distance_n_mi(&self, other: &Position<Valid>) -> f64 {
+        self.distance_m(other) / METRES_PER_N_MI
+    }
+    fn angular_distance(&self, other: &Position<Valid>) -> f64 {
+        let (lat_1, lat_2) = (self.lat.to_radians(), other.lat.to_radians());
+        let d_lat = lat_2 - lat_1;
+        let d_lon = (other.lon - self.lon).to_radians();
+        let a =
+            (d_lat / 2.0).sin().powi(2) + lat_1.cos() * lat_2.cos() * (d_lon / 2.0).sin().powi(2);
+        2.0 * a.sqrt().atan2((1.0 - a).sqrt())
+    }
+
+    /// The bearing to set off on to reach `other` along a great circle.
+    pub fn initial_bearing(&self, other: &Position<Valid>) -> f64 {
+        let (lat_1, lat_2) = (self.lat.to_radians(), other.lat.to_radians());
+        let d_lon = (other.lon - self.lon).to_radians();
+        let y = d_lon.sin() * lat_2.cos();
+        let x = lat_1.cos() * lat_2.sin() - lat_1.sin() * lat_2.cos() * d_lon.cos();
+        normalise_bearing(y.atan2(x).to_degrees())
+    }
+
+    /// The bearing on arrival at `other` along a great circle.
+    pub fn final_bearing(&self, other: &Position<Valid>) -> f64 {
+        normalise_bearing(other.initial_bearing(self) + 180.0)
+    }
+
+    /// The position reached by travelling `distance_m` along a great circle, setting off on
+    /// `bearing`.
+    pub fn destination(&self, distance_m: f64, bearing: f64) -> Position<Valid> {
+        let delta = distance_m / EARTH_RADIUS_M;
+        let theta = bearing.to_radians();
+        let lat_1 = self.lat.to_radians();
+        let lat_2 = (lat_1.sin() * delta.cos() + lat_1.cos() * delta.sin() * theta.cos()).asin();
+        let lon_2 = self.lon.to_radians()
+            + (theta.sin() * delta.sin() * lat_1.cos())
+                .atan2(delta.cos() - lat_1.sin() * lat_2.sin());
+        Position::from_radians(lat_2, lon_2)
+    }
+
+    /// Distance from this position to the great circle through `start` and `end`. Negative when
+    /// this position is to the left of the path.
+    pub fn cross_track_distance_m(&self, start: &Position<Valid>, end: &Position<Valid>) -> f64 {
+        let delta_13 = start.angular_distance(self);
+        let theta_13 = start.initial_bearing(self).to_radians();
+        let theta_12 = start.initial_bearing(end).to_radians();
+        (delta_13.sin() * (theta_13 - theta_12).sin()).asin() * EARTH_RADIUS_M
+    }
+
+    /// Distance from `start` to the point on the great circle through `start` and `end` nearest
+    /// this position. Negative when that point is behind `start`.
+    pub fn along_track_distance_m(&self, start: &Position<Valid>, end: &Position<Valid>) -> f64 {
+        let delta_13 = start.angular_distance(self);
+        let delta_xt = self.cross_track_distance_m(start, end) / EARTH_RADIUS_M;
+        let delta_at = (delta_13.cos() / delta_xt.cos()).clamp(-1.0, 1.0).acos();
+        let theta_13 = start.initial_bearing(self).to_radians();
+        let theta_12 = start.initial_bearing(end).to_radians();
+        delta_at.copysign((theta_12 - theta_13).cos()) * EARTH_RADIUS_M
+    }
+
+    /// The point `fraction` of the way to `other` along a great circle.
+    pub fn interpolate(&self, other: &Position<Valid>, fraction: f64) -> Position<Valid> {
+        let delta = self.angular_distance(other);
+        if delta == 0.0 {
+            return *self;
+        }
+        let a = ((1.0 - fraction) * delta).sin() / delta.sin();
+        let b = (fraction * delta).sin() / delta.sin();
+        let (lat_1, lon_1) = (self.lat.to_radians(), self.lon.to_radians());
+        let (lat_2, lon_2) = (other.lat.to_radians(), other.lon.to_radians());
+        let x = a * lat_1.cos() * lon_1.cos() + b * lat_2.cos() * lon_2.cos();
+        let y = a * lat_1.cos() * lon_1.sin() + b * lat_2.cos() * lon_2.sin();
+        let z = a * lat_1.sin() + b * lat_2.sin();
+        Position::from_radians(z.atan2(x.hypot(y)), y.atan2(x))
+    }
+
+    pub fn midpoint(&self, other: &Position<Valid>) -> Position<Valid> {
+        self.interpolate(other, 0.5)
+    }
+
+    /// Distance to `other` along a line of constant bearing.
+    pub fn rhumb_distance_m(&self, other: &Position<Valid>) -> f64 {
+        let (lat_1, lat_2) = (self.lat.to_radians(), other.lat.to_radians());
+        let d_lat = lat_2 - lat_1;
+        let d_lon = wrap_radians((other.lon - self.lon).to_radians());
+        let d_psi = stretched_latitude_difference(lat_1, lat_2);
+        // Along a parallel the stretched difference is zero, so use the latitude directly.
+        let q = if d_psi.abs() > 1e-12 {
+            d_lat / d_psi
+        } else {
+            lat_1.cos()
+        };
+        d_lat.hypot(q * d_lon) * EARTH_RADIUS_M
+    }
+
+    /// The constant bearing of the rhumb line to `other`.
+    pub fn rhumb_bearing(&self, other: &Position<Valid>) -> f64 {
+        let (lat_1, lat_2) = (self.lat.to_radians(), other.lat.to_radians());
+        let d_lon = wrap_radians((other.lon - self.lon).to_radians());
+        let d_psi = stretched_latitude_difference(lat_1, lat_2);
+        normalise_bearing(d_lon.atan2(d_psi).to_degrees())
+    }
+
+    /// The position reached by travelling `distance_m` on a constant `bearing`.
+    pub fn rhumb_destination(&self, distance_m: f64, bearing: f64) -> Position<Valid> {
+        let delta = distance_m / EARTH_RADIUS_M;
+        let theta = bearing.to_radians();
+        let lat_1 = self.lat.to_radians();
+        let d_lat = delta * theta.cos();
+        let mut lat_2 = lat_1 + d_lat;
+        // Going past a pole comes back down the other side.
+        if lat_2.abs() > std::f64::consts::FRAC_PI_2 {
+            lat_2 = std::f64::consts::PI.copysign(lat_2) - lat_2;
+        }
+        let d_psi = stretched_latitude_difference(lat_1, lat_2);
+        let q = if d_psi.abs() > 1e-12 {
+            d_lat / d_psi
+        } else {
+            lat_1.cos()
+        };
+        let d_lon = delta * theta.sin() / q;
+        Position::from_radians(lat_2, self.lon.to_radians() + d_lon)
+    }
+}
+
+fn normalise_bearing(degrees: f64) -> f64 {
+    degrees.rem_euclid(360.0)
+}
+
+/// Wraps a longitude difference to ±π, so rhumb lines take the shorter way round.
+fn wrap_radians(value: f64) -> f64 {
+    let pi = std::f64::consts::PI;
+    (value + pi).rem_euclid(2.0 * pi) - pi
+}
+
+/// The difference in Mercator-projected latitude between two latitudes.
+fn stretched_latitude_difference(lat_1: f64, lat_2: f64) -> f64 {
+    let quarter = std::f64::consts::FRAC_PI_4;
+    ((lat_2 / 2.0 + quarter).tan() / (lat_1 / 2.0 + quarter).tan()).ln()
 }
 
 impl From<Position<Valid>> for Position<MaybeValid> {
@@ -61,43 +256,119 @@ pub fn coord_from_es(value: &str) -> Option<f64> {
     let degs = sections.next()?.parse::<f64>().ok()?;
     let mins = sections.next()?.parse::<f64>().ok()?;
     let secs = sections.next()?.parse::<f64>().ok()?;
+    if mins >= 60.0 || secs >= 60.0 {
+        return None;
+    }
 
     let coord = degs + (mins / 60.0) + (secs / 3600.0);
     return Some(coord * multiply_by);
 }
 
+/// Formats a latitude in the same layout `coord_from_es` reads, e.g. `N051.07.25.010`.
+pub fn lat_to_es(lat: f64) -> String {
+    coord_to_es(lat, 'N', 'S')
+}
+
+/// Formats a longitude in the same layout `coord_from_es` reads, e.g. `E002.39.13.334`.
+pub fn lon_to_es(lon: f64) -> String {
+    coord_to_es(lon, 'E', 'W')
+}
+
+fn coord_to_es(value: f64, positive: char, negative: char) -> String {
+    let hemisphere = if value.is_sign_negative() {
+        negative
+    } else {
+        positive
+    };
+    // Rounding to whole thousandths of a second means a coordinate read from a file is written
+    // back out with the same digits, so it parses to exactly the same value.
+    let (degs, mins, thousandths) = split_degrees(value.abs(), 60_000);
+    format!(
+        "{}{:03}.{:02}.{:02}.{:03}",
+        hemisphere,
+        degs,
+        mins,
+        thousandths / 1000,
+        thousandths % 1000
+    )
+}
+
+/// Splits a positive number of degrees into whole degrees, whole minutes and the rest of the
+/// minute rounded to `steps_per_minute`, carrying into the minutes and degrees so none of them
+/// overflow.
+pub(crate) fn split_degrees(value: f64, steps_per_minute: u32) -> (u32, u32, u32) {
+    let mut degs = value.trunc() as u32;
+    let mins_with_fraction = value.fract() * 60.0;
+    let mut mins = mins_with_fraction.trunc() as u32;
+    let mut steps = (mins_with_fraction.fract() * steps_per_minute as f64).round() as u32;
+    if steps >= steps_per_minute {
+        steps -= steps_per_minute;
+        mins += 1;
+    }
+    if mins >= 60 {
+        mins -= 60;
+        degs += 1;
+    }
+    (degs, mins, steps)
+}
+
+/// A heading in degrees, from just above 0 up to 360. `Reference` is [`Magnetic`] or [`True`], so
+/// the two can't be mixed up; convert between them with [`Heading::to_true`] and
+/// [`Heading::to_magnetic`].
 #[derive(Debug, Clone, Copy, PartialEq)]
-pub struct Heading(f32);
-impl Heading {
-    pub fn new(heading: f32) -> SectorResult<Heading> {
+pub struct Heading<Reference = Magnetic>(f32, PhantomData<Reference>);
+pub type MagneticHeading = Heading<Magnetic>;
+pub type TrueHeading = Heading<True>;
+
+impl<Reference> Heading<Reference> {
+    pub fn new(heading: f32) -> SectorResult<Heading<Reference>> {
         heading.try_into()
     }
-    pub fn new_from_u16(heading: u16) -> SectorResult<Heading> {
+    pub fn new_from_u16(heading: u16) -> SectorResult<Heading<Reference>> {
         let value: f32 = heading.try_into().map_err(|_| Error::InvalidHeading)?;
         Self::new(value)
     }
+    /// Wraps any value into range, e.g. -10 becomes 350 and 370 becomes 10.
+    pub fn wrapping(heading: f32) -> Heading<Reference> {
+        let value = heading.rem_euclid(360.0);
+        Heading(if value == 0.0 { 360.0 } else { value }, PhantomData)
+    }
     pub fn value(&self) -> f32 {
         self.0
     }
     pub fn value_u16(&self) -> u16 {
         self.0.round() as u16
     }
-    pub fn reciprocal(&self) -> Heading {
+    pub fn reciprocal(&self) -> Heading<Reference> {
         let new = if self.0 < 180.0 {
             self.0 + 180.0
         } else {
             self.0 - 180.0
         };
-        Heading(new)
+        Heading(new, PhantomData)
+    }
+}
+impl Heading<Magnetic> {
+    pub fn to_true(&self, variation: &impl VariationModel, at: Position<Valid>) -> TrueHeading {
+        Heading::wrapping(self.0 - variation.variation_at(at))
     }
 }
-impl Display for Heading {
+impl Heading<True> {
+    pub fn to_magnetic(
+        &self,
+        variation: &impl VariationModel,
+        at: Position<Valid>,
+    ) -> MagneticHeading {
+        Heading::wrapping(self.0 + variation.variation_at(at))
+    }
+}
+impl<Reference> Display for Heading<Reference> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:03}", self.0)
     }
 }
 
-impl TryFrom<f32> for Heading {
+impl<Reference> TryFrom<f32> for Heading<Reference> {
     type Error = Error;
     fn try_from(mut value: f32) -> Result<Self, Self::Error> {
         if value > 360.0 {
@@ -106,7 +377,74 @@ impl TryFrom<f32> for Heading {
         if value == 0.0 {
             value = 360.0;
         }
-        Ok(Heading(value))
+        Ok(Heading(value, PhantomData))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Magnetic;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct True;
+
+/// Gives the magnetic variation in degrees at a position, using the sector file convention that
+/// West is positive. A true heading is the magnetic heading minus the variation.
+///
+/// A plain `f32` is a variation that is the same everywhere.
+pub trait VariationModel {
+    fn variation_at(&self, position: Position<Valid>) -> f32;
+}
+impl VariationModel for f32 {
+    fn variation_at(&self, _: Position<Valid>) -> f32 {
+        *self
+    }
+}
+
+/// Positions are written as `{ lat, lon }`. Reading a `Position<Valid>` checks it is in range, so
+/// the typestate can't be forged by editing serialised data.
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use serde::{de, ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{Heading, MaybeValid, Position, Valid};
+
+    #[derive(Deserialize)]
+    #[serde(rename = "Position")]
+    struct RawPosition {
+        lat: f64,
+        lon: f64,
+    }
+
+    impl<Status> Serialize for Position<Status> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut state = serializer.serialize_struct("Position", 2)?;
+            state.serialize_field("lat", &self.lat)?;
+            state.serialize_field("lon", &self.lon)?;
+            state.end()
+        }
+    }
+    impl<'de> Deserialize<'de> for Position<MaybeValid> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let raw = RawPosition::deserialize(deserializer)?;
+            Ok(Position::new(raw.lat, raw.lon))
+        }
+    }
+    impl<'de> Deserialize<'de> for Position<Valid> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Position::<MaybeValid>::deserialize(deserializer)?
+                .validate()
+                .map_err(de::Error::custom)
+        }
+    }
+
+    impl<Reference> Serialize for Heading<Reference> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_f32(self.0)
+        }
+    }
+    impl<'de, Reference> Deserialize<'de> for Heading<Reference> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Heading::new(f32::deserialize(deserializer)?).map_err(de::Error::custom)
+        }
     }
 }
 
@@ -114,3 +452,71 @@ impl TryFrom<f32> for Heading {
 pub struct MaybeValid;
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Valid;
+
+#[test]
+fn test_geodesy() {
+    let position = |lat: &str, lon: &str| {
+        Position::try_new_from_es(lat, lon)
+            .unwrap()
+            .validate()
+            .unwrap()
+    };
+    let close =
+        |a: f64, b: f64, tolerance: f64| assert!((a - b).abs() < tolerance, "{} != {}", a, b);
+
+    // Land's End to John o' Groats.
+    let lands_end = position("N050.03.59.000", "W005.42.53.000");
+    let john_o_groats = position("N058.38.38.000", "W003.04.12.000");
+    close(lands_end.distance_m(&john_o_groats), 968_900.0, 100.0);
+    close(lands_end.initial_bearing(&john_o_groats), 9.1198, 1e-4);
+    close(lands_end.final_bearing(&john_o_groats), 11.2752, 1e-4);
+    let midpoint = lands_end.midpoint(&john_o_groats);
+    close(midpoint.lat, 54.0 + 21.0 / 60.0 + 44.0 / 3600.0, 1e-3);
+    close(midpoint.lon, -(4.0 + 31.0 / 60.0 + 50.0 / 3600.0), 1e-3);
+    close(
+        lands_end.interpolate(&john_o_groats, 0.0).lon,
+        lands_end.lon,
+        1e-9,
+    );
+
+    let destination = position("N053.19.14.000", "W001.43.47.000").destination(124_800.0, 96.0217);
+    close(destination.lat, 53.0 + 11.0 / 60.0 + 18.0 / 3600.0, 1e-3);
+    close(destination.lon, 8.0 / 60.0, 1e-3);
+
+    let start = Position::new(53.3206, -1.7297).validate().unwrap();
+    let end = Position::new(53.1887, 0.1334).validate().unwrap();
+    let point = Position::new(53.2611, -0.7972).validate().unwrap();
+    close(point.cross_track_distance_m(&start, &end), -307.5, 1.0);
+    close(point.along_track_distance_m(&start, &end), 62_331.0, 10.0);
+
+    // Dover to Calais.
+    let dover = position("N051.07.32.000", "E001.20.17.000");
+    let calais = position("N050.57.48.000", "E001.51.09.000");
+    close(dover.rhumb_distance_m(&calais), 40_230.0, 10.0);
+    close(dover.rhumb_bearing(&calais), 116.6361, 1e-3);
+    let rhumb_destination = dover.rhumb_destination(40_230.0, 116.6361);
+    close(rhumb_destination.lat, calais.lat, 1e-3);
+    close(rhumb_destination.lon, calais.lon, 1e-3);
+
+    // Across the antimeridian.
+    let fiji = Position::new(-18.0, 179.5).validate().unwrap();
+    let samoa = Position::new(-18.0, -179.5).validate().unwrap();
+    close(fiji.rhumb_bearing(&samoa), 90.0, 1e-9);
+    close(
+        fiji.rhumb_destination(fiji.rhumb_distance_m(&samoa), 90.0)
+            .lon,
+        -179.5,
+        1e-9,
+    );
+}
+
+#[test]
+fn test_heading_conversion() {
+    let position = Position::new(51.5, -0.5).validate().unwrap();
+    let magnetic = MagneticHeading::new(271.0).unwrap();
+    assert_eq!(magnetic.to_true(&1.5, position).value(), 269.5);
+    assert_eq!(magnetic.to_true(&-90.0, position).value(), 1.0);
+    let north = TrueHeading::new(359.0).unwrap().to_magnetic(&1.0, position);
+    assert_eq!(north.value(), 360.0);
+    assert_eq!(north.to_true(&1.0, position).value(), 359.0);
+}
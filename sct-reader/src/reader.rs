@@ -2,104 +2,237 @@ use std::{
     collections::HashMap,
     fs::File,
     io::{BufRead, BufReader, BufWriter},
+    iter::Enumerate,
+    sync::Arc,
     time::Instant,
 };
 
 use crate::{
     colour::Colour,
+    coordinate::CoordinateFormat,
+    diagnostic::{Diagnostic, Severity},
+    encoding::{DecodedLine, DecodedLines, Encoding},
     error::Error,
-    partial::{ArtccOrAirwayLineType, BeaconType, PartialSector, SidStarType},
-    sector::Sector,
+    line::ColouredLine,
+    partial::{BeaconType, LineParser, PartialSector},
+    position::{Position, Valid},
+    sector::{Label, LineCategory, Sector, SectorInfoField},
+    waypoint::{Airport, Fix, Ndb, RunwayStrip, Vor},
     SectorResult,
 };
 use std::io::Write;
 
+/// Something read from a sector file, in the order it appears. See [`SctReader::items`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SctItem {
+    /// A `#define`. The name is as written, though names are matched ignoring case.
+    Colour {
+        name: String,
+        colour: Colour,
+    },
+    /// One of the `[INFO]` lines. The value has been checked, but is left as written.
+    InfoField {
+        field: SectorInfoField,
+        value: String,
+    },
+    Airport(Airport),
+    Vor(Vor),
+    Ndb(Ndb),
+    Fix(Fix),
+    /// A runway, belonging to the airport with this identifier.
+    RunwayStrip {
+        airport: String,
+        strip: RunwayStrip,
+    },
+    /// The start of a named group of lines. Comes before the group's first [`SctItem::Segment`].
+    /// In `[SID]` and `[STAR]` a name can be used again, which starts another group.
+    Group {
+        section: LineCategory,
+        name: String,
+    },
+    /// A line belonging to the most recent group of this name.
+    Segment {
+        section: LineCategory,
+        group: String,
+        line: ColouredLine,
+    },
+    /// The start of a region, added to the region group of this name.
+    Region {
+        group: String,
+        colour: Colour,
+        colour_name: Option<Arc<str>>,
+    },
+    /// A vertex of the most recent region in this group.
+    RegionVertex {
+        group: String,
+        position: Position<Valid>,
+    },
+    Label(Label),
+    /// The notation most of the file's coordinates are written in. Comes after everything else.
+    CoordinateFormat(CoordinateFormat),
+    /// A line that couldn't be read.
+    Diagnostic(Diagnostic),
+}
+
 pub struct SctReader<R: BufRead> {
     source: R,
-    current_section: FileSection,
-    partial_sector: PartialSector,
-    errors: Vec<(usize, String, Error)>,
+    file_name: Option<String>,
+    encoding: Encoding,
 }
 impl<R: BufRead> SctReader<R> {
     pub fn new(source: R) -> Self {
         Self {
             source,
+            file_name: None,
+            encoding: Encoding::Auto,
+        }
+    }
+
+    /// Sets the file name reported in diagnostics.
+    pub fn with_file_name(mut self, file_name: impl Into<String>) -> Self {
+        self.file_name = Some(file_name.into());
+        self
+    }
+
+    /// Sets how the source is decoded. Defaults to [`Encoding::Auto`].
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Reads the file one item at a time, without building up a [`Sector`]. Only the colour
+    /// definitions, waypoint positions and group names are kept, to make sense of later lines.
+    pub fn items(self) -> SctItems<R> {
+        SctItems {
+            lines: DecodedLines::new(self.source, self.encoding).enumerate(),
+            file_name: self.file_name,
             current_section: FileSection::ColourDefinitions,
-            partial_sector: PartialSector::new(),
-            errors: vec![],
+            parser: LineParser::new(),
+            finished: false,
         }
     }
 
-    pub fn try_read(mut self) -> SectorResult<Sector> {
-        for (mut line_number, line) in self.source.lines().enumerate() {
-            if let Ok(line) = line {
-                let mut line = line.trim_end();
-                line_number += 1;
+    pub fn try_read(self) -> SectorResult<Sector> {
+        let mut partial_sector = PartialSector::new();
+        for item in self.items() {
+            partial_sector.add(item?);
+        }
+        partial_sector.try_into()
+    }
+}
 
-                if line.is_empty() || line.starts_with(';') {
-                    continue;
-                }
-                if line.contains(';') {
-                    let mut line_split = line.split(';');
-                    line = line_split.next().unwrap().trim_end();
-                }
-                if line.starts_with('[') {
-                    match parse_file_section(line) {
-                        Ok(new_section) => self.current_section = new_section,
-                        Err(e) => self.errors.push((line_number + 1, line.to_owned(), e)),
-                    }
-                    continue;
-                }
-                if line.starts_with("#define") {
-                    self.current_section = FileSection::ColourDefinitions;
-                }
+/// An iterator over the [`SctItem`]s in a sector file, from [`SctReader::items`]. Lines that can't
+/// be read give an [`SctItem::Diagnostic`]; only failing to read the source at all is an error.
+pub struct SctItems<R: BufRead> {
+    lines: Enumerate<DecodedLines<R>>,
+    file_name: Option<String>,
+    current_section: FileSection,
+    parser: LineParser,
+    finished: bool,
+}
+impl<R: BufRead> SctItems<R> {
+    fn read_line(&mut self, line_number: usize, line: DecodedLine) {
+        let line = match line {
+            DecodedLine::Text(line) => line,
+            DecodedLine::Invalid(line) => {
+                self.parser.emit(SctItem::Diagnostic(
+                    Diagnostic::new(
+                        line_number,
+                        line.trim_end(),
+                        Some(self.current_section.name()),
+                        Severity::Error,
+                        Error::InvalidEncoding,
+                    )
+                    .with_file(self.file_name.as_deref()),
+                ));
+                return;
+            }
+        };
+        let mut line = line.trim_end();
 
-                let result = match self.current_section {
-                    FileSection::ColourDefinitions => self.partial_sector.parse_colour_line(line),
-                    FileSection::Info => self.partial_sector.parse_sector_info_line(line),
-                    FileSection::Airport => self.partial_sector.parse_airport_line(line),
-                    FileSection::Runway => self.partial_sector.parse_runway_line(line),
-                    FileSection::Vor => self
-                        .partial_sector
-                        .parse_vor_or_ndb_line(line, BeaconType::Vor),
-                    FileSection::Ndb => self
-                        .partial_sector
-                        .parse_vor_or_ndb_line(line, BeaconType::Ndb),
-                    FileSection::Fixes => self.partial_sector.parse_fixes_line(line),
-                    FileSection::Artcc => self
-                        .partial_sector
-                        .parse_artcc_or_airway_line(line, ArtccOrAirwayLineType::Artcc),
-                    FileSection::ArtccHigh => self
-                        .partial_sector
-                        .parse_artcc_or_airway_line(line, ArtccOrAirwayLineType::ArtccHigh),
-                    FileSection::ArtccLow => self
-                        .partial_sector
-                        .parse_artcc_or_airway_line(line, ArtccOrAirwayLineType::ArtccLow),
-                    FileSection::LowAirway => self
-                        .partial_sector
-                        .parse_artcc_or_airway_line(line, ArtccOrAirwayLineType::LowAirway),
-                    FileSection::HighAirway => self
-                        .partial_sector
-                        .parse_artcc_or_airway_line(line, ArtccOrAirwayLineType::HighAirway),
-                    FileSection::Sid => self
-                        .partial_sector
-                        .parse_sid_star_line(line, SidStarType::Sid),
-                    FileSection::Star => self
-                        .partial_sector
-                        .parse_sid_star_line(line, SidStarType::Star),
-                    FileSection::Geo => self.partial_sector.parse_geo_line(line),
-                    FileSection::Regions => self.partial_sector.parse_region_line(line),
-                    FileSection::Labels => self.partial_sector.parse_label_line(line),
-                };
-                if let Err(e) = result {
-                    self.errors.push((line_number + 1, line.to_owned(), e));
-                }
+        if line.is_empty() || line.starts_with(';') {
+            return;
+        }
+        if line.contains(';') {
+            let mut line_split = line.split(';');
+            line = line_split.next().unwrap().trim_end();
+        }
+        if line.starts_with('[') {
+            match parse_file_section(line) {
+                Ok(new_section) => self.current_section = new_section,
+                Err(e) => self.parser.emit(SctItem::Diagnostic(
+                    Diagnostic::new(line_number, line, None, Severity::Warning, e)
+                        .with_file(self.file_name.as_deref()),
+                )),
             }
+            return;
+        }
+        if line.starts_with("#define") {
+            self.current_section = FileSection::ColourDefinitions;
         }
 
-        let mut sector: Sector = self.partial_sector.try_into()?;
-        sector.non_critical_errors = self.errors;
-        Ok(sector)
+        let parser = &mut self.parser;
+        let result = match self.current_section {
+            FileSection::ColourDefinitions => parser.parse_colour_line(line),
+            FileSection::Info => parser.parse_sector_info_line(line),
+            FileSection::Airport => parser.parse_airport_line(line),
+            FileSection::Runway => parser.parse_runway_line(line),
+            FileSection::Vor => parser.parse_vor_or_ndb_line(line, BeaconType::Vor),
+            FileSection::Ndb => parser.parse_vor_or_ndb_line(line, BeaconType::Ndb),
+            FileSection::Fixes => parser.parse_fixes_line(line),
+            FileSection::Artcc => parser.parse_artcc_or_airway_line(line, LineCategory::Artcc),
+            FileSection::ArtccHigh => {
+                parser.parse_artcc_or_airway_line(line, LineCategory::ArtccHigh)
+            }
+            FileSection::ArtccLow => {
+                parser.parse_artcc_or_airway_line(line, LineCategory::ArtccLow)
+            }
+            FileSection::LowAirway => {
+                parser.parse_artcc_or_airway_line(line, LineCategory::LowAirway)
+            }
+            FileSection::HighAirway => {
+                parser.parse_artcc_or_airway_line(line, LineCategory::HighAirway)
+            }
+            FileSection::Sid => parser.parse_sid_star_line(line, LineCategory::Sid),
+            FileSection::Star => parser.parse_sid_star_line(line, LineCategory::Star),
+            FileSection::Geo => parser.parse_geo_line(line),
+            FileSection::Regions => parser.parse_region_line(line),
+            FileSection::Labels => parser.parse_label_line(line),
+        };
+        if let Err(e) = result {
+            self.parser.emit(SctItem::Diagnostic(
+                Diagnostic::new(
+                    line_number,
+                    line,
+                    Some(self.current_section.name()),
+                    Severity::Error,
+                    e,
+                )
+                .with_file(self.file_name.as_deref()),
+            ));
+        }
+    }
+}
+impl<R: BufRead> Iterator for SctItems<R> {
+    type Item = SectorResult<SctItem>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.parser.next_item() {
+                return Some(Ok(item));
+            }
+            let Some((line_number, line)) = self.lines.next() else {
+                if std::mem::replace(&mut self.finished, true) {
+                    return None;
+                }
+                self.parser.finish();
+                continue;
+            };
+            match line {
+                Ok(line) => self.read_line(line_number + 1, line),
+                Err(e) => return Some(Err(e.into())),
+            }
+        }
     }
 }
 
@@ -123,6 +256,29 @@ enum FileSection {
     Regions,
     Labels,
 }
+impl FileSection {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::ColourDefinitions => "#define",
+            Self::Info => "[INFO]",
+            Self::Vor => "[VOR]",
+            Self::Ndb => "[NDB]",
+            Self::Airport => "[AIRPORT]",
+            Self::Runway => "[RUNWAY]",
+            Self::Fixes => "[FIXES]",
+            Self::Geo => "[GEO]",
+            Self::LowAirway => "[LOW AIRWAY]",
+            Self::HighAirway => "[HIGH AIRWAY]",
+            Self::Artcc => "[ARTCC]",
+            Self::ArtccHigh => "[ARTCC HIGH]",
+            Self::ArtccLow => "[ARTCC LOW]",
+            Self::Sid => "[SID]",
+            Self::Star => "[STAR]",
+            Self::Regions => "[REGIONS]",
+            Self::Labels => "[LABELS]",
+        }
+    }
+}
 
 fn parse_file_section(value: &str) -> SectorResult<FileSection> {
     let new_section = match value.to_uppercase().as_str() {
@@ -147,13 +303,30 @@ fn parse_file_section(value: &str) -> SectorResult<FileSection> {
     Ok(new_section)
 }
 
+/// A sector file with a fixed `[INFO]` section, taking up its first ten lines, followed by
+/// `body`. `body` can start with `#define`s.
+#[cfg(any(test, feature = "test-support"))]
+#[doc(hidden)]
+pub fn test_sct(name: &str, body: &str) -> String {
+    format!("[INFO]\n{name}\nEGKK_APP\nEGKK\nN051.08.53.000\nW000.11.25.000\n60\n38\n1\n1\n{body}")
+}
+
+/// Reads [`test_sct`], which is expected to succeed.
+#[cfg(any(test, feature = "test-support"))]
+#[doc(hidden)]
+pub fn test_sector(name: &str, body: &str) -> Sector {
+    SctReader::new(test_sct(name, body).as_bytes())
+        .try_read()
+        .unwrap()
+}
+
 #[test]
 fn test() {
     let file =
         File::open(r#"C:\Users\chpme\AppData\Roaming\EuroScope\UK\Data\Sector\UK_2023_11.sct"#)
             .unwrap();
     let reader = BufReader::new(file);
-    let sct_reader = SctReader::new(reader);
+    let sct_reader = SctReader::new(reader).with_file_name("UK_2023_11.sct");
     let timer = Instant::now();
     match sct_reader.try_read() {
         Ok(sector) => {
@@ -162,12 +335,68 @@ fn test() {
             writeln!(output, "Took {} ms", elapsed.as_millis()).unwrap();
             write!(output, "{:#?}", sector).unwrap();
             writeln!(output).unwrap();
-            for (line_number, line, error) in sector.non_critical_errors {
-                writeln!(output, "{} on line {}:", error, line_number).unwrap();
-                writeln!(output, "{}", line).unwrap();
-                writeln!(output).unwrap();
+            for diagnostic in sector.non_critical_errors {
+                writeln!(output, "{}", diagnostic.render()).unwrap();
             }
         }
         Err(error) => println!("{:#?}", error),
     }
 }
+
+#[test]
+fn test_items() {
+    let sct = "\
+#define COLOR_Red 255
+[INFO]
+Items test
+[FIXES]
+BIG N051.19.49.000 E000.02.46.000
+[SID]
+EGKK BIG                  BIG BIG N051.00.00.000 E000.00.00.000
+                          NOPE NOPE BIG BIG
+[REGIONS]
+REGIONNAME Apron
+COLOR_Red N051.00.00.000 E000.00.00.000
+N051.01.00.000 E000.01.00.000
+";
+    let items = SctReader::new(sct.as_bytes())
+        .items()
+        .collect::<SectorResult<Vec<_>>>()
+        .unwrap();
+    let kinds = items
+        .iter()
+        .map(|item| match item {
+            SctItem::Colour { name, .. } => format!("colour {}", name),
+            SctItem::InfoField { field, value } => format!("{:?} {}", field, value),
+            SctItem::Fix(fix) => format!("fix {}", fix.identifier),
+            SctItem::Group { section, name } => format!("group {} {}", section, name),
+            SctItem::Segment { section, group, .. } => format!("segment {} {}", section, group),
+            SctItem::Region { group, .. } => format!("region {}", group),
+            SctItem::RegionVertex { group, .. } => format!("vertex {}", group),
+            SctItem::Diagnostic(diagnostic) => format!("line {}", diagnostic.line_number),
+            SctItem::CoordinateFormat(format) => format!("{} coordinates", format),
+            other => panic!("unexpected {:?}", other),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![
+            "colour COLOR_Red",
+            "Name Items test",
+            "fix BIG",
+            "group [SID] EGKK BIG",
+            "segment [SID] EGKK BIG",
+            "line 8",
+            "region Apron",
+            "vertex Apron",
+            "vertex Apron",
+            "EuroScope coordinates",
+        ]
+    );
+
+    // Missing most of `[INFO]`, so the items can't make a sector.
+    assert_eq!(
+        SctReader::new(sct.as_bytes()).try_read(),
+        Err(Error::SectorInfoError(SectorInfoField::DefaultCallsign))
+    );
+}
@@ -1,19 +1,24 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt::Display, sync::Arc};
 
 use crate::{
     colour::Colour,
+    coordinate::CoordinateFormat,
+    diagnostic::Diagnostic,
     error::Error,
+    ese::ExtendedSector,
     line::{ColouredLine, LineGroup},
+    merge::Provenance,
     partial::{
         region::{PartialRegion, PartialRegionGroup},
         sector_info::PartialSectorInfo,
         PartialSector,
     },
-    position::{Position, Valid},
+    position::{Position, Valid, VariationModel},
     waypoint::{Airport, Fix, Ndb, Vor},
 };
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sector {
     pub sector_info: SectorInfo,
     pub colours: HashMap<String, Colour>,
@@ -31,8 +36,82 @@ pub struct Sector {
     pub geo_entries: Vec<LineGroup<ColouredLine>>,
     pub regions: Vec<RegionGroup>,
     pub labels: Vec<LabelGroup>,
+    pub extended_sector: Option<ExtendedSector>,
+    /// Where each item came from, once other sectors have been merged in with [`Sector::merge`].
+    pub provenance: Provenance,
 
-    pub non_critical_errors: Vec<(usize, String, Error)>,
+    pub non_critical_errors: Vec<Diagnostic>,
+}
+
+impl Sector {
+    pub fn line_groups(&self, category: LineCategory) -> &Vec<LineGroup<ColouredLine>> {
+        match category {
+            LineCategory::Artcc => &self.artcc_entries,
+            LineCategory::ArtccHigh => &self.artcc_high_entries,
+            LineCategory::ArtccLow => &self.artcc_low_entries,
+            LineCategory::LowAirway => &self.low_airways,
+            LineCategory::HighAirway => &self.high_airways,
+            LineCategory::Sid => &self.sid_entries,
+            LineCategory::Star => &self.star_entries,
+            LineCategory::Geo => &self.geo_entries,
+        }
+    }
+    pub fn line_groups_mut(&mut self, category: LineCategory) -> &mut Vec<LineGroup<ColouredLine>> {
+        match category {
+            LineCategory::Artcc => &mut self.artcc_entries,
+            LineCategory::ArtccHigh => &mut self.artcc_high_entries,
+            LineCategory::ArtccLow => &mut self.artcc_low_entries,
+            LineCategory::LowAirway => &mut self.low_airways,
+            LineCategory::HighAirway => &mut self.high_airways,
+            LineCategory::Sid => &mut self.sid_entries,
+            LineCategory::Star => &mut self.star_entries,
+            LineCategory::Geo => &mut self.geo_entries,
+        }
+    }
+}
+
+/// The sections of a sector file that are made up of named groups of lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineCategory {
+    Artcc,
+    ArtccHigh,
+    ArtccLow,
+    LowAirway,
+    HighAirway,
+    Sid,
+    Star,
+    Geo,
+}
+impl LineCategory {
+    pub const ALL: [LineCategory; 8] = [
+        LineCategory::Artcc,
+        LineCategory::ArtccHigh,
+        LineCategory::ArtccLow,
+        LineCategory::LowAirway,
+        LineCategory::HighAirway,
+        LineCategory::Sid,
+        LineCategory::Star,
+        LineCategory::Geo,
+    ];
+}
+impl Display for LineCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Artcc => "[ARTCC]",
+                Self::ArtccHigh => "[ARTCC HIGH]",
+                Self::ArtccLow => "[ARTCC LOW]",
+                Self::LowAirway => "[LOW AIRWAY]",
+                Self::HighAirway => "[HIGH AIRWAY]",
+                Self::Sid => "[SID]",
+                Self::Star => "[STAR]",
+                Self::Geo => "[GEO]",
+            }
+        )
+    }
 }
 
 impl TryFrom<PartialSector> for Sector {
@@ -61,12 +140,15 @@ impl TryFrom<PartialSector> for Sector {
             geo_entries: value.geo_entries,
             regions,
             labels: value.labels,
-            non_critical_errors: vec![],
+            extended_sector: None,
+            provenance: Provenance::default(),
+            non_critical_errors: value.non_critical_errors,
         })
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RegionGroup {
     pub name: String,
     pub regions: Vec<Region>,
@@ -99,9 +181,12 @@ impl TryFrom<PartialRegionGroup> for RegionGroup {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Region {
     pub colour: Colour,
+    /// The `#define` name the colour was given by, as written where it was defined.
+    pub colour_name: Option<Arc<str>>,
     pub vertices: Vec<Position<Valid>>,
 }
 impl TryFrom<PartialRegion> for Region {
@@ -109,25 +194,31 @@ impl TryFrom<PartialRegion> for Region {
     fn try_from(value: PartialRegion) -> Result<Self, Self::Error> {
         Ok(Region {
             colour: value.colour.ok_or_else(|| Error::InvalidRegion)?,
+            colour_name: value.colour_name,
             vertices: value.vertices,
         })
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LabelGroup {
     pub name: String,
     pub labels: Vec<Label>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Label {
     pub name: String,
     pub position: Position<Valid>,
     pub colour: Colour,
+    /// The `#define` name the colour was given by, as written where it was defined.
+    pub colour_name: Option<Arc<str>>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SectorInfo {
     pub name: String,
     pub default_callsign: String,
@@ -137,21 +228,47 @@ pub struct SectorInfo {
     pub n_mi_per_deg_lon: f32,
     pub magnetic_variation: f32,
     pub sector_scale: f32,
+    /// The notation most of the file's coordinates are written in.
+    pub coordinate_format: CoordinateFormat,
+}
+
+/// The `[INFO]` magnetic variation applies everywhere in the sector.
+impl VariationModel for SectorInfo {
+    fn variation_at(&self, _: Position<Valid>) -> f32 {
+        self.magnetic_variation
+    }
 }
 
 impl TryFrom<PartialSectorInfo> for SectorInfo {
     type Error = Error;
     fn try_from(value: PartialSectorInfo) -> Result<Self, Self::Error> {
-        let name = value.name.ok_or(Error::SectorInfoError)?;
-        let default_callsign = value.default_callsign.ok_or(Error::SectorInfoError)?;
-        let default_airport = value.default_airport.ok_or(Error::SectorInfoError)?;
-        let lat = value.default_centre_pt_lat.ok_or(Error::SectorInfoError)?;
-        let lon = value.default_centre_pt_lon.ok_or(Error::SectorInfoError)?;
+        use SectorInfoField::*;
+        let name = value.name.ok_or(Error::SectorInfoError(Name))?;
+        let default_callsign = value
+            .default_callsign
+            .ok_or(Error::SectorInfoError(DefaultCallsign))?;
+        let default_airport = value
+            .default_airport
+            .ok_or(Error::SectorInfoError(DefaultAirport))?;
+        let lat = value
+            .default_centre_pt_lat
+            .ok_or(Error::SectorInfoError(DefaultCentrePointLat))?;
+        let lon = value
+            .default_centre_pt_lon
+            .ok_or(Error::SectorInfoError(DefaultCentrePointLon))?;
         let default_centre_pt = Position::new(lat, lon).validate()?;
-        let n_mi_per_deg_lat = value.n_mi_per_deg_lat.ok_or(Error::SectorInfoError)?;
-        let n_mi_per_deg_lon = value.n_mi_per_deg_lon.ok_or(Error::SectorInfoError)?;
-        let magnetic_variation = value.magnetic_variation.ok_or(Error::SectorInfoError)?;
-        let sector_scale = value.sector_scale.ok_or(Error::SectorInfoError)?;
+        let n_mi_per_deg_lat = value
+            .n_mi_per_deg_lat
+            .ok_or(Error::SectorInfoError(NMiPerDegLat))?;
+        let n_mi_per_deg_lon = value
+            .n_mi_per_deg_lon
+            .ok_or(Error::SectorInfoError(NMiPerDegLon))?;
+        let magnetic_variation = value
+            .magnetic_variation
+            .ok_or(Error::SectorInfoError(MagneticVariation))?;
+        let sector_scale = value
+            .sector_scale
+            .ok_or(Error::SectorInfoError(SectorScale))?;
 
         Ok(SectorInfo {
             name,
@@ -162,6 +279,99 @@ impl TryFrom<PartialSectorInfo> for SectorInfo {
             n_mi_per_deg_lon,
             magnetic_variation,
             sector_scale,
+            coordinate_format: value
+                .coordinate_format
+                .unwrap_or(CoordinateFormat::EuroScope),
         })
     }
 }
+
+/// The fields of the `[INFO]` section, in the order they appear in the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SectorInfoField {
+    Name,
+    DefaultCallsign,
+    DefaultAirport,
+    DefaultCentrePointLat,
+    DefaultCentrePointLon,
+    NMiPerDegLat,
+    NMiPerDegLon,
+    MagneticVariation,
+    SectorScale,
+}
+impl SectorInfoField {
+    pub fn from_line(line: usize) -> Option<SectorInfoField> {
+        use SectorInfoField::*;
+        [
+            Name,
+            DefaultCallsign,
+            DefaultAirport,
+            DefaultCentrePointLat,
+            DefaultCentrePointLon,
+            NMiPerDegLat,
+            NMiPerDegLon,
+            MagneticVariation,
+            SectorScale,
+        ]
+        .get(line.checked_sub(1)?)
+        .copied()
+    }
+}
+impl Display for SectorInfoField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Name => "sector name (line 1)",
+                Self::DefaultCallsign => "default callsign (line 2)",
+                Self::DefaultAirport => "default airport (line 3)",
+                Self::DefaultCentrePointLat => "default centre point latitude (line 4)",
+                Self::DefaultCentrePointLon => "default centre point longitude (line 5)",
+                Self::NMiPerDegLat => "nautical miles per degree of latitude (line 6)",
+                Self::NMiPerDegLon => "nautical miles per degree of longitude (line 7)",
+                Self::MagneticVariation => "magnetic variation (line 8)",
+                Self::SectorScale => "sector scale (line 9)",
+            }
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde() {
+    use crate::reader::test_sector;
+
+    let sector = test_sector(
+        "Serde test",
+        "\
+#define COLOR_Red 255
+[AIRPORT]
+EGKK 126.830 N051.08.53.000 W000.11.25.000 D
+[RUNWAY]
+08R 26L 077 257 N051.08.48.020 W000.12.57.930 N051.09.21.650 W000.09.31.990 EGKK
+[VOR]
+MAY 117.900 N051.01.03.000 E000.06.59.000
+[FIXES]
+BIG N051.19.49.000 E000.02.46.000
+[GEO]
+Coast N051.00.00.000 E000.00.00.000 N051.01.00.000 E000.01.00.000 COLOR_Red
+[REGIONS]
+REGIONNAME Apron
+COLOR_Red N051.00.00.000 E000.00.00.000
+N051.01.00.000 E000.01.00.000
+N051.01.00.000 E000.00.00.000
+[LABELS]
+\"Gatwick\" N051.08.53.000 W000.11.25.000 COLOR_Red
+",
+    );
+    let bytes = bincode::serialize(&sector).unwrap();
+    let round_tripped: Sector = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(round_tripped, sector);
+
+    // An out of range position can be read as `MaybeValid`, but not as `Valid`.
+    let bytes = bincode::serialize(&Position::new(91.0, 0.0)).unwrap();
+    assert!(bincode::deserialize::<Position>(&bytes).is_ok());
+    assert!(bincode::deserialize::<Position<Valid>>(&bytes).is_err());
+}
@@ -0,0 +1,600 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use crate::{
+    line::Line,
+    position::{Position, Valid},
+    sector::{LineCategory, Sector},
+    waypoint::Waypoint,
+};
+
+/// Maximum number of children per node of the tree.
+const NODE_CAPACITY: usize = 16;
+
+/// A latitude / longitude aligned box. Boxes crossing the antimeridian are not supported.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min_lat: f64,
+    pub min_lon: f64,
+    pub max_lat: f64,
+    pub max_lon: f64,
+}
+impl BoundingBox {
+    /// The smallest box containing both corners, in either order.
+    pub fn new(a: Position<Valid>, b: Position<Valid>) -> BoundingBox {
+        BoundingBox {
+            min_lat: a.lat.min(b.lat),
+            min_lon: a.lon.min(b.lon),
+            max_lat: a.lat.max(b.lat),
+            max_lon: a.lon.max(b.lon),
+        }
+    }
+    pub fn from_point(position: Position<Valid>) -> BoundingBox {
+        BoundingBox::new(position, position)
+    }
+    pub fn from_points<'a>(
+        mut positions: impl Iterator<Item = &'a Position<Valid>>,
+    ) -> Option<BoundingBox> {
+        let first = BoundingBox::from_point(*positions.next()?);
+        Some(positions.fold(first, |bbox, position| {
+            bbox.union(&BoundingBox::from_point(*position))
+        }))
+    }
+    /// A box that contains every point within `radius_n_mi` of `centre`.
+    pub fn around(centre: Position<Valid>, radius_n_mi: f64) -> BoundingBox {
+        let d_lat = radius_n_mi / 60.0;
+        let d_lon = d_lat / centre.lat.to_radians().cos().max(1e-6);
+        BoundingBox {
+            min_lat: (centre.lat - d_lat).max(-90.0),
+            min_lon: (centre.lon - d_lon).max(-180.0),
+            max_lat: (centre.lat + d_lat).min(90.0),
+            max_lon: (centre.lon + d_lon).min(180.0),
+        }
+    }
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        BoundingBox {
+            min_lat: self.min_lat.min(other.min_lat),
+            min_lon: self.min_lon.min(other.min_lon),
+            max_lat: self.max_lat.max(other.max_lat),
+            max_lon: self.max_lon.max(other.max_lon),
+        }
+    }
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.min_lat <= other.max_lat
+            && other.min_lat <= self.max_lat
+            && self.min_lon <= other.max_lon
+            && other.min_lon <= self.max_lon
+    }
+    pub fn contains(&self, position: Position<Valid>) -> bool {
+        (self.min_lat..=self.max_lat).contains(&position.lat)
+            && (self.min_lon..=self.max_lon).contains(&position.lon)
+    }
+    fn centre(&self) -> (f64, f64) {
+        (
+            (self.min_lat + self.max_lat) / 2.0,
+            (self.min_lon + self.max_lon) / 2.0,
+        )
+    }
+    /// Great circle distance in nautical miles from a point to the nearest point of the box, or
+    /// zero if the point is inside it. Never more than the distance to anything in the box, so it
+    /// can bound the nearest neighbour search.
+    fn distance_n_mi(&self, position: Position<Valid>) -> f64 {
+        if self.contains(position) {
+            return 0.0;
+        }
+        let at = |lat: f64, lon: f64| {
+            let point = Position::new(lat, lon)
+                .validate()
+                .expect("inside a valid box");
+            position.distance_n_mi(&point)
+        };
+        // Distance along a parallel grows with the difference in longitude, so the nearest point
+        // of each parallel edge is at the clamped longitude. Great circles bend towards the pole,
+        // so the nearest point of each meridian edge is found on the great circle, then clamped.
+        let lon = position.lon.clamp(self.min_lon, self.max_lon);
+        let parallels = [self.min_lat, self.max_lat].map(|lat| at(lat, lon));
+        let meridians = [self.min_lon, self.max_lon].map(|lon| {
+            let (lat, d_lon) = (position.lat.to_radians(), (lon - position.lon).to_radians());
+            let nearest = lat.sin().atan2(lat.cos() * d_lon.cos()).to_degrees();
+            at(nearest.clamp(self.min_lat, self.max_lat), lon)
+        });
+        parallels
+            .into_iter()
+            .chain(meridians)
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+/// Something in a [`Sector`] that has been added to a [`SpatialIndex`]. Indices refer to the
+/// corresponding `Vec`s in the sector the index was built from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SectorItem {
+    Airport(usize),
+    Vor(usize),
+    Ndb(usize),
+    Fix(usize),
+    Segment {
+        category: LineCategory,
+        group: usize,
+        line: usize,
+    },
+    Region {
+        group: usize,
+        region: usize,
+    },
+    Label {
+        group: usize,
+        label: usize,
+    },
+}
+impl SectorItem {
+    pub fn is_waypoint(&self) -> bool {
+        matches!(
+            self,
+            Self::Airport(_) | Self::Vor(_) | Self::Ndb(_) | Self::Fix(_)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Geometry {
+    Point(Position<Valid>),
+    Segment(Position<Valid>, Position<Valid>),
+    Area,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    bbox: BoundingBox,
+    geometry: Geometry,
+    item: SectorItem,
+}
+impl Entry {
+    fn distance_n_mi(&self, position: Position<Valid>) -> f64 {
+        match self.geometry {
+            Geometry::Point(point) => position.distance_n_mi(&point),
+            Geometry::Segment(start, end) => segment_distance_n_mi(position, start, end),
+            Geometry::Area => self.bbox.distance_n_mi(position),
+        }
+    }
+    fn intersects(&self, bbox: &BoundingBox) -> bool {
+        if !self.bbox.intersects(bbox) {
+            return false;
+        }
+        match self.geometry {
+            Geometry::Segment(start, end) => segment_intersects_box(start, end, bbox),
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Node {
+    Leaf(Vec<Entry>),
+    Branch(Vec<(BoundingBox, usize)>),
+}
+
+/// An R-tree over the waypoints, line segments, regions and labels of a [`Sector`], bulk loaded
+/// using sort-tile-recursive packing.
+///
+/// Distances are in nautical miles. Segment distances and region distances use a flat-earth
+/// approximation around the query point, which is accurate to well within a mile at radar ranges.
+#[derive(Debug)]
+pub struct SpatialIndex {
+    nodes: Vec<Node>,
+    root: Option<usize>,
+    len: usize,
+}
+impl SpatialIndex {
+    pub fn new(sector: &Sector) -> SpatialIndex {
+        let mut entries = vec![];
+
+        fn add_waypoints<W: Waypoint>(
+            entries: &mut Vec<Entry>,
+            waypoints: &[W],
+            item: fn(usize) -> SectorItem,
+        ) {
+            entries.extend(waypoints.iter().enumerate().map(|(i, waypoint)| Entry {
+                bbox: BoundingBox::from_point(waypoint.position()),
+                geometry: Geometry::Point(waypoint.position()),
+                item: item(i),
+            }));
+        }
+        add_waypoints(&mut entries, &sector.airports, SectorItem::Airport);
+        add_waypoints(&mut entries, &sector.vors, SectorItem::Vor);
+        add_waypoints(&mut entries, &sector.ndbs, SectorItem::Ndb);
+        add_waypoints(&mut entries, &sector.fixes, SectorItem::Fix);
+
+        for category in LineCategory::ALL {
+            for (group_index, group) in sector.line_groups(category).iter().enumerate() {
+                for (line_index, line) in group.lines.iter().enumerate() {
+                    entries.push(Entry {
+                        bbox: BoundingBox::new(line.start(), line.end()),
+                        geometry: Geometry::Segment(line.start(), line.end()),
+                        item: SectorItem::Segment {
+                            category,
+                            group: group_index,
+                            line: line_index,
+                        },
+                    });
+                }
+            }
+        }
+
+        for (group_index, group) in sector.regions.iter().enumerate() {
+            for (region_index, region) in group.regions.iter().enumerate() {
+                if let Some(bbox) = BoundingBox::from_points(region.vertices.iter()) {
+                    entries.push(Entry {
+                        bbox,
+                        geometry: Geometry::Area,
+                        item: SectorItem::Region {
+                            group: group_index,
+                            region: region_index,
+                        },
+                    });
+                }
+            }
+        }
+
+        for (group_index, group) in sector.labels.iter().enumerate() {
+            for (label_index, label) in group.labels.iter().enumerate() {
+                entries.push(Entry {
+                    bbox: BoundingBox::from_point(label.position),
+                    geometry: Geometry::Point(label.position),
+                    item: SectorItem::Label {
+                        group: group_index,
+                        label: label_index,
+                    },
+                });
+            }
+        }
+
+        SpatialIndex::build(entries)
+    }
+
+    fn build(entries: Vec<Entry>) -> SpatialIndex {
+        let len = entries.len();
+        let mut nodes = vec![];
+        if entries.is_empty() {
+            return SpatialIndex {
+                nodes,
+                root: None,
+                len,
+            };
+        }
+
+        let mut level = pack(entries, |entry| entry.bbox)
+            .into_iter()
+            .map(|children| {
+                let bbox = union_all(children.iter().map(|entry| entry.bbox));
+                nodes.push(Node::Leaf(children));
+                (bbox, nodes.len() - 1)
+            })
+            .collect::<Vec<_>>();
+        while level.len() > 1 {
+            level = pack(level, |(bbox, _)| *bbox)
+                .into_iter()
+                .map(|children| {
+                    let bbox = union_all(children.iter().map(|(bbox, _)| *bbox));
+                    nodes.push(Node::Branch(children));
+                    (bbox, nodes.len() - 1)
+                })
+                .collect();
+        }
+        SpatialIndex {
+            root: level.first().map(|(_, index)| *index),
+            nodes,
+            len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Everything that touches `bbox`. Segments are tested exactly rather than by their bounding
+    /// box, so a diagonal segment passing near a corner is not returned.
+    pub fn query_bbox(&self, bbox: &BoundingBox) -> Vec<SectorItem> {
+        let mut results = vec![];
+        let mut stack = self.root.into_iter().collect::<Vec<_>>();
+        while let Some(index) = stack.pop() {
+            match &self.nodes[index] {
+                Node::Leaf(entries) => results.extend(
+                    entries
+                        .iter()
+                        .filter(|entry| entry.intersects(bbox))
+                        .map(|entry| entry.item),
+                ),
+                Node::Branch(children) => stack.extend(
+                    children
+                        .iter()
+                        .filter(|(child_bbox, _)| child_bbox.intersects(bbox))
+                        .map(|(_, child)| *child),
+                ),
+            }
+        }
+        results
+    }
+
+    /// Everything within `radius_n_mi` of `centre`.
+    pub fn query_radius(&self, centre: Position<Valid>, radius_n_mi: f64) -> Vec<SectorItem> {
+        let search_box = BoundingBox::around(centre, radius_n_mi);
+        let mut results = vec![];
+        let mut stack = self.root.into_iter().collect::<Vec<_>>();
+        while let Some(index) = stack.pop() {
+            match &self.nodes[index] {
+                Node::Leaf(entries) => results.extend(
+                    entries
+                        .iter()
+                        .filter(|entry| {
+                            entry.bbox.intersects(&search_box)
+                                && entry.distance_n_mi(centre) <= radius_n_mi
+                        })
+                        .map(|entry| entry.item),
+                ),
+                Node::Branch(children) => stack.extend(
+                    children
+                        .iter()
+                        .filter(|(child_bbox, _)| child_bbox.intersects(&search_box))
+                        .map(|(_, child)| *child),
+                ),
+            }
+        }
+        results
+    }
+
+    /// The `k` items nearest to `position`, closest first, with their distances in nautical miles.
+    /// Regions are measured to their bounding box rather than their outline, so a region counts as
+    /// at zero distance anywhere inside its bounding box.
+    pub fn nearest(&self, position: Position<Valid>, k: usize) -> Vec<(SectorItem, f64)> {
+        self.nearest_matching(position, k, |_| true)
+    }
+
+    /// As [`SpatialIndex::nearest`], but only considering items for which `filter` returns true.
+    pub fn nearest_matching(
+        &self,
+        position: Position<Valid>,
+        k: usize,
+        filter: impl Fn(&SectorItem) -> bool,
+    ) -> Vec<(SectorItem, f64)> {
+        let mut results = vec![];
+        let mut queue = BinaryHeap::new();
+        if let Some(root) = self.root {
+            queue.push(Candidate {
+                distance: 0.0,
+                kind: CandidateKind::Node(root),
+            });
+        }
+        while let Some(Candidate { distance, kind }) = queue.pop() {
+            if results.len() >= k {
+                break;
+            }
+            match kind {
+                CandidateKind::Item(item) => results.push((item, distance)),
+                CandidateKind::Node(index) => match &self.nodes[index] {
+                    Node::Leaf(entries) => {
+                        for entry in entries.iter().filter(|entry| filter(&entry.item)) {
+                            queue.push(Candidate {
+                                distance: entry.distance_n_mi(position),
+                                kind: CandidateKind::Item(entry.item),
+                            });
+                        }
+                    }
+                    Node::Branch(children) => {
+                        for (bbox, child) in children {
+                            queue.push(Candidate {
+                                distance: bbox.distance_n_mi(position),
+                                kind: CandidateKind::Node(*child),
+                            });
+                        }
+                    }
+                },
+            }
+        }
+        results
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CandidateKind {
+    Node(usize),
+    Item(SectorItem),
+}
+
+/// An entry in the nearest neighbour queue. Ordered so that the `BinaryHeap` pops the closest
+/// first.
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    distance: f64,
+    kind: CandidateKind,
+}
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for Candidate {}
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.distance.total_cmp(&self.distance)
+    }
+}
+
+/// Sort-tile-recursive packing: sorts by longitude into vertical slices, then each slice by
+/// latitude, and chunks into groups of `NODE_CAPACITY`.
+fn pack<T>(mut items: Vec<T>, bbox: impl Fn(&T) -> BoundingBox) -> Vec<Vec<T>> {
+    let node_count = items.len().div_ceil(NODE_CAPACITY);
+    let slice_count = (node_count as f64).sqrt().ceil() as usize;
+    let slice_size = slice_count * NODE_CAPACITY;
+
+    items.sort_by(|a, b| bbox(a).centre().1.total_cmp(&bbox(b).centre().1));
+    let mut groups = vec![];
+    while !items.is_empty() {
+        let rest = items.split_off(slice_size.min(items.len()));
+        let mut slice = std::mem::replace(&mut items, rest);
+        slice.sort_by(|a, b| bbox(a).centre().0.total_cmp(&bbox(b).centre().0));
+        while !slice.is_empty() {
+            let rest = slice.split_off(NODE_CAPACITY.min(slice.len()));
+            groups.push(std::mem::replace(&mut slice, rest));
+        }
+    }
+    groups
+}
+
+fn union_all(mut boxes: impl Iterator<Item = BoundingBox>) -> BoundingBox {
+    let first = boxes.next().expect("nodes are never empty");
+    boxes.fold(first, |a, b| a.union(&b))
+}
+
+/// Projects a point onto a flat plane centred on `origin`, in nautical miles.
+fn to_local(origin: Position<Valid>, lat: f64, lon: f64) -> (f64, f64) {
+    let x = (lon - origin.lon) * 60.0 * origin.lat.to_radians().cos();
+    let y = (lat - origin.lat) * 60.0;
+    (x, y)
+}
+
+/// Great circle distance to the point of the segment nearest `position` on a flat projection.
+/// That point is on the straight line between the ends, so it is never nearer than the segment's
+/// bounding box.
+fn segment_distance_n_mi(
+    position: Position<Valid>,
+    start: Position<Valid>,
+    end: Position<Valid>,
+) -> f64 {
+    let (ax, ay) = to_local(position, start.lat, start.lon);
+    let (bx, by) = to_local(position, end.lat, end.lon);
+    let (dx, dy) = (bx - ax, by - ay);
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq == 0.0 {
+        0.0
+    } else {
+        (-(ax * dx + ay * dy) / length_sq).clamp(0.0, 1.0)
+    };
+    let nearest = Position::new(
+        start.lat + t * (end.lat - start.lat),
+        start.lon + t * (end.lon - start.lon),
+    )
+    .validate()
+    .expect("between two valid positions");
+    position.distance_n_mi(&nearest)
+}
+
+/// Liang-Barsky clipping of the segment against the box, treating lat / lon as a flat plane.
+fn segment_intersects_box(
+    start: Position<Valid>,
+    end: Position<Valid>,
+    bbox: &BoundingBox,
+) -> bool {
+    let (dx, dy) = (end.lon - start.lon, end.lat - start.lat);
+    let mut t0: f64 = 0.0;
+    let mut t1: f64 = 1.0;
+    for (p, q) in [
+        (-dx, start.lon - bbox.min_lon),
+        (dx, bbox.max_lon - start.lon),
+        (-dy, start.lat - bbox.min_lat),
+        (dy, bbox.max_lat - start.lat),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return false;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+            if t0 > t1 {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+#[test]
+fn test_spatial_queries() {
+    use crate::reader::SctReader;
+
+    let sct = "\
+[INFO]
+Spatial test
+EGTT_CTR
+EGLL
+N051.28.39.000
+W000.27.41.000
+60
+38
+1
+1
+[AIRPORT]
+EGLL 118.500 N051.28.39.000 W000.27.41.000 D
+[VOR]
+BNN 113.750 N051.43.34.300 W000.32.59.500
+LAM 115.600 N051.38.46.000 E000.09.07.000
+[FIXES]
+BPK N051.44.58.000 W000.06.25.000
+KONAN N051.07.58.000 E002.00.00.000
+[GEO]
+Coast N051.00.00.000 W000.30.00.000 N052.00.00.000 W000.30.00.000
+";
+    let sector = SctReader::new(sct.as_bytes()).try_read().unwrap();
+    let index = SpatialIndex::new(&sector);
+    assert_eq!(index.len(), 6);
+
+    let egll = sector.airports[0].position;
+    let mut near_egll = index
+        .query_radius(egll, 22.0)
+        .into_iter()
+        .filter(SectorItem::is_waypoint)
+        .collect::<Vec<_>>();
+    near_egll.sort_by_key(|item| format!("{:?}", item));
+    assert_eq!(
+        near_egll,
+        vec![
+            SectorItem::Airport(0),
+            SectorItem::Fix(0),
+            SectorItem::Vor(0)
+        ]
+    );
+
+    let nearest = index.nearest_matching(egll, 2, SectorItem::is_waypoint);
+    assert_eq!(nearest[0], (SectorItem::Airport(0), 0.0));
+    assert_eq!(nearest[1].0, SectorItem::Vor(0));
+
+    let viewport = BoundingBox::new(
+        Position::new(51.4, -0.6).validate().unwrap(),
+        Position::new(51.6, -0.4).validate().unwrap(),
+    );
+    let segment = SectorItem::Segment {
+        category: LineCategory::Geo,
+        group: 0,
+        line: 0,
+    };
+    assert!(index.query_bbox(&viewport).contains(&segment));
+
+    // The box bound must never be more than the distance to any point in the box, even where
+    // the shortest route bends towards the pole.
+    let from = Position::new(50.0, 0.0).validate().unwrap();
+    let wide = BoundingBox::new(
+        Position::new(40.0, 90.0).validate().unwrap(),
+        Position::new(60.0, 100.0).validate().unwrap(),
+    );
+    let bound = wide.distance_n_mi(from);
+    for lat in (40..=60).step_by(5) {
+        for lon in (90..=100).step_by(5) {
+            let point = Position::new(lat as f64, lon as f64).validate().unwrap();
+            assert!(bound <= from.distance_n_mi(&point) + 1e-9);
+        }
+    }
+    let corner = Position::new(60.0, 90.0).validate().unwrap();
+    assert!((bound - from.distance_n_mi(&corner)).abs() < 1e-6);
+}
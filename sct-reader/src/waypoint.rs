@@ -1,7 +1,9 @@
 use std::fmt::Display;
 
 use crate::{
-    position::{Heading, Position, Valid},
+    position::{
+        Heading, MagneticHeading, Position, TrueHeading, Valid, VariationModel, METRES_PER_FOOT,
+    },
     AirspaceClass,
 };
 
@@ -10,7 +12,12 @@ pub trait Waypoint {
     fn position(&self) -> Position<Valid>;
 }
 
-#[derive(Debug, Clone)]
+/// Identifiers are reused around the world, so two items with the same identifier are only taken
+/// to be the same item within this distance.
+pub(crate) const MATCH_RADIUS_N_MI: f64 = 100.0;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Fix {
     pub identifier: String,
     pub position: Position<Valid>,
@@ -24,7 +31,8 @@ impl Waypoint for Fix {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vor {
     pub identifier: String,
     pub position: Position<Valid>,
@@ -44,7 +52,8 @@ impl Vor {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Ndb {
     pub identifier: String,
     pub position: Position<Valid>,
@@ -64,7 +73,8 @@ impl Ndb {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Airport {
     pub identifier: String,
     pub position: Position<Valid>,
@@ -87,21 +97,50 @@ impl Airport {
     pub fn airspace_class(&self) -> AirspaceClass {
         self.airspace_class
     }
+    /// The runway end with the given designator, e.g. "27L", whichever end of its strip it was
+    /// written as. The leading zero is optional, so "9" finds "09".
+    pub fn runway(&self, identifier: &str) -> Option<&RunwayEnd> {
+        let wanted = identifier.trim().trim_start_matches('0');
+        self.runway_ends().find(|end| {
+            end.identifier()
+                .trim_start_matches('0')
+                .eq_ignore_ascii_case(wanted)
+        })
+    }
+    /// Both ends of every runway.
+    pub fn runway_ends(&self) -> impl Iterator<Item = &RunwayEnd> {
+        self.runways
+            .iter()
+            .flat_map(|strip| [&strip.end_a, &strip.end_b])
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RunwayStrip {
     pub end_a: RunwayEnd,
     pub end_b: RunwayEnd,
 }
+impl RunwayStrip {
+    /// Distance between the two thresholds.
+    pub fn length_m(&self) -> f64 {
+        self.end_a
+            .td_threshold_pos
+            .distance_m(&self.end_b.td_threshold_pos)
+    }
+    pub fn length_ft(&self) -> f64 {
+        self.length_m() / METRES_PER_FOOT
+    }
+}
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RunwayEnd {
     pub number: u8,
     pub td_threshold_pos: Position<Valid>,
     pub se_threshold_pos: Position<Valid>,
     pub modifier: RunwayModifier,
-    pub magnetic_hdg: Heading,
+    pub magnetic_hdg: MagneticHeading,
 }
 impl RunwayEnd {
     pub fn identifier(&self) -> String {
@@ -121,6 +160,22 @@ impl RunwayEnd {
             magnetic_hdg,
         }
     }
+    /// The true bearing from this end's threshold along the runway.
+    pub fn true_bearing(&self) -> TrueHeading {
+        Heading::wrapping(
+            self.td_threshold_pos
+                .initial_bearing(&self.se_threshold_pos) as f32,
+        )
+    }
+    /// How many degrees the declared magnetic heading is clockwise of the one worked out from the
+    /// thresholds, from -180 to 180. Anything more than a degree or two means the heading,
+    /// the thresholds or the variation is wrong.
+    pub fn magnetic_heading_error(&self, variation: &impl VariationModel) -> f32 {
+        let computed = self
+            .true_bearing()
+            .to_magnetic(variation, self.td_threshold_pos);
+        (self.magnetic_hdg.value() - computed.value() + 540.0).rem_euclid(360.0) - 180.0
+    }
     fn reciprocal_number(&self) -> u8 {
         if self.number > 18 {
             self.number - 18
@@ -131,6 +186,7 @@ impl RunwayEnd {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RunwayModifier {
     Left,
     Right,
@@ -165,3 +221,34 @@ impl Display for RunwayModifier {
         )
     }
 }
+
+#[test]
+fn test_runway_geometry() {
+    use crate::reader::test_sector;
+
+    let sector = test_sector(
+        "Runway test",
+        "\
+[AIRPORT]
+EGKK 124.225 N051.08.53.000 W000.11.25.000 D
+[RUNWAY]
+08R 26L 077 257 N051.08.48.020 W000.12.57.930 N051.09.21.650 W000.09.31.990 EGKK
+",
+    );
+    let airport = &sector.airports[0];
+    let strip = &airport.runways[0];
+    assert!((strip.length_m() - 4_123.0).abs() < 1.0);
+    assert!((strip.length_ft() - strip.length_m() / 0.3048).abs() < 1e-9);
+
+    let landing_26l = airport.runway("26L").unwrap();
+    assert_eq!(landing_26l, &strip.end_b);
+    assert_eq!(airport.runway("8r"), Some(&strip.end_a));
+    assert_eq!(airport.runway("27L"), None);
+
+    let bearing = strip.end_a.true_bearing().value();
+    assert!((bearing - 75.4).abs() < 0.1);
+    assert!((landing_26l.true_bearing().value() - bearing - 180.0).abs() < 0.1);
+    let error = strip.end_a.magnetic_heading_error(&sector.sector_info);
+    assert!(error.abs() < 1.0);
+    assert!((strip.end_a.magnetic_heading_error(&-10.0) - error - 11.0).abs() < 1e-3);
+}
@@ -0,0 +1,385 @@
+use std::{collections::HashMap, io::Write, sync::Arc};
+
+use crate::{
+    colour::Colour,
+    coordinate::{self, Axis, CoordinateFormat},
+    line::{ColouredLine, LineGroup},
+    position::{Position, Valid},
+    sector::{LineCategory, Sector},
+    waypoint::{Airport, Waypoint},
+    SectorResult,
+};
+
+/// Width of the name column in the `[SID]` and `[STAR]` sections.
+const SID_STAR_NAME_WIDTH: usize = 26;
+
+/// Writes a [`Sector`] out as a `.sct` file.
+///
+/// Reading the output back with [`SctReader`](crate::reader::SctReader) gives a `Sector` equal to
+/// the one that was written, as long as the original was read without any non-critical errors.
+/// Positions are written in the sector's `coordinate_format`, so a file mixing decimal degrees
+/// with the dotted notations can come back with its decimal positions rounded to the nearest
+/// thousandth of a second. Navaids referenced by name in line sections are written as
+/// coordinates, and the extended sector (if attached) is not written. Items use the colour name
+/// they were read with, as long as it is still defined as the same colour; alpha channels are
+/// dropped.
+pub struct SctWriter<W: Write> {
+    destination: W,
+    /// The `#define`s written, by lower case name.
+    colours: HashMap<String, Colour>,
+    /// The notation positions are written in.
+    format: CoordinateFormat,
+}
+impl<W: Write> SctWriter<W> {
+    pub fn new(destination: W) -> Self {
+        Self {
+            destination,
+            colours: HashMap::new(),
+            format: CoordinateFormat::EuroScope,
+        }
+    }
+
+    pub fn write(mut self, sector: &Sector) -> SectorResult<()> {
+        self.format = sector.sector_info.coordinate_format;
+        self.write_colour_definitions(sector)?;
+        self.write_sector_info(sector)?;
+
+        writeln!(self.destination, "\n[VOR]")?;
+        for vor in &sector.vors {
+            self.write_beacon(vor, &vor.frequency)?;
+        }
+        writeln!(self.destination, "\n[NDB]")?;
+        for ndb in &sector.ndbs {
+            self.write_beacon(ndb, &ndb.frequency)?;
+        }
+        writeln!(self.destination, "\n[AIRPORT]")?;
+        for airport in &sector.airports {
+            writeln!(
+                self.destination,
+                "{} {} {} {}",
+                airport.identifier,
+                airport.tower_frequency,
+                self.position(&airport.position),
+                airport.airspace_class
+            )?;
+        }
+        writeln!(self.destination, "\n[RUNWAY]")?;
+        for airport in &sector.airports {
+            self.write_runways(airport)?;
+        }
+        writeln!(self.destination, "\n[FIXES]")?;
+        for fix in &sector.fixes {
+            writeln!(
+                self.destination,
+                "{} {}",
+                fix.identifier,
+                self.position(&fix.position)
+            )?;
+        }
+
+        self.write_line_groups("[ARTCC]", &sector.artcc_entries)?;
+        self.write_line_groups("[ARTCC HIGH]", &sector.artcc_high_entries)?;
+        self.write_line_groups("[ARTCC LOW]", &sector.artcc_low_entries)?;
+        self.write_sid_star_groups("[SID]", &sector.sid_entries)?;
+        self.write_sid_star_groups("[STAR]", &sector.star_entries)?;
+        self.write_line_groups("[LOW AIRWAY]", &sector.low_airways)?;
+        self.write_line_groups("[HIGH AIRWAY]", &sector.high_airways)?;
+        self.write_line_groups("[GEO]", &sector.geo_entries)?;
+
+        self.write_regions(sector)?;
+        self.write_labels(sector)?;
+        self.destination.flush()?;
+        Ok(())
+    }
+
+    fn write_colour_definitions(&mut self, sector: &Sector) -> SectorResult<()> {
+        // `Sector::colours` only has lower case names, so the spelling comes from the items.
+        let lines = LineCategory::ALL
+            .into_iter()
+            .flat_map(|category| sector.line_groups(category))
+            .flat_map(|group| group.lines.iter().map(|line| &line.colour_name));
+        let regions = sector
+            .regions
+            .iter()
+            .flat_map(|group| group.regions.iter().map(|region| &region.colour_name));
+        let labels = sector
+            .labels
+            .iter()
+            .flat_map(|group| group.labels.iter().map(|label| &label.colour_name));
+        let spellings = lines
+            .chain(regions)
+            .chain(labels)
+            .flatten()
+            .map(|name| (name.to_lowercase(), name))
+            .collect::<HashMap<_, _>>();
+
+        let mut colours = sector.colours.iter().collect::<Vec<_>>();
+        colours.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, colour) in colours {
+            let spelling = spellings.get(name).map_or(name.as_str(), |name| name);
+            writeln!(
+                self.destination,
+                "#define {} {}",
+                spelling,
+                u32::from(*colour)
+            )?;
+            self.colours.insert(name.clone(), *colour);
+        }
+        Ok(())
+    }
+
+    fn write_sector_info(&mut self, sector: &Sector) -> SectorResult<()> {
+        let info = &sector.sector_info;
+        let centre = &info.default_centre_pt;
+        let lat = coordinate::write_sector_field(centre.lat, Axis::Lat, self.format);
+        let lon = coordinate::write_sector_field(centre.lon, Axis::Lon, self.format);
+        writeln!(self.destination, "\n[INFO]")?;
+        writeln!(self.destination, "{}", info.name)?;
+        writeln!(self.destination, "{}", info.default_callsign)?;
+        writeln!(self.destination, "{}", info.default_airport)?;
+        writeln!(self.destination, "{}", lat)?;
+        writeln!(self.destination, "{}", lon)?;
+        writeln!(self.destination, "{}", info.n_mi_per_deg_lat)?;
+        writeln!(self.destination, "{}", info.n_mi_per_deg_lon)?;
+        writeln!(self.destination, "{}", info.magnetic_variation)?;
+        writeln!(self.destination, "{}", info.sector_scale)?;
+        Ok(())
+    }
+
+    fn write_beacon<B: Waypoint>(&mut self, beacon: &B, frequency: &str) -> SectorResult<()> {
+        writeln!(
+            self.destination,
+            "{} {} {}",
+            beacon.identifier(),
+            frequency,
+            self.position(&beacon.position())
+        )?;
+        Ok(())
+    }
+
+    fn write_runways(&mut self, airport: &Airport) -> SectorResult<()> {
+        for runway in &airport.runways {
+            writeln!(
+                self.destination,
+                "{} {} {} {} {} {} {}",
+                runway.end_a.identifier(),
+                runway.end_b.identifier(),
+                runway.end_a.magnetic_hdg,
+                runway.end_b.magnetic_hdg,
+                self.position(&runway.end_a.td_threshold_pos),
+                self.position(&runway.end_b.td_threshold_pos),
+                airport.identifier
+            )?;
+        }
+        Ok(())
+    }
+
+    fn write_line_groups(
+        &mut self,
+        section: &str,
+        groups: &[LineGroup<ColouredLine>],
+    ) -> SectorResult<()> {
+        writeln!(self.destination, "\n{}", section)?;
+        for group in groups {
+            for line in &group.lines {
+                writeln!(
+                    self.destination,
+                    "{} {}{}",
+                    group.name,
+                    self.line(line),
+                    self.colour_suffix(line.colour, &line.colour_name)
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_sid_star_groups(
+        &mut self,
+        section: &str,
+        groups: &[LineGroup<ColouredLine>],
+    ) -> SectorResult<()> {
+        writeln!(self.destination, "\n{}", section)?;
+        for group in groups {
+            let mut name = group.name.as_str();
+            for line in &group.lines {
+                writeln!(
+                    self.destination,
+                    "{:<width$} {}{}",
+                    name,
+                    self.line(line),
+                    self.colour_suffix(line.colour, &line.colour_name),
+                    width = SID_STAR_NAME_WIDTH - 1
+                )?;
+                name = "";
+            }
+        }
+        Ok(())
+    }
+
+    fn write_regions(&mut self, sector: &Sector) -> SectorResult<()> {
+        writeln!(self.destination, "\n[REGIONS]")?;
+        for group in &sector.regions {
+            writeln!(self.destination, "REGIONNAME {}", group.name)?;
+            for region in &group.regions {
+                let mut vertices = region.vertices.iter();
+                let Some(first) = vertices.next() else {
+                    continue;
+                };
+                writeln!(
+                    self.destination,
+                    "{:<26} {}",
+                    self.colour(region.colour, &region.colour_name),
+                    self.position(first)
+                )?;
+                for vertex in vertices {
+                    writeln!(self.destination, "{:<26} {}", "", self.position(vertex))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_labels(&mut self, sector: &Sector) -> SectorResult<()> {
+        writeln!(self.destination, "\n[LABELS]")?;
+        for group in &sector.labels {
+            for label in &group.labels {
+                writeln!(
+                    self.destination,
+                    "\"{}\" {} {}",
+                    label.name,
+                    self.position(&label.position),
+                    self.colour(label.colour, &label.colour_name)
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn line(&self, line: &ColouredLine) -> String {
+        format!(
+            "{} {}",
+            self.position(&line.line.start),
+            self.position(&line.line.end)
+        )
+    }
+
+    fn position(&self, position: &Position<Valid>) -> String {
+        format!(
+            "{} {}",
+            coordinate::write_sector_field(position.lat, Axis::Lat, self.format),
+            coordinate::write_sector_field(position.lon, Axis::Lon, self.format)
+        )
+    }
+
+    fn colour(&self, colour: Colour, name: &Option<Arc<str>>) -> String {
+        match name {
+            Some(name) if self.colours.get(&name.to_lowercase()) == Some(&colour) => {
+                name.to_string()
+            }
+            _ => u32::from(colour).to_string(),
+        }
+    }
+
+    fn colour_suffix(&self, colour: Option<Colour>, name: &Option<Arc<str>>) -> String {
+        colour
+            .map(|colour| format!(" {}", self.colour(colour, name)))
+            .unwrap_or_default()
+    }
+}
+
+#[test]
+fn test_round_trip() {
+    use crate::reader::SctReader;
+
+    let sct = "\
+#define COLOR_AoRcenter1 7895160
+#define coast 32768
+
+[INFO]
+Round trip test
+EGTT_CTR
+EGLL
+N051.28.39.000
+W000.27.41.000
+60
+38.5
+-1.5
+1
+
+[VOR]
+BNN 113.750 N051.43.34.300 W000.32.59.500
+[NDB]
+WOD 352.000 N051.27.10.670 W000.52.42.140
+[AIRPORT]
+EGLL 118.500 N051.28.39.000 W000.27.41.000 D
+[RUNWAY]
+27R 09L 271 091 N051.27.54.670 W000.26.00.710 N051.28.39.980 W000.29.05.930 EGLL
+[FIXES]
+BPK N051.44.58.000 W000.06.25.000
+[ARTCC]
+AoR London ACC N051.00.00.000 W001.00.00.000 N052.00.00.000 W001.00.00.000 COLOR_AoRcenter1
+AoR London ACC N052.00.00.000 W001.00.00.000 N052.00.00.000 E000.00.59.999
+[SID]
+EGLL 27R BPK7F            EGLL EGLL BPK BPK
+                          BPK BPK BNN BNN 255
+[LOW AIRWAY]
+L9 BPK BPK BNN BNN
+[GEO]
+N051.00.00.000 W000.00.00.000 N051.00.01.000 W000.00.00.001 coast
+[REGIONS]
+REGIONNAME Heathrow
+coast N051.27.54.670 W000.26.00.710
+      N051.28.39.980 W000.29.05.930
+      N051.28.00.000 W000.28.00.000
+[LABELS]
+\"Heathrow\" N051.28.39.000 W000.27.41.000 coast
+";
+    let original = SctReader::new(sct.as_bytes()).try_read().unwrap();
+    assert!(original.non_critical_errors.is_empty());
+
+    let mut written = vec![];
+    SctWriter::new(&mut written).write(&original).unwrap();
+    let round_tripped = SctReader::new(written.as_slice()).try_read().unwrap();
+
+    assert_eq!(original, round_tripped);
+
+    let decimal = "\
+[INFO]
+Decimal round trip test
+EGTT_CTR
+EGLL
+51.4775
+-0.461389
+60
+38.5
+-1.5
+1
+
+[VOR]
+BNN 113.750 51.726194 -0.549861
+[AIRPORT]
+EGLL 118.500 51.4775 -0.461389 D
+[FIXES]
+BPK 51.749444 -0.106944
+[GEO]
+51.0 -0.1 51.000277777777 -0.100000277777 32768
+[REGIONS]
+REGIONNAME Heathrow
+12345 51.465186 -0.433531
+      51.477772 -0.484981
+      51.466667 -0.466667
+";
+    let original = SctReader::new(decimal.as_bytes()).try_read().unwrap();
+    assert!(original.non_critical_errors.is_empty());
+    assert_eq!(
+        original.sector_info.coordinate_format,
+        CoordinateFormat::Decimal
+    );
+
+    let mut written = vec![];
+    SctWriter::new(&mut written).write(&original).unwrap();
+    let round_tripped = SctReader::new(written.as_slice()).try_read().unwrap();
+
+    assert_eq!(original, round_tripped);
+}
@@ -0,0 +1,244 @@
+//! Just enough XML to read KML overlays without any dependencies. Namespaces, DTDs and processing
+//! instructions are skipped over, and namespace prefixes are dropped from element names.
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct Element {
+    pub(crate) name: String,
+    pub(crate) attributes: Vec<(String, String)>,
+    pub(crate) children: Vec<Element>,
+    /// All of the text directly inside this element, with entities decoded.
+    pub(crate) text: String,
+}
+impl Element {
+    /// Reads a document, returning its root element.
+    pub(crate) fn parse(document: &str) -> Result<Element, String> {
+        let mut stack = vec![Element::default()];
+        let mut rest = document;
+        while !rest.is_empty() {
+            let offset = document.len() - rest.len();
+            let error = |message: &str| format!("{} at byte {}", message, offset);
+            let Some(start) = rest.find('<') else {
+                stack.last_mut().unwrap().text.push_str(&decode(rest));
+                break;
+            };
+            stack
+                .last_mut()
+                .unwrap()
+                .text
+                .push_str(&decode(&rest[..start]));
+            rest = &rest[start..];
+
+            if let Some(after) = rest.strip_prefix("<![CDATA[") {
+                let end = after
+                    .find("]]>")
+                    .ok_or_else(|| error("unterminated CDATA"))?;
+                stack.last_mut().unwrap().text.push_str(&after[..end]);
+                rest = &after[end + 3..];
+            } else if let Some(after) = rest.strip_prefix("<!--") {
+                let end = after
+                    .find("-->")
+                    .ok_or_else(|| error("unterminated comment"))?;
+                rest = &after[end + 3..];
+            } else if rest.starts_with("<?") || rest.starts_with("<!") {
+                let end = rest
+                    .find('>')
+                    .ok_or_else(|| error("unterminated declaration"))?;
+                rest = &rest[end + 1..];
+            } else if let Some(after) = rest.strip_prefix("</") {
+                let end = after.find('>').ok_or_else(|| error("unterminated tag"))?;
+                let name = local_name(after[..end].trim());
+                let element = stack.pop().filter(|element| element.name == name);
+                let element = element.ok_or_else(|| error("mismatched closing tag"))?;
+                stack
+                    .last_mut()
+                    .ok_or_else(|| error("unexpected closing tag"))?
+                    .children
+                    .push(element);
+                rest = &after[end + 1..];
+            } else {
+                let end = tag_end(rest).ok_or_else(|| error("unterminated tag"))?;
+                let tag = &rest[1..end];
+                let (tag, self_closing) = match tag.strip_suffix('/') {
+                    Some(tag) => (tag, true),
+                    None => (tag, false),
+                };
+                let element = open_tag(tag).ok_or_else(|| error("invalid tag"))?;
+                if self_closing {
+                    stack.last_mut().unwrap().children.push(element);
+                } else {
+                    stack.push(element);
+                }
+                rest = &rest[end + 1..];
+            }
+            if stack.is_empty() {
+                return Err(error("unexpected closing tag"));
+            }
+        }
+        if stack.len() > 1 {
+            return Err(format!("unclosed element <{}>", stack.last().unwrap().name));
+        }
+        stack
+            .pop()
+            .and_then(|document| document.children.into_iter().next())
+            .ok_or_else(|| "no root element".to_owned())
+    }
+
+    pub(crate) fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+    pub(crate) fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+    /// This element and everything inside it, depth first.
+    pub(crate) fn descendants(&self) -> Vec<&Element> {
+        let mut elements = vec![self];
+        let mut i = 0;
+        while i < elements.len() {
+            let children = &elements[i].children;
+            elements.splice(i + 1..i + 1, children.iter());
+            i += 1;
+        }
+        elements
+    }
+    pub(crate) fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+    /// The text of the named child, trimmed.
+    pub(crate) fn child_text(&self, name: &str) -> Option<&str> {
+        self.child(name).map(|child| child.text.trim())
+    }
+}
+
+/// The index of the `>` closing the tag at the start of `text`, skipping any inside quoted
+/// attribute values.
+fn tag_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), c) if c == open => quote = None,
+            (None, '>') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn open_tag(tag: &str) -> Option<Element> {
+    let tag = tag.trim();
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let name = local_name(&tag[..name_end]);
+    if name.is_empty() {
+        return None;
+    }
+    let mut attributes = vec![];
+    let mut rest = tag[name_end..].trim_start();
+    while !rest.is_empty() {
+        let (key, after) = rest.split_once('=')?;
+        let after = after.trim_start();
+        let quote = after.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let (value, after) = after[1..].split_once(quote)?;
+        attributes.push((local_name(key.trim()).to_owned(), decode(value)));
+        rest = after.trim_start();
+    }
+    Some(Element {
+        name: name.to_owned(),
+        attributes,
+        ..Default::default()
+    })
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// Decodes the predefined entities and character references.
+fn decode(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let replacement = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match replacement {
+            Some(c) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+#[test]
+fn test_xml() {
+    let document = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- An overlay -->
+<kml xmlns="http://www.opengis.net/kml/2.2" xmlns:gx="http://www.google.com/kml/ext/2.2">
+  <Document>
+    <Style id="a&amp;b"><LineStyle><color>ff0000ff</color></LineStyle></Style>
+    <name>Fish &amp; chips &#x2708;</name>
+    <description><![CDATA[<b>bold</b>]]></description>
+    <gx:Track/>
+  </Document>
+</kml>"#;
+    let kml = Element::parse(document).unwrap();
+    assert_eq!(kml.name, "kml");
+    let document = kml.child("Document").unwrap();
+    assert_eq!(document.child_text("name"), Some("Fish & chips ✈"));
+    assert_eq!(document.child_text("description"), Some("<b>bold</b>"));
+    assert_eq!(
+        document.child("Style").unwrap().attribute("id"),
+        Some("a&b")
+    );
+    assert!(document.child("Track").is_some());
+    let names = kml
+        .descendants()
+        .iter()
+        .map(|element| element.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec![
+            "kml",
+            "Document",
+            "Style",
+            "LineStyle",
+            "color",
+            "name",
+            "description",
+            "Track"
+        ]
+    );
+    assert_eq!(document.children_named("Style").count(), 1);
+
+    assert_eq!(
+        Element::parse("<a><b></a>"),
+        Err("mismatched closing tag at byte 6".to_owned())
+    );
+    assert!(Element::parse("<a>").is_err());
+}